@@ -1,9 +1,45 @@
+use std::env;
+use std::path::PathBuf;
+
+/// Environment variables that let forks rebrand the app at build time.
+/// The string ones are read directly by `src/consts.rs` through `option_env!`.
+const BRANDING_ENV_VARS: &[&str] = &[
+    "VOLUME_LOCKER_APP_NAME",
+    "VOLUME_LOCKER_APP_AUMID",
+    "VOLUME_LOCKER_APP_UID",
+    "VOLUME_LOCKER_REPO_URL",
+    "VOLUME_LOCKER_ICONS_DIR",
+];
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=build.rs");
+    for var in BRANDING_ENV_VARS {
+        println!("cargo:rerun-if-env-changed={var}");
+    }
+
+    // The icons directory must contain volume-locked.{ico,png} and volume-unlocked.ico
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?);
+    let icons_dir = env::var_os("VOLUME_LOCKER_ICONS_DIR")
+        .map(|dir| manifest_dir.join(dir))
+        .unwrap_or_else(|| manifest_dir.join("icons"));
+    println!("cargo:rerun-if-changed={}", icons_dir.display());
+    println!("cargo:rustc-env=BRANDING_ICONS_DIR={}", icons_dir.display());
+
     #[cfg(target_os = "windows")]
     {
         let mut res = winresource::WindowsResource::new();
-        res.set_icon_with_id("icons/volume-locked.ico", "volume-locked-icon");
-        res.set_icon_with_id("icons/volume-unlocked.ico", "volume-unlocked-icon");
+        res.set_icon_with_id(
+            &icons_dir.join("volume-locked.ico").to_string_lossy(),
+            "volume-locked-icon",
+        );
+        res.set_icon_with_id(
+            &icons_dir.join("volume-unlocked.ico").to_string_lossy(),
+            "volume-unlocked-icon",
+        );
+        if let Ok(app_name) = env::var("VOLUME_LOCKER_APP_NAME") {
+            res.set("FileDescription", &app_name);
+            res.set("ProductName", &app_name);
+        }
         res.set_language(0x0009); // English
         res.compile()?;
     }
@@ -0,0 +1,166 @@
+use crate::types::DeviceId;
+use anyhow::Context;
+use std::time::Duration;
+
+/// Name of the protocol registered for routes, so `volume-locker:` links open the app.
+pub const URI_SCHEME_NAME: &str = "volume-locker";
+
+/// URI scheme that may prefix a route, e.g. `volume-locker:action:check-updates`.
+const URI_SCHEME: &str = "volume-locker:";
+
+/// Something an external entry point (command line, protocol handler, toast or
+/// jump list) asks the app to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    /// `open:device/<id>`: opens the Windows settings page of a device.
+    OpenDevice(DeviceId),
+    /// `action:pause/<duration>`: pauses all enforcement for a while, e.g. `30m`.
+    Pause(Duration),
+    /// `action:check-updates`: checks for a new version right away.
+    CheckForUpdates,
+}
+
+impl std::fmt::Display for Route {
+    /// Formats the route so that [`parse_route`] reads it back unchanged.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Route::OpenDevice(device_id) => write!(f, "open:device/{device_id}"),
+            Route::Pause(duration) => write!(f, "action:pause/{}s", duration.as_secs()),
+            Route::CheckForUpdates => write!(f, "action:check-updates"),
+        }
+    }
+}
+
+/// Returns true if `arg` looks like an activation route rather than a command.
+pub fn is_route(arg: &str) -> bool {
+    let arg = arg.strip_prefix(URI_SCHEME).unwrap_or(arg);
+    arg.starts_with("open:") || arg.starts_with("action:")
+}
+
+/// Parses an activation route, with or without the `volume-locker:` scheme.
+pub fn parse_route(arg: &str) -> anyhow::Result<Route> {
+    let route = arg.strip_prefix(URI_SCHEME).unwrap_or(arg);
+    let route = route.trim_end_matches('/');
+
+    if let Some(target) = route.strip_prefix("open:") {
+        return match target.split_once('/') {
+            Some(("device", id)) if !id.is_empty() => Ok(Route::OpenDevice(id.into())),
+            _ => anyhow::bail!("unknown open route: {arg}"),
+        };
+    }
+
+    if let Some(action) = route.strip_prefix("action:") {
+        return match action.split_once('/') {
+            Some(("pause", duration)) => Ok(Route::Pause(
+                parse_duration(duration).with_context(|| format!("invalid route: {arg}"))?,
+            )),
+            None if action == "check-updates" => Ok(Route::CheckForUpdates),
+            _ => anyhow::bail!("unknown action route: {arg}"),
+        };
+    }
+
+    anyhow::bail!("unknown route: {arg}")
+}
+
+/// Parses a duration like `45s`, `30m` or `2h`.
+fn parse_duration(value: &str) -> anyhow::Result<Duration> {
+    let split = value.len().saturating_sub(1);
+    let (amount, unit) = value.split_at_checked(split).context("empty duration")?;
+    let amount: u64 = amount
+        .parse()
+        .with_context(|| format!("invalid duration: {value}"))?;
+    let seconds = match unit {
+        "s" => amount,
+        "m" => amount.saturating_mul(60),
+        "h" => amount.saturating_mul(60 * 60),
+        _ => anyhow::bail!("invalid duration unit in {value}, expected s, m or h"),
+    };
+    if seconds == 0 {
+        anyhow::bail!("duration must be greater than zero");
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_open_device() {
+        assert_eq!(
+            parse_route("open:device/{0.0.1.00000000}.{abc}").unwrap(),
+            Route::OpenDevice("{0.0.1.00000000}.{abc}".into())
+        );
+    }
+
+    #[test]
+    fn parses_pause_durations() {
+        assert_eq!(
+            parse_route("action:pause/30m").unwrap(),
+            Route::Pause(Duration::from_secs(30 * 60))
+        );
+        assert_eq!(
+            parse_route("action:pause/2h").unwrap(),
+            Route::Pause(Duration::from_secs(2 * 60 * 60))
+        );
+        assert_eq!(
+            parse_route("action:pause/45s").unwrap(),
+            Route::Pause(Duration::from_secs(45))
+        );
+    }
+
+    #[test]
+    fn parses_check_updates() {
+        assert_eq!(
+            parse_route("action:check-updates").unwrap(),
+            Route::CheckForUpdates
+        );
+    }
+
+    #[test]
+    fn formatted_routes_parse_back() {
+        for route in [
+            Route::OpenDevice("{0.0.0.00000000}.{abc}".into()),
+            Route::Pause(Duration::from_secs(90 * 60)),
+            Route::CheckForUpdates,
+        ] {
+            assert_eq!(parse_route(&route.to_string()).unwrap(), route);
+        }
+    }
+
+    #[test]
+    fn uri_scheme_matches_registered_name() {
+        assert_eq!(URI_SCHEME, format!("{URI_SCHEME_NAME}:"));
+    }
+
+    #[test]
+    fn accepts_uri_scheme_and_trailing_slash() {
+        assert_eq!(
+            parse_route("volume-locker:action:check-updates/").unwrap(),
+            Route::CheckForUpdates
+        );
+        assert!(is_route("volume-locker:open:device/x"));
+    }
+
+    #[test]
+    fn rejects_invalid_routes() {
+        for route in [
+            "open:device/",
+            "open:window/main",
+            "action:pause/",
+            "action:pause/0m",
+            "action:pause/10d",
+            "action:pause/m",
+            "action:reboot",
+            "apply",
+        ] {
+            assert!(parse_route(route).is_err(), "{route} should be rejected");
+        }
+    }
+
+    #[test]
+    fn commands_are_not_routes() {
+        assert!(!is_route("apply"));
+        assert!(!is_route("--config"));
+    }
+}
@@ -1,31 +1,147 @@
+//! Application state and the handlers that change it.
+//!
+//! Ownership rules:
+//!
+//! - [`AppState`] is owned by the event loop and only touched on its thread. It
+//!   holds COM interfaces and the tray icon, which can't leave that thread, so
+//!   it is not shared behind a lock.
+//! - Work that runs elsewhere (COM callbacks, the update check, device hooks
+//!   and any future worker) never reads or writes `AppState`. It sends a
+//!   [`UserEvent`] through an [`EventSender`], and
+//!   [`AppState::handle_user_event`] applies the change. Events are handled one
+//!   at a time, so handlers see a consistent state without locking.
+//! - Changes to [`PersistentState`] go through [`transact`] and reach other
+//!   subsystems as a [`ConfigDelta`] in `UserEvent::ConfigurationChanged`.
+//! - The few process-wide values read from any thread (event history, last
+//!   error, notification retry queue) live in their own modules behind a
+//!   `Mutex` that is held only to copy data in or out.
+
+use crate::activation::Route;
 use crate::audio::{
-    AudioBackend, AudioBackendImpl, AudioDevice, check_and_unmute_device, collect_device_names,
-    enforce_priorities, enforce_volume_lock, migrate_device_ids,
+    AudioBackend, AudioDevice, AudioServiceMonitor, AudioServiceWait, ServiceProbeOutcome,
+    ServiceWaitOutcome, VolumeLockOutcome, enforce_device_locks, enforce_priorities,
+    migrate_device_ids, sync_device_names,
+};
+use crate::audit;
+use crate::config::{ConfigDelta, PersistentState, save_state_to, transact};
+use crate::conflicts::{find_conflicting_apps, unacknowledged};
+use crate::consts::APP_NAME;
+use crate::health::{HealthSnapshot, last_error};
+use crate::hooks::{PriorityDeviceTracker, send_device_hook};
+use crate::notification::{NotificationThrottler, Notifier};
+use crate::platform::{
+    NotificationDuration, is_battery_saver_on, open_device_settings, running_process_names,
+};
+use crate::profile::instance_id;
+use crate::types::{
+    DeviceId, DeviceRole, DeviceType, TemporaryPriorities, UserEvent, VolumeChangedEvent,
+    VolumePercent, VolumeScalar,
 };
-use crate::config::{PersistentState, save_state};
-use crate::consts::{APP_NAME, APP_UID, CURRENT_VERSION};
-use crate::notification::{NotificationThrottler, log_and_notify_error};
-use crate::types::{DeviceId, TemporaryPriorities, UserEvent, VolumeChangedEvent, VolumeScalar};
 use crate::ui::{
     MenuContext, MenuEventContext, MenuEventResult, MenuIdMap, TrayMenuItems, handle_menu_event,
-    rebuild_tray_menu,
+    rebuild_tray_menu, tray_tooltip, waiting_tooltip,
 };
 use crate::update;
 use crate::update::UpdateInfo;
+use anyhow::Context;
 use auto_launch::AutoLaunch;
-use tao::event_loop::{ControlFlow, EventLoopProxy};
-use tray_icon::TrayIconBuilder;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tao::event_loop::{ControlFlow, EventLoopClosed, EventLoopProxy};
 use tray_icon::menu::{CheckMenuItem, Menu, MenuItem};
+use tray_icon::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+
+/// Hands events to the event loop. Implemented by the loop's proxy, and by a
+/// channel in tests so the handlers can run without a real event loop.
+/// How many writes in a row a device may refuse before it is reported as an error.
+const VOLUME_REFUSALS_BEFORE_ERROR: u32 = 3;
+
+pub trait EventSender: Clone + Send + Sync + 'static {
+    fn send_event(&self, event: UserEvent) -> Result<(), EventLoopClosed<UserEvent>>;
+}
+
+impl EventSender for EventLoopProxy<UserEvent> {
+    fn send_event(&self, event: UserEvent) -> Result<(), EventLoopClosed<UserEvent>> {
+        Self::send_event(self, event)
+    }
+}
+
+/// Collapses a burst of device change requests, such as the removal, arrival
+/// and default change Windows reports for one replugged device, into a single
+/// reload. Only the first request after the last reload started sends
+/// `UserEvent::DevicesChanged`; later ones are covered by the pending reload.
+#[derive(Clone, Default)]
+pub struct DevicesChangedCoalescer {
+    pending: Arc<AtomicBool>,
+}
+
+impl DevicesChangedCoalescer {
+    pub fn request(&self, proxy: &impl EventSender) {
+        if self.pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        if let Err(e) = proxy.send_event(UserEvent::DevicesChanged) {
+            log::warn!("Failed to send DevicesChanged event: {e:#}");
+            self.pending.store(false, Ordering::Release);
+        }
+    }
+
+    /// Marks the pending reload as started, so requests from now on are not
+    /// covered by it and schedule another one.
+    fn start(&self) {
+        self.pending.store(false, Ordering::Release);
+    }
+}
 
-pub struct AppState {
+/// The default output device, watched so the tooltip shows its level without
+/// rebuilding the menu.
+pub struct DefaultOutputStatus {
+    device: Box<dyn AudioDevice>,
+    name: String,
+    volume: VolumePercent,
+    muted: bool,
+}
+
+pub struct AppState<B: AudioBackend> {
     pub persistent_state: PersistentState,
+    /// Where `persistent_state` is saved.
+    pub state_path: PathBuf,
     pub menu_id_map: MenuIdMap,
     pub watched_devices: Vec<Box<dyn AudioDevice>>,
+    /// Shows toasts, shared with `notification_throttler`.
+    pub notifier: Rc<dyn Notifier>,
     pub notification_throttler: NotificationThrottler,
     pub temporary_priorities: TemporaryPriorities,
+    pub priority_device_tracker: PriorityDeviceTracker,
+    pub last_enforcement: Option<Instant>,
+    /// Since when a volume the app wrote is waiting for its change notification.
+    pub awaiting_volume_callback: Option<Instant>,
+    /// How many times in a row each device kept another level after its
+    /// locked volume was written.
+    pub volume_refusals: HashMap<DeviceId, u32>,
+    /// Input devices the user was already alerted about going live, see
+    /// `PersistentState::alert_live_mics`.
+    pub live_mic_alerts: HashSet<DeviceId>,
+    pub audio_service_monitor: AudioServiceMonitor,
+    /// Set while the audio service is not up yet, see `wait_for_audio_service`.
+    pub audio_service_wait: Option<AudioServiceWait>,
+    /// Known conflicting device switchers that were running at the last check.
+    pub conflicting_apps: Vec<&'static str>,
+    /// All enforcement is paused until this instant, if set.
+    pub paused_until: Option<Instant>,
+    pub default_output: Option<DefaultOutputStatus>,
+    /// Whether background work is reduced because battery saver is on and eco mode is enabled.
+    pub eco_active: bool,
+    /// Whether syncing device names and types was put off in eco mode and is still due.
+    pub device_records_deferred: bool,
     pub update_info: Option<UpdateInfo>,
     pub tray_icon: Option<tray_icon::TrayIcon>,
-    pub backend: AudioBackendImpl,
+    pub devices_changed: DevicesChangedCoalescer,
+    pub backend: B,
 }
 
 pub struct EventLoopRefs<'a> {
@@ -36,28 +152,117 @@ pub struct EventLoopRefs<'a> {
     pub tray_menu: &'a Menu,
     pub output_devices_heading_item: &'a MenuItem,
     pub input_devices_heading_item: &'a MenuItem,
+    pub locked_icon: &'a tray_icon::Icon,
+    pub unlocked_icon: &'a tray_icon::Icon,
 }
 
-impl AppState {
+impl<B: AudioBackend> AppState<B> {
+    pub fn new(
+        persistent_state: PersistentState,
+        state_path: PathBuf,
+        backend: B,
+        notifier: Rc<dyn Notifier>,
+    ) -> Self {
+        let notification_throttler = NotificationThrottler::with_notifier(
+            persistent_state.tunables.notification_cooldown(),
+            Rc::clone(&notifier),
+        );
+        Self {
+            persistent_state,
+            state_path,
+            menu_id_map: MenuIdMap::new(),
+            watched_devices: Vec::new(),
+            notifier,
+            notification_throttler,
+            temporary_priorities: TemporaryPriorities::default(),
+            priority_device_tracker: PriorityDeviceTracker::default(),
+            last_enforcement: None,
+            awaiting_volume_callback: None,
+            volume_refusals: HashMap::new(),
+            live_mic_alerts: HashSet::new(),
+            audio_service_monitor: AudioServiceMonitor::default(),
+            audio_service_wait: None,
+            conflicting_apps: Vec::new(),
+            paused_until: None,
+            default_output: None,
+            eco_active: false,
+            device_records_deferred: false,
+            update_info: None,
+            tray_icon: None,
+            devices_changed: DevicesChangedCoalescer::default(),
+            backend,
+        }
+    }
+
+    /// Registers for device change notifications, which reload the devices.
+    pub fn watch_device_changes(&self, proxy: &impl EventSender) -> anyhow::Result<()> {
+        let devices_changed = self.devices_changed.clone();
+        let proxy = proxy.clone();
+        self.backend
+            .register_device_change_callback(Box::new(move || devices_changed.request(&proxy)))
+            .context("failed to register device change callback")
+    }
+
+    /// Handles one event sent to the event loop.
+    pub fn handle_user_event(
+        &mut self,
+        event: UserEvent,
+        refs: &EventLoopRefs,
+        proxy: &impl EventSender,
+        control_flow: &mut ControlFlow,
+    ) {
+        match event {
+            UserEvent::Menu(event) => self.handle_menu_click(&event, refs, proxy, control_flow),
+            UserEvent::TrayIcon(TrayIconEvent::Click {
+                button,
+                button_state: MouseButtonState::Down,
+                ..
+            }) if button == MouseButton::Right || button == MouseButton::Left => {
+                self.handle_tray_click(refs);
+            }
+            UserEvent::TrayIcon(_) => {}
+            UserEvent::VolumeChanged(event) => self.handle_volume_changed(event),
+            UserEvent::Activate(route) => self.handle_activation(route),
+            UserEvent::ApplyConfiguration(state) => self.apply_configuration(*state, proxy),
+            UserEvent::DefaultOutputVolumeChanged(volume) => {
+                self.handle_default_output_volume_changed(volume);
+            }
+            UserEvent::DevicesChanged => {
+                self.handle_devices_changed(proxy, refs.locked_icon, refs.unlocked_icon);
+            }
+            UserEvent::ConfigurationChanged(delta) => {
+                self.handle_configuration_changed(&delta, proxy);
+            }
+        }
+    }
+
     pub fn handle_volume_changed(&mut self, event: VolumeChangedEvent) {
         let VolumeChangedEvent {
             device_id,
             new_volume,
         } = event;
 
-        let Some(device_settings) = self.persistent_state.device_settings(&device_id) else {
+        if new_volume.is_some() {
+            // Volume callbacks are firing
+            self.awaiting_volume_callback = None;
+        }
+
+        if self.is_paused() {
             return;
-        };
+        }
 
-        let device_name = device_settings.name.clone();
-        let device_type = device_settings.device_type;
-        let volume_lock = device_settings.volume_lock;
-        let unmute_lock = device_settings.unmute_lock;
+        let Some(device_settings) = self.persistent_state.enforced_device_settings(&device_id)
+        else {
+            return;
+        };
 
         let device = match self.backend.device_by_id(&device_id) {
             Ok(d) => d,
             Err(e) => {
-                log::error!("Failed to get device by id for {device_name}: {e}");
+                log::error!(
+                    "Failed to get device by id for {}: {e}",
+                    device_settings.display_name()
+                );
                 return;
             }
         };
@@ -73,43 +278,70 @@ impl AppState {
             },
         };
 
-        if volume_lock.is_locked {
-            enforce_volume_lock(
-                &device_id,
-                device.as_ref(),
-                &device_name,
-                volume_lock,
-                new_volume,
-                &mut self.notification_throttler,
-            );
-        }
+        let now = Instant::now();
+        self.last_enforcement = Some(now);
 
-        if unmute_lock.is_locked {
-            check_and_unmute_device(
-                device.as_ref(),
-                device_type,
-                unmute_lock.notify,
-                &mut self.notification_throttler,
+        let alert_if_unmuted = self.persistent_state.alert_live_mics
+            && device_settings.device_type == DeviceType::Input
+            && device_settings.unmute_lock.is_locked
+            && !self.live_mic_alerts.contains(&device_id)
+            && device.is_muted().unwrap_or(false);
+        let outcome = enforce_device_locks(
+            device.as_ref(),
+            &device_settings,
+            new_volume,
+            &mut self.notification_throttler,
+        );
+        if alert_if_unmuted && device.is_muted().is_ok_and(|muted| !muted) {
+            self.live_mic_alerts.insert(device_id.clone());
+            self.notifier.notify_or_log(
+                "Microphone Is Live",
+                &format!(
+                    "{} was unmuted by its lock. Check Mute all inputs in the menu to mute every microphone.",
+                    device_settings.display_name()
+                ),
+                NotificationDuration::Long,
             );
         }
+        if outcome == VolumeLockOutcome::Refused {
+            let refusals = self.volume_refusals.entry(device_id).or_default();
+            *refusals += 1;
+            // Logged as an error once, so the health status reports it
+            if *refusals == VOLUME_REFUSALS_BEFORE_ERROR {
+                log::error!(
+                    "{} keeps refusing its locked volume of {}%",
+                    device_settings.display_name(),
+                    device_settings.volume_lock.target_percent
+                );
+            }
+            return;
+        }
+        self.volume_refusals.remove(&device_id);
+        if outcome == VolumeLockOutcome::Restored {
+            self.awaiting_volume_callback.get_or_insert(now);
+        }
     }
 
-    pub fn migrate_device_ids_if_needed(&mut self) {
-        let migrations_occurred = migrate_device_ids(&self.backend, &mut self.persistent_state);
-
-        if migrations_occurred {
-            if let Err(e) = save_state(&self.persistent_state) {
-                log_and_notify_error(
-                    "Failed to Save State",
-                    &format!("Failed to save state after device migration: {e:#}"),
-                );
-            } else {
-                log::info!("Saved state after device migration");
+    /// Brings device records up to date with the system: migrates devices
+    /// whose ID changed and syncs names and types. Saves only if something
+    /// changed. Syncing enumerates every device and is not needed to enforce
+    /// anything, so in eco mode it is put off until eco mode ends.
+    pub fn refresh_device_records(&mut self, proxy: &impl EventSender) {
+        let sync_names = !self.eco_active;
+        self.device_records_deferred = !sync_names;
+        let ((), delta) = transact(&mut self.persistent_state, |persistent_state| {
+            migrate_device_ids(&self.backend, persistent_state);
+            if sync_names {
+                sync_device_names(&self.backend, persistent_state);
             }
+        });
+        if !delta.is_empty() {
+            log::info!("Device records updated");
+            send_configuration_changed(proxy, delta);
         }
     }
 
-    pub fn rebuild_watched_devices(&mut self, proxy: &EventLoopProxy<UserEvent>) -> bool {
+    pub fn rebuild_watched_devices(&mut self, proxy: &impl EventSender) -> bool {
         self.watched_devices.clear();
 
         let locked_device_ids = self.persistent_state.locked_device_ids();
@@ -126,9 +358,9 @@ impl AppState {
     fn try_watch_device(
         &mut self,
         device_id: &DeviceId,
-        proxy: &EventLoopProxy<UserEvent>,
+        proxy: &impl EventSender,
     ) -> Option<Box<dyn AudioDevice>> {
-        let device_settings = self.persistent_state.device_settings(device_id)?;
+        let device_settings = self.persistent_state.enforced_device_settings(device_id)?;
         let device_name = &device_settings.name;
 
         let device = match self.backend.device_by_id(device_id) {
@@ -156,21 +388,13 @@ impl AppState {
             return None;
         }
 
-        if device_settings.unmute_lock.is_locked {
-            check_and_unmute_device(
-                device.as_ref(),
-                device_settings.device_type,
-                device_settings.unmute_lock.notify,
-                &mut self.notification_throttler,
-            );
-        }
-
         log::info!(
             "Watching {device_name} (Locked: {}, Unmute: {})",
             device_settings.volume_lock.is_locked,
             device_settings.unmute_lock.is_locked
         );
 
+        // Enforces the locks right away, through the same path as a volume change
         if let Err(e) = proxy.send_event(UserEvent::VolumeChanged(VolumeChangedEvent {
             device_id: device_id.clone(),
             new_volume: None,
@@ -201,78 +425,328 @@ impl AppState {
 
     pub fn handle_devices_changed(
         &mut self,
-        proxy: &EventLoopProxy<UserEvent>,
+        proxy: &impl EventSender,
         locked_icon: &tray_icon::Icon,
         unlocked_icon: &tray_icon::Icon,
     ) {
         log::info!("Reloading list of watched devices...");
+        // Changes reported from now on may be missed by this reload
+        self.devices_changed.start();
+        if self.audio_service_wait.is_some() {
+            log::info!("Not loading devices until the audio service is available");
+            return;
+        }
+
+        self.refresh_device_records(proxy);
+
+        self.notify_priority_device_changes();
+
+        if self.is_paused() {
+            log::info!("Not enforcing priorities while paused");
+        } else if self.persistent_state.yield_to_conflicting_apps
+            && !self.conflicting_apps.is_empty()
+        {
+            log::info!(
+                "Not enforcing priorities while {} is running",
+                self.conflicting_apps.join(", ")
+            );
+        } else {
+            enforce_priorities(
+                &self.backend,
+                &self.persistent_state,
+                &mut self.notification_throttler,
+                &self.temporary_priorities,
+            );
+        }
 
-        self.migrate_device_ids_if_needed();
-        for (device_id, new_name, device_type) in collect_device_names(&self.backend) {
-            if let Some(settings) = self.persistent_state.device_settings_mut(&device_id) {
-                settings.name = new_name;
-                settings.device_type = device_type;
+        // Devices being replaced may never report the writes made to them
+        self.awaiting_volume_callback = None;
+        let any_device_locked = self.rebuild_watched_devices(proxy);
+        self.last_enforcement = Some(Instant::now());
+        self.watch_default_output(proxy);
+
+        self.update_tray_icon(any_device_locked, locked_icon, unlocked_icon);
+    }
+
+    /// Watches the current default output device and refreshes the tooltip.
+    fn watch_default_output(&mut self, proxy: &impl EventSender) {
+        self.default_output = None;
+        let status = self
+            .backend
+            .default_device(DeviceType::Output, DeviceRole::Console)
+            .and_then(|device| {
+                let cb_proxy = proxy.clone();
+                device.watch_volume(Box::new(move |volume| {
+                    let _ = cb_proxy.send_event(UserEvent::DefaultOutputVolumeChanged(volume));
+                }))?;
+                Ok(DefaultOutputStatus {
+                    name: device.name(),
+                    volume: device.volume()?.to_percent(),
+                    muted: device.is_muted()?,
+                    device,
+                })
+            });
+        match status {
+            Ok(status) => self.default_output = Some(status),
+            Err(e) => log::warn!("Not showing the default output volume: {e:#}"),
+        }
+        self.update_tooltip();
+    }
+
+    /// Updates the cached default output level from its volume callback.
+    pub fn handle_default_output_volume_changed(&mut self, volume: Option<VolumeScalar>) {
+        let Some(status) = &mut self.default_output else {
+            return;
+        };
+        match volume.map_or_else(|| status.device.volume(), Ok) {
+            Ok(volume) => status.volume = volume.to_percent(),
+            Err(e) => log::warn!("Failed to get default output volume: {e:#}"),
+        }
+        match status.device.is_muted() {
+            Ok(muted) => status.muted = muted,
+            Err(e) => log::warn!("Failed to get default output mute state: {e:#}"),
+        }
+        self.update_tooltip();
+    }
+
+    fn tooltip(&self) -> String {
+        if self.audio_service_wait.is_some() {
+            return waiting_tooltip();
+        }
+        let unmuted_inputs: Vec<&str> = if self.persistent_state.mute_inputs {
+            Vec::new()
+        } else {
+            self.watched_devices
+                .iter()
+                .filter_map(|device| self.persistent_state.device_settings(device.id()))
+                .filter(|settings| {
+                    settings.device_type == DeviceType::Input && settings.unmute_lock.is_locked
+                })
+                .map(|settings| settings.name.as_str())
+                .collect()
+        };
+        tray_tooltip(
+            self.default_output
+                .as_ref()
+                .map(|status| (status.name.as_str(), status.volume, status.muted)),
+            &unmuted_inputs,
+        )
+    }
+
+    fn update_tooltip(&self) {
+        let Some(tray_icon) = &self.tray_icon else {
+            return;
+        };
+        if let Err(e) = tray_icon.set_tooltip(Some(self.tooltip())) {
+            log::warn!("Failed to update tray tooltip: {e:#}");
+        }
+    }
+
+    /// Starts connecting to the audio service, which may not be up yet when the
+    /// app is started at login. Until it is, the tray says so, devices are not
+    /// loaded, and `check_audio_service` retries with a growing delay.
+    pub fn wait_for_audio_service(&mut self, proxy: &impl EventSender) {
+        self.audio_service_wait = Some(AudioServiceWait::new(
+            Duration::from_secs(1),
+            Duration::from_secs(8),
+        ));
+        self.connect_audio_service(proxy);
+    }
+
+    /// When the next attempt to connect to the audio service is due, while
+    /// still waiting for it.
+    pub fn next_audio_service_attempt(&self) -> Option<Instant> {
+        self.audio_service_wait
+            .as_ref()
+            .map(AudioServiceWait::next_attempt)
+    }
+
+    fn connect_audio_service(&mut self, proxy: &impl EventSender) {
+        let Some(wait) = &mut self.audio_service_wait else {
+            return;
+        };
+        match wait.attempt(&self.backend) {
+            ServiceWaitOutcome::Waiting => {}
+            ServiceWaitOutcome::Unavailable(e) => {
+                self.notifier.log_and_notify_error(
+                    "Audio Service Unavailable",
+                    &format!(
+                        "{APP_NAME} could not connect to the Windows audio service and keeps trying. Volume locks and device priorities are enforced once it is available: {e:#}"
+                    ),
+                );
+            }
+            ServiceWaitOutcome::Connected => {
+                log::info!("Connected to the audio service");
+                self.audio_service_wait = None;
+                self.update_tooltip();
+                self.devices_changed.request(proxy);
             }
         }
+    }
 
-        enforce_priorities(
-            &self.backend,
-            &self.persistent_state,
-            &mut self.notification_throttler,
-            &self.temporary_priorities,
+    /// Probes the audio service and, once it is back after a restart, rebuilds
+    /// every registration against the fresh connection. While still waiting
+    /// for the service to come up, tries to connect instead.
+    pub fn check_audio_service(&mut self, proxy: &impl EventSender) {
+        if self.audio_service_wait.is_some() {
+            self.connect_audio_service(proxy);
+            return;
+        }
+        let endpoint = self
+            .watched_devices
+            .first()
+            .or_else(|| self.default_output.as_ref().map(|status| &status.device));
+        match self
+            .audio_service_monitor
+            .probe(&self.backend, endpoint.map(AsRef::as_ref))
+        {
+            ServiceProbeOutcome::Healthy | ServiceProbeOutcome::Degraded => {}
+            ServiceProbeOutcome::ReconnectFailed => {
+                log::warn!("Audio service is still unavailable, will retry");
+            }
+            ServiceProbeOutcome::Reconnected => {
+                log::info!("Reconnected to the audio service after it restarted");
+                self.devices_changed.request(proxy);
+                self.notifier.notify_or_log(
+                    "Audio Service Recovered",
+                    "The Windows audio service restarted. Volume locks and device priorities are being enforced again.",
+                    NotificationDuration::Short,
+                );
+            }
+        }
+    }
+
+    /// Looks for known conflicting device switchers, warns once about each new
+    /// one, and re-evaluates priorities when yielding and the set changed.
+    pub fn refresh_conflicting_apps(&mut self, proxy: &impl EventSender) {
+        let running = match running_process_names() {
+            Ok(names) => names,
+            Err(e) => {
+                log::warn!("Failed to list running processes: {e:#}");
+                return;
+            }
+        };
+        let conflicts = find_conflicting_apps(&running);
+        if conflicts == self.conflicting_apps {
+            return;
+        }
+        log::info!("Conflicting apps running: {conflicts:?}");
+
+        let new_conflicts = unacknowledged(
+            &conflicts,
+            &self.persistent_state.acknowledged_conflicting_apps,
         );
+        if !new_conflicts.is_empty() {
+            self.notifier.notify_or_log(
+                "Conflicting App Detected",
+                &format!(
+                    "{} may also switch audio devices, which can fight with your priority lists. You can pause priorities while it runs from Preferences.",
+                    new_conflicts.join(", ")
+                ),
+                NotificationDuration::Long,
+            );
+            let ((), delta) = transact(&mut self.persistent_state, |persistent_state| {
+                persistent_state
+                    .acknowledged_conflicting_apps
+                    .extend(new_conflicts.iter().map(ToString::to_string));
+            });
+            send_configuration_changed(proxy, delta);
+        }
 
-        let any_device_locked = self.rebuild_watched_devices(proxy);
+        self.conflicting_apps = conflicts;
+        if self.persistent_state.yield_to_conflicting_apps {
+            self.devices_changed.request(proxy);
+        }
+    }
 
-        self.update_tray_icon(any_device_locked, locked_icon, unlocked_icon);
+    fn notify_priority_device_changes(&mut self) {
+        let events = self
+            .priority_device_tracker
+            .update(&self.backend, &self.persistent_state);
+        for event in events {
+            log::info!(
+                "Priority {} device {} was {:?}",
+                event.device_type,
+                event.device_name,
+                event.event
+            );
+            if let Some(url) = &self.persistent_state.device_hook_url {
+                send_device_hook(url, &event);
+            }
+        }
     }
 
-    pub fn handle_configuration_changed(&mut self, proxy: &EventLoopProxy<UserEvent>) {
-        if let Err(e) = save_state(&self.persistent_state) {
-            log_and_notify_error(
+    /// Saves the change and reloads what it affects. Returns `true` if there
+    /// was a change and it was saved.
+    pub fn handle_configuration_changed(
+        &mut self,
+        delta: &ConfigDelta,
+        proxy: &impl EventSender,
+    ) -> bool {
+        if delta.is_empty() {
+            return false;
+        }
+        if let Err(e) = save_state_to(&self.state_path, &self.persistent_state) {
+            self.notifier.log_and_notify_error(
                 "Failed to Save State",
                 &format!("Failed to save state: {e:#}"),
             );
-            return;
+            return false;
         }
         log::info!(
             "Configuration saved ({} devices tracked)",
             self.persistent_state.device_count()
         );
-        if let Err(e) = proxy.send_event(UserEvent::DevicesChanged) {
-            log::warn!("Failed to send DevicesChanged event: {e:#}");
+        if delta.affects_enforcement() {
+            self.devices_changed.request(proxy);
         }
+        true
+    }
+
+    /// Replaces the whole configuration, e.g. with one installed by `apply`.
+    pub fn apply_configuration(&mut self, state: PersistentState, proxy: &impl EventSender) {
+        let ((), delta) = transact(&mut self.persistent_state, |persistent_state| {
+            *persistent_state = state;
+        });
+        log::info!(
+            "Applying a configuration from a later launch ({} devices tracked)",
+            self.persistent_state.device_count()
+        );
+        send_configuration_changed(proxy, delta);
     }
 
     pub fn handle_menu_click(
         &mut self,
         event: &tray_icon::menu::MenuEvent,
         refs: &EventLoopRefs,
-        proxy: &EventLoopProxy<UserEvent>,
+        proxy: &impl EventSender,
         control_flow: &mut ControlFlow,
     ) {
         if event.id == refs.quit_item.id() {
             self.tray_icon.take();
             *control_flow = ControlFlow::Exit;
         } else if let Some(menu_info) = self.menu_id_map.get(&event.id) {
-            let mut ctx = MenuEventContext {
-                tray_menu: refs.tray_menu,
-                persistent_state: &mut self.persistent_state,
-                backend: &self.backend,
-                temporary_priorities: &mut self.temporary_priorities,
-                update_info: &self.update_info,
-            };
-            let result = handle_menu_event(event, menu_info, &mut ctx);
+            let (result, delta) = transact(&mut self.persistent_state, |persistent_state| {
+                let mut ctx = MenuEventContext {
+                    tray_menu: refs.tray_menu,
+                    persistent_state,
+                    backend: &self.backend,
+                    notifier: self.notifier.as_ref(),
+                    temporary_priorities: &mut self.temporary_priorities,
+                    update_info: &self.update_info,
+                };
+                handle_menu_event(event, menu_info, &mut ctx)
+            });
 
             match result {
                 MenuEventResult::DevicesChanged => {
-                    if let Err(e) = proxy.send_event(UserEvent::DevicesChanged) {
-                        log::warn!("Failed to send DevicesChanged event: {e:#}");
-                    }
+                    self.devices_changed.request(proxy);
                 }
-                MenuEventResult::SaveConfig => {
-                    if let Err(e) = proxy.send_event(UserEvent::ConfigurationChanged) {
-                        log::warn!("Failed to send ConfigurationChanged event: {e:#}");
+                MenuEventResult::SaveConfig => send_configuration_changed(proxy, delta),
+                MenuEventResult::DeviceForgotten(record) => {
+                    // The history only lists devices whose removal was saved
+                    if self.handle_configuration_changed(&delta, proxy) {
+                        audit::record(&record);
                     }
                 }
                 MenuEventResult::UpdatePerform(info) => match update::install_update(&info) {
@@ -281,12 +755,13 @@ impl AppState {
                         *control_flow = ControlFlow::Exit;
                     }
                     Err(e) => {
-                        log_and_notify_error("Update Failed", &format!("Update failed: {e:#}"));
+                        self.notifier.log_and_notify_error(
+                            "Update Failed",
+                            &format!("Update failed: {e:#}"),
+                        );
                     }
                 },
-                MenuEventResult::UpdateCheck => {
-                    self.update_info = update::check_for_update(true).unwrap_or(None);
-                }
+                MenuEventResult::UpdateCheck => self.check_for_updates(true),
                 MenuEventResult::ToggleAutoLaunch(checked) => {
                     let result = if checked {
                         refs.auto_launch.enable()
@@ -294,29 +769,120 @@ impl AppState {
                         refs.auto_launch.disable()
                     };
                     if let Err(e) = result {
-                        log_and_notify_error(
+                        self.notifier.log_and_notify_error(
                             "Failed to Toggle Auto-Launch",
                             &format!("Failed to toggle auto-launch: {e:#}"),
                         );
                     }
                 }
+                MenuEventResult::RestoreAll => self.restore_all_locks(proxy),
                 MenuEventResult::NoChange => {}
             }
         }
     }
 
+    /// Ends any pause and reloads the watched devices, which re-applies every
+    /// lock and priority list without waiting for the next device event.
+    fn restore_all_locks(&mut self, proxy: &impl EventSender) {
+        if self.paused_until.take().is_some() {
+            log::info!("Ending pause to restore all locks");
+        }
+        log::info!("Restoring all locks on request");
+        self.devices_changed.request(proxy);
+    }
+
+    /// Enters or leaves eco mode following battery saver. While in it, the
+    /// periodic tick runs less often, short notifications are batched and
+    /// syncing device records is put off.
+    pub fn refresh_power_mode(&mut self, proxy: &impl EventSender) {
+        let eco_active = self.persistent_state.eco_mode && is_battery_saver_on();
+        self.set_eco_active(eco_active, proxy);
+    }
+
+    fn set_eco_active(&mut self, eco_active: bool, proxy: &impl EventSender) {
+        if eco_active == self.eco_active {
+            if eco_active {
+                self.notification_throttler.flush_batch();
+            }
+            return;
+        }
+        log::info!(
+            "{} eco mode",
+            if eco_active { "Entering" } else { "Leaving" }
+        );
+        self.eco_active = eco_active;
+        self.notification_throttler.set_batching(eco_active);
+        if !eco_active && self.device_records_deferred {
+            self.refresh_device_records(proxy);
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused_until
+            .is_some_and(|paused_until| Instant::now() < paused_until)
+    }
+
+    /// Resumes enforcement once a pause requested through an activation route is over.
+    pub fn resume_if_pause_expired(&mut self, proxy: &impl EventSender) {
+        if self.paused_until.is_none() || self.is_paused() {
+            return;
+        }
+        self.paused_until = None;
+        log::info!("Pause is over, resuming enforcement");
+        self.notifier.notify_or_log(
+            "Enforcement Resumed",
+            "Volume locks and priorities are enforced again.",
+            NotificationDuration::Short,
+        );
+        self.devices_changed.request(proxy);
+    }
+
+    /// Checks for updates, notifying about every outcome if `manual_request`.
+    /// A failed check keeps the update found by the last successful one.
+    fn check_for_updates(&mut self, manual_request: bool) {
+        match update::check_for_update(manual_request, self.notifier.as_ref()) {
+            Ok(info) => self.update_info = info,
+            // check_for_update already logged the error, and reported it if manual
+            Err(e) => log::debug!("Keeping previous update info after failed check: {e:#}"),
+        }
+    }
+
+    /// Single entry point for activation routes, whatever launched them.
+    pub fn handle_activation(&mut self, route: Route) {
+        log::info!("Handling activation route: {route:?}");
+        match route {
+            Route::OpenDevice(device_id) => {
+                if let Err(e) = open_device_settings(&device_id) {
+                    self.notifier.log_and_notify_error(
+                        "Failed to Open Device Settings",
+                        &format!("Failed to open settings for {device_id}: {e:#}"),
+                    );
+                }
+            }
+            Route::Pause(duration) => {
+                self.paused_until = Some(Instant::now() + duration);
+                let minutes = duration.as_secs().div_ceil(60);
+                self.notifier.respond(
+                    "Enforcement Paused",
+                    &format!("Volume locks and priorities are paused for {minutes} min."),
+                );
+            }
+            Route::CheckForUpdates => self.check_for_updates(true),
+        }
+    }
+
     pub fn handle_init(
         &mut self,
         tray_menu: &Menu,
         unlocked_icon: &tray_icon::Icon,
-        proxy: &EventLoopProxy<UserEvent>,
+        proxy: &impl EventSender,
     ) {
-        let tooltip = format!("{APP_NAME} v{CURRENT_VERSION}");
+        let tooltip = self.tooltip();
         match TrayIconBuilder::new()
             .with_menu(Box::new(tray_menu.clone()))
             .with_tooltip(&tooltip)
             .with_icon(unlocked_icon.clone())
-            .with_id(APP_UID)
+            .with_id(instance_id())
             .with_menu_on_left_click(false)
             .with_menu_on_right_click(false)
             .build()
@@ -326,15 +892,20 @@ impl AppState {
         }
 
         if self.persistent_state.check_updates_on_launch {
-            self.update_info = update::check_for_update(false).unwrap_or(None);
+            self.check_for_updates(false);
         }
 
-        if let Err(e) = proxy.send_event(UserEvent::DevicesChanged) {
-            log::warn!("Failed to send initial DevicesChanged event: {e:#}");
-        }
+        self.devices_changed.request(proxy);
     }
 
     pub fn handle_tray_click(&mut self, refs: &EventLoopRefs) {
+        let health = HealthSnapshot {
+            watched_devices: self.watched_devices.len(),
+            locked_devices: self.persistent_state.locked_device_ids().len(),
+            last_enforcement: self.last_enforcement,
+            last_error: last_error(),
+            awaiting_volume_callback: self.awaiting_volume_callback,
+        };
         let ctx = MenuContext {
             backend: &self.backend,
             persistent_state: &self.persistent_state,
@@ -344,6 +915,7 @@ impl AppState {
                 false
             }),
             update_info: &self.update_info,
+            health: &health,
         };
         match rebuild_tray_menu(
             refs.tray_menu,
@@ -363,7 +935,7 @@ impl AppState {
                 }
             }
             Err(e) => {
-                log_and_notify_error(
+                self.notifier.log_and_notify_error(
                     "Failed to Rebuild Tray Menu",
                     &format!("Failed to rebuild tray menu: {e:#}"),
                 );
@@ -372,9 +944,563 @@ impl AppState {
     }
 }
 
+/// Hands the changes of a [`transact`] call to the event loop, which saves them
+/// and reloads whatever they affect.
+fn send_configuration_changed(proxy: &impl EventSender, delta: ConfigDelta) {
+    if let Err(e) = proxy.send_event(UserEvent::ConfigurationChanged(delta)) {
+        log::warn!("Failed to send ConfigurationChanged event: {e:#}");
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::types::{DeviceType, TemporaryPriorities};
+    use super::{
+        AppState, DevicesChangedCoalescer, EventLoopRefs, EventSender, VOLUME_REFUSALS_BEFORE_ERROR,
+    };
+    use crate::audio::tests::{MockAudioBackend, MockDevice, make_device_settings};
+    use crate::audio::{AudioBackend, AudioServiceWait};
+    use crate::config::{PersistentState, read_state_file};
+    use crate::notification::tests::RecordingNotifier;
+    use crate::types::{
+        DeviceId, DeviceRole, DeviceType, TemporaryPriorities, UserEvent, VolumeChangedEvent,
+    };
+    use crate::ui::{DeviceAction, MenuAction};
+    use std::rc::Rc;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use tao::event_loop::{ControlFlow, EventLoopClosed};
+    use tray_icon::menu::{CheckMenuItem, Menu, MenuItem};
+
+    impl EventSender for mpsc::Sender<UserEvent> {
+        fn send_event(&self, event: UserEvent) -> Result<(), EventLoopClosed<UserEvent>> {
+            self.send(event).map_err(|e| EventLoopClosed(e.0))
+        }
+    }
+
+    /// The tray items `main` keeps next to the event loop.
+    struct TestTray {
+        auto_launch: auto_launch::AutoLaunch,
+        auto_launch_check_item: CheckMenuItem,
+        check_updates_on_launch_item: CheckMenuItem,
+        quit_item: MenuItem,
+        tray_menu: Menu,
+        output_devices_heading_item: MenuItem,
+        input_devices_heading_item: MenuItem,
+        icon: tray_icon::Icon,
+    }
+
+    impl TestTray {
+        fn new() -> Self {
+            Self {
+                auto_launch: auto_launch::AutoLaunchBuilder::new()
+                    .set_app_name("Volume Locker Test")
+                    .set_app_path(r"C:\volume-locker-test.exe")
+                    .build()
+                    .unwrap(),
+                auto_launch_check_item: CheckMenuItem::new("Auto-launch", true, false, None),
+                check_updates_on_launch_item: CheckMenuItem::new(
+                    "Check updates",
+                    true,
+                    false,
+                    None,
+                ),
+                quit_item: MenuItem::new("Quit", true, None),
+                tray_menu: Menu::new(),
+                output_devices_heading_item: MenuItem::new("Output devices", false, None),
+                input_devices_heading_item: MenuItem::new("Input devices", false, None),
+                icon: tray_icon::Icon::from_rgba(vec![0; 4], 1, 1).unwrap(),
+            }
+        }
+
+        fn refs(&self) -> EventLoopRefs<'_> {
+            EventLoopRefs {
+                auto_launch: &self.auto_launch,
+                auto_launch_check_item: &self.auto_launch_check_item,
+                check_updates_on_launch_item: &self.check_updates_on_launch_item,
+                quit_item: &self.quit_item,
+                tray_menu: &self.tray_menu,
+                output_devices_heading_item: &self.output_devices_heading_item,
+                input_devices_heading_item: &self.input_devices_heading_item,
+                locked_icon: &self.icon,
+                unlocked_icon: &self.icon,
+            }
+        }
+    }
+
+    #[test]
+    fn device_change_bursts_are_coalesced_into_one_reload() {
+        let (proxy, events) = mpsc::channel();
+        let coalescer = DevicesChangedCoalescer::default();
+
+        for _ in 0..3 {
+            coalescer.request(&proxy);
+        }
+        assert_eq!(events.try_iter().count(), 1);
+
+        // A change reported once the reload started needs another one
+        coalescer.start();
+        coalescer.request(&proxy);
+        coalescer.request(&proxy);
+        assert_eq!(events.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn devices_are_loaded_once_the_audio_service_is_up() {
+        let device = MockDevice::new("dev1", "Speaker", true);
+        let mut state = PersistentState::default();
+        let mut settings = make_device_settings("Speaker", DeviceType::Output);
+        settings.volume_lock.is_locked = true;
+        state.devices.insert(device.id.clone(), settings);
+        let backend = MockAudioBackend::new(vec![device]);
+        *backend.reconnect_fails.borrow_mut() = true;
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = AppState::new(
+            state,
+            dir.path().join("state.json"),
+            backend,
+            Rc::new(RecordingNotifier::default()),
+        );
+        let (proxy, events) = mpsc::channel();
+        let tray = TestTray::new();
+
+        app.wait_for_audio_service(&proxy);
+        assert!(app.next_audio_service_attempt().is_some());
+        app.handle_devices_changed(&proxy, &tray.icon, &tray.icon);
+        assert!(app.watched_devices.is_empty());
+        assert_eq!(events.try_iter().count(), 0);
+
+        // Retried from the event loop tick once the attempt is due
+        *app.backend.reconnect_fails.borrow_mut() = false;
+        app.audio_service_wait = Some(AudioServiceWait::new(Duration::ZERO, Duration::ZERO));
+        app.check_audio_service(&proxy);
+        assert!(app.next_audio_service_attempt().is_none());
+        assert!(matches!(events.try_recv(), Ok(UserEvent::DevicesChanged)));
+        app.handle_devices_changed(&proxy, &tray.icon, &tray.icon);
+        assert_eq!(app.watched_devices.len(), 1);
+    }
+
+    #[test]
+    fn refused_volume_write_does_not_wait_for_a_callback() {
+        let mut device = MockDevice::new("dev1", "Speaker", true);
+        device.ignores_volume_changes = true;
+        let mut state = PersistentState::default();
+        let mut settings = make_device_settings("Speaker", DeviceType::Output);
+        settings.volume_lock.is_locked = true;
+        state.devices.insert(device.id.clone(), settings);
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = AppState::new(
+            state,
+            dir.path().join("state.json"),
+            MockAudioBackend::new(vec![device]),
+            Rc::new(RecordingNotifier::default()),
+        );
+
+        for _ in 0..VOLUME_REFUSALS_BEFORE_ERROR {
+            app.handle_volume_changed(VolumeChangedEvent {
+                device_id: DeviceId::from("dev1"),
+                new_volume: None,
+            });
+        }
+
+        assert!(app.awaiting_volume_callback.is_none());
+        assert_eq!(
+            app.volume_refusals.get("dev1"),
+            Some(&VOLUME_REFUSALS_BEFORE_ERROR)
+        );
+    }
+
+    #[test]
+    fn eco_mode_defers_syncing_device_records() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("dev1", "Speakers", true)]);
+        let mut state = PersistentState::default();
+        // Recorded with the wrong type, which only a sync fixes
+        state.devices.insert(
+            DeviceId::from("dev1"),
+            make_device_settings("Speakers", DeviceType::Input),
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = AppState::new(
+            state,
+            dir.path().join("state.json"),
+            backend,
+            Rc::new(RecordingNotifier::default()),
+        );
+        let (proxy, _events) = mpsc::channel();
+
+        app.set_eco_active(true, &proxy);
+        app.refresh_device_records(&proxy);
+        let device_type = |app: &AppState<MockAudioBackend>| {
+            app.persistent_state
+                .device_settings(&DeviceId::from("dev1"))
+                .unwrap()
+                .device_type
+        };
+        assert_eq!(device_type(&app), DeviceType::Input);
+
+        app.set_eco_active(false, &proxy);
+        assert_eq!(device_type(&app), DeviceType::Output);
+        assert!(!app.device_records_deferred);
+    }
+
+    #[test]
+    fn applied_configuration_is_saved_through_a_config_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.json");
+        let mut app = AppState::new(
+            PersistentState::default(),
+            state_path.clone(),
+            MockAudioBackend::new(vec![MockDevice::new("dev1", "Speakers", true)]),
+            Rc::new(RecordingNotifier::default()),
+        );
+        let (proxy, events) = mpsc::channel();
+        let mut applied = PersistentState::default();
+        applied.devices.insert(
+            DeviceId::from("dev1"),
+            make_device_settings("Speakers", DeviceType::Output),
+        );
+
+        app.apply_configuration(applied.clone(), &proxy);
+        assert_eq!(app.persistent_state, applied);
+        let Ok(UserEvent::ConfigurationChanged(delta)) = events.try_recv() else {
+            panic!("expected a configuration change");
+        };
+        assert_eq!(delta.devices, [DeviceId::from("dev1")]);
+
+        assert!(app.handle_configuration_changed(&delta, &proxy));
+        assert_eq!(read_state_file(&state_path).unwrap(), applied);
+    }
+
+    /// Worker threads never touch `AppState`: they hand changes to the event
+    /// loop, which applies them one at a time. However the workers race, every
+    /// change is applied, each worker's in the order it sent them, and what is
+    /// saved matches the last change applied.
+    #[test]
+    fn concurrent_changes_are_applied_in_turn_by_the_event_loop() {
+        const WORKERS: usize = 4;
+        const CHANGES_PER_WORKER: usize = 25;
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.json");
+        let mut app = AppState::new(
+            PersistentState::default(),
+            state_path.clone(),
+            MockAudioBackend::new(vec![MockDevice::new("dev1", "Speakers", true)]),
+            Rc::new(RecordingNotifier::default()),
+        );
+        let (proxy, events) = mpsc::channel();
+        let tray = TestTray::new();
+        let refs = tray.refs();
+        let mut control_flow = ControlFlow::Wait;
+
+        let workers: Vec<_> = (0..WORKERS)
+            .map(|worker| {
+                let proxy = proxy.clone();
+                std::thread::spawn(move || {
+                    for change in 0..CHANGES_PER_WORKER {
+                        let mut state = PersistentState::default();
+                        state.devices.insert(
+                            DeviceId::from(format!("worker{worker}")),
+                            make_device_settings(&change.to_string(), DeviceType::Output),
+                        );
+                        proxy
+                            .send_event(UserEvent::ApplyConfiguration(Box::new(state)))
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        let mut applied = vec![Vec::new(); WORKERS];
+        let mut last_applied = None;
+        let mut remaining = WORKERS * CHANGES_PER_WORKER;
+        while remaining > 0 {
+            let event = events
+                .recv_timeout(std::time::Duration::from_secs(10))
+                .expect("workers stopped sending");
+            if let UserEvent::ApplyConfiguration(state) = &event {
+                let (device_id, settings) = state.devices_iter().next().unwrap();
+                let worker: usize = device_id.strip_prefix("worker").unwrap().parse().unwrap();
+                applied[worker].push(settings.name.parse::<usize>().unwrap());
+                last_applied = Some(state.as_ref().clone());
+                remaining -= 1;
+            }
+            app.handle_user_event(event, &refs, &proxy, &mut control_flow);
+        }
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        // Follow-up events, such as saving the last change
+        while let Ok(event) = events.try_recv() {
+            app.handle_user_event(event, &refs, &proxy, &mut control_flow);
+        }
+
+        let expected: Vec<usize> = (0..CHANGES_PER_WORKER).collect();
+        for changes in applied {
+            assert_eq!(changes, expected);
+        }
+        assert_eq!(Some(&app.persistent_state), last_applied.as_ref());
+        assert_eq!(read_state_file(&state_path).unwrap(), app.persistent_state);
+    }
+
+    /// Forgets the disconnected device "gone" through its tray menu item and
+    /// returns the event history records this test thread made.
+    fn forget_through_menu(state_path: std::path::PathBuf) -> Vec<crate::audit::AuditRecord> {
+        let mut state = PersistentState::default();
+        state.devices.insert(
+            DeviceId::from("gone"),
+            make_device_settings("Old Speakers", DeviceType::Output),
+        );
+        state
+            .priority_list_mut(DeviceType::Output)
+            .push("gone".into());
+        let mut app = AppState::new(
+            state,
+            state_path,
+            MockAudioBackend::new(vec![MockDevice::new("dev1", "Speakers", true)]),
+            Rc::new(RecordingNotifier::default()),
+        );
+        let (proxy, _events) = mpsc::channel();
+        let tray = TestTray::new();
+        let refs = tray.refs();
+        app.handle_tray_click(&refs);
+        let id = app
+            .menu_id_map
+            .iter()
+            .find(|(_, info)| {
+                matches!(
+                    info.action,
+                    MenuAction::Device {
+                        action: DeviceAction::ForgetDevice,
+                        ..
+                    }
+                )
+            })
+            .map(|(id, _)| id.clone())
+            .expect("the disconnected device can be forgotten");
+
+        let event = tray_icon::menu::MenuEvent { id };
+        app.handle_menu_click(&event, &refs, &proxy, &mut ControlFlow::Wait);
+        assert_eq!(app.persistent_state.device_count(), 0);
+        crate::audit::tests::recorded()
+    }
+
+    #[test]
+    fn forgotten_device_is_recorded_once_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.json");
+
+        let records = forget_through_menu(state_path.clone());
+
+        assert_eq!(read_state_file(&state_path).unwrap().device_count(), 0);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].device_id, DeviceId::from("gone"));
+    }
+
+    #[test]
+    fn forgotten_device_is_not_recorded_when_saving_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let records = forget_through_menu(dir.path().join("missing").join("state.json"));
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn live_mic_alert_is_shown_once_per_device() {
+        let mut mic = MockDevice::new("mic1", "Microphone", true);
+        mic.device_type = DeviceType::Input;
+        *mic.muted.borrow_mut() = true;
+        let mut state = PersistentState::default();
+        let mut settings = make_device_settings("Microphone", DeviceType::Input);
+        settings.unmute_lock.is_locked = true;
+        state.devices.insert(mic.id.clone(), settings);
+        state.alert_live_mics = true;
+        let notifier = RecordingNotifier::default();
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = AppState::new(
+            state,
+            dir.path().join("state.json"),
+            MockAudioBackend::new(vec![mic]),
+            Rc::new(notifier.clone()),
+        );
+
+        // Every lookup hands out a muted copy, so the lock unmutes it each time
+        for _ in 0..2 {
+            app.handle_volume_changed(VolumeChangedEvent {
+                device_id: DeviceId::from("mic1"),
+                new_volume: None,
+            });
+        }
+
+        assert_eq!(notifier.titles(), ["Microphone Is Live"]);
+    }
+
+    /// Simulates rapid device activation changes, ID re-enumerations and
+    /// renames, each reported as a burst of device change notifications, and
+    /// runs them through the event handlers the event loop uses, followed by a
+    /// tray menu rebuild. The number of changes can be raised with the
+    /// `VOLUME_LOCKER_STRESS_ITERATIONS` environment variable.
+    #[test]
+    #[allow(clippy::too_many_lines)]
+    fn device_churn_stress() {
+        // Device lookups allowed per device for one reload or menu rebuild
+        const LOOKUPS_PER_DEVICE: usize = 12;
+
+        let iterations: usize = std::env::var("VOLUME_LOCKER_STRESS_ITERATIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(500);
+
+        let mut devices = Vec::new();
+        for (i, device_type) in [DeviceType::Output, DeviceType::Input]
+            .into_iter()
+            .flat_map(|t| std::iter::repeat_n(t, 4))
+            .enumerate()
+        {
+            let mut device = MockDevice::new(&format!("id{i}"), &format!("Device {i}"), true);
+            device.device_type = device_type;
+            devices.push(device);
+        }
+        let backend = MockAudioBackend::new(devices);
+        let device_count = backend.devices.len();
+
+        let mut state = PersistentState::default();
+        for (i, device) in backend.devices.iter().enumerate() {
+            let mut settings = make_device_settings(&device.name, device.device_type);
+            settings.volume_lock.is_locked = i % 2 == 0;
+            state.devices.insert(device.id.clone(), settings);
+        }
+        // Priority lists hold device indexes, mirrored in `state` as IDs
+        let expected_priorities = [
+            (DeviceType::Output, vec![2, 0, 3]),
+            (DeviceType::Input, vec![5, 7, 4]),
+        ];
+        for (device_type, indexes) in &expected_priorities {
+            *state.priority_list_mut(*device_type) = indexes
+                .iter()
+                .map(|&i| backend.devices[i].id.clone())
+                .collect();
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = AppState::new(
+            state,
+            dir.path().join("state.json"),
+            backend,
+            Rc::new(RecordingNotifier::default()),
+        );
+        let (proxy, events) = mpsc::channel();
+        app.watch_device_changes(&proxy).unwrap();
+
+        let tray = TestTray::new();
+        let refs = tray.refs();
+        let mut control_flow = ControlFlow::Wait;
+
+        // Deterministic xorshift so failures are reproducible
+        let mut seed: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            usize::try_from(seed % 1024).unwrap()
+        };
+
+        for generation in 0..iterations {
+            let index = next() % device_count;
+            let devices = &mut app.backend.devices;
+            match next() % 3 {
+                0 => devices[index].active = !devices[index].active,
+                1 => devices[index].id = DeviceId::from(format!("id{index}-{generation}")),
+                _ => devices[index].name = format!("Device {index} rev {generation}"),
+            }
+            // Windows reports one change as several notifications
+            for _ in 0..=next() % 4 {
+                app.devices_changed.request(&proxy);
+            }
+
+            *app.backend.handed_out_count.borrow_mut() = 0;
+            let mut reloads = 0;
+            let mut handled = 0;
+            while let Ok(event) = events.try_recv() {
+                if matches!(event, UserEvent::DevicesChanged) {
+                    reloads += 1;
+                }
+                handled += 1;
+                assert!(handled < 100, "events keep coming after a change");
+                app.handle_user_event(event, &refs, &proxy, &mut control_flow);
+            }
+            // One reload, and one more if updating the device records changed
+            // what is enforced
+            assert!(
+                (1..=2).contains(&reloads),
+                "{reloads} reloads for one change"
+            );
+            let lookups = *app.backend.handed_out_count.borrow();
+            assert!(
+                lookups <= reloads * LOOKUPS_PER_DEVICE * device_count,
+                "{lookups} device lookups for {reloads} reloads"
+            );
+
+            let backend = &app.backend;
+            let state = &app.persistent_state;
+            assert_eq!(state.device_count(), device_count);
+            for device in &backend.devices {
+                let settings = state.device_settings(&device.id).expect("device tracked");
+                assert_eq!(settings.name, device.name);
+            }
+            let expected_watched = backend
+                .devices
+                .iter()
+                .enumerate()
+                .filter(|(i, device)| i % 2 == 0 && device.active)
+                .count();
+            assert_eq!(app.watched_devices.len(), expected_watched);
+            // Replaced devices released their callbacks; the default output is watched too
+            let default_output_watches = usize::from(app.default_output.is_some());
+            assert_eq!(
+                *backend.live_watches.borrow(),
+                expected_watched + default_output_watches
+            );
+            for (device_type, indexes) in &expected_priorities {
+                let expected_ids: Vec<_> = indexes
+                    .iter()
+                    .map(|&i| backend.devices[i].id.clone())
+                    .collect();
+                assert_eq!(state.priority_list(*device_type), expected_ids.as_slice());
+
+                if let Some(&top) = indexes.iter().find(|&&i| backend.devices[i].active) {
+                    let default = backend
+                        .default_device(*device_type, DeviceRole::Console)
+                        .unwrap();
+                    assert_eq!(*default.id(), backend.devices[top].id);
+                }
+            }
+
+            *app.backend.handed_out_count.borrow_mut() = 0;
+            app.handle_tray_click(&refs);
+            let lookups = *app.backend.handed_out_count.borrow();
+            assert!(
+                lookups <= LOOKUPS_PER_DEVICE * device_count,
+                "{lookups} device lookups for one menu rebuild"
+            );
+            for device in app.backend.devices.iter().filter(|device| device.active) {
+                assert!(
+                    app.menu_id_map.values().any(|info| matches!(
+                        &info.action,
+                        MenuAction::Device { device_id, .. } if *device_id == device.id
+                    )),
+                    "{} missing from the menu",
+                    device.name
+                );
+            }
+            assert_eq!(
+                *app.backend.live_watches.borrow(),
+                expected_watched + default_output_watches,
+                "the menu rebuild leaked volume callbacks"
+            );
+        }
+
+        let watches = Rc::clone(&app.backend.live_watches);
+        drop(app);
+        assert_eq!(*watches.borrow(), 0);
+    }
 
     #[test]
     fn temporary_priorities_get_returns_none_by_default() {
@@ -63,6 +63,39 @@ pub fn migrate_device_ids(
     state_changed
 }
 
+/// Copies the current name and type of every enumerated device into its
+/// settings. Only devices whose record differs are touched, so a burst of
+/// device change events with nothing renamed changes nothing. Returns `true`
+/// if the state changed and should be saved.
+pub fn sync_device_names(
+    backend: &impl AudioBackend,
+    persistent_state: &mut PersistentState,
+) -> bool {
+    let mut state_changed = false;
+    for device_type in [DeviceType::Output, DeviceType::Input] {
+        let devices = match backend.devices(device_type) {
+            Ok(devices) => devices,
+            Err(e) => {
+                log::warn!("Failed to get {device_type:?} devices: {e:#}");
+                continue;
+            }
+        };
+        for device in devices {
+            let Some(settings) = persistent_state.device_settings_mut(device.id()) else {
+                continue;
+            };
+            let name = device.name();
+            if settings.name != name || settings.device_type != device_type {
+                log::info!("Device {} is now known as {name}", settings.name);
+                settings.name = name;
+                settings.device_type = device_type;
+                state_changed = true;
+            }
+        }
+    }
+    state_changed
+}
+
 fn find_device_by_name_and_type(
     backend: &impl AudioBackend,
     target_name: &str,
@@ -167,6 +200,73 @@ mod tests {
         assert_eq!(state.input.priority_list, vec!["mic_new"]);
     }
 
+    #[test]
+    fn sync_names_reports_no_change_when_names_match() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("id1", "Speakers", true)]);
+        let mut state = PersistentState::default();
+        state.devices.insert(
+            "id1".into(),
+            make_device_settings("Speakers", DeviceType::Output),
+        );
+
+        assert!(!sync_device_names(&backend, &mut state));
+    }
+
+    #[test]
+    fn sync_names_updates_name_and_type() {
+        let mut mic = MockDevice::new("id1", "Microphone (USB)", true);
+        mic.device_type = DeviceType::Input;
+        let backend = MockAudioBackend::new(vec![mic]);
+        let mut state = PersistentState::default();
+        state.devices.insert(
+            "id1".into(),
+            make_device_settings("Microphone", DeviceType::Output),
+        );
+
+        assert!(sync_device_names(&backend, &mut state));
+        assert_eq!(state.devices["id1"].name, "Microphone (USB)");
+        assert_eq!(state.devices["id1"].device_type, DeviceType::Input);
+    }
+
+    #[test]
+    fn sync_names_ignores_unknown_devices() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("new", "Speakers", true)]);
+        let mut state = PersistentState::default();
+
+        assert!(!sync_device_names(&backend, &mut state));
+        assert_eq!(state.device_count(), 0);
+    }
+
+    #[test]
+    fn sync_names_settles_after_rename_storm() {
+        let mut backend = MockAudioBackend::new(vec![
+            MockDevice::new("id1", "Speakers", true),
+            MockDevice::new("id2", "Headphones", true),
+        ]);
+        let mut state = PersistentState::default();
+        for device in &backend.devices {
+            state.devices.insert(
+                device.id.clone(),
+                make_device_settings(&device.name, device.device_type),
+            );
+        }
+
+        // A driver flapping the name reports a change for each distinct name only
+        let mut saves = 0;
+        for name in ["Speakers (2)", "Speakers (2)", "Speakers", "Speakers (2)"] {
+            backend.devices[0].name = name.to_string();
+            for _ in 0..10 {
+                if sync_device_names(&backend, &mut state) {
+                    saves += 1;
+                }
+            }
+        }
+
+        assert_eq!(saves, 3);
+        assert_eq!(state.devices["id1"].name, "Speakers (2)");
+        assert_eq!(state.devices["id2"].name, "Headphones");
+    }
+
     #[test]
     fn find_device_by_name_found() {
         let backend = MockAudioBackend::new(vec![
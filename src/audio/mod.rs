@@ -17,6 +17,81 @@ pub trait AudioBackend {
         &self,
         callback: Box<dyn Fn() + Send + Sync>,
     ) -> anyhow::Result<()>;
+
+    /// Creates the connection to the audio service and (re-)registers the device
+    /// change callback, e.g. once the service is up or after it was restarted.
+    fn reconnect(&self) -> anyhow::Result<()>;
+
+    /// Whether `error`, returned by a device, means the audio service itself is
+    /// gone rather than that one device.
+    fn is_service_error(&self, error: &anyhow::Error) -> bool;
+}
+
+/// Range a checked volume change is allowed to land in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VolumeBounds {
+    pub min: VolumeScalar,
+    pub max: VolumeScalar,
+}
+
+impl VolumeBounds {
+    /// The whole range the device supports.
+    pub const FULL: Self = Self {
+        min: VolumeScalar::MIN,
+        max: VolumeScalar::MAX,
+    };
+
+    pub fn clamp(self, volume: VolumeScalar) -> VolumeScalar {
+        if volume < self.min {
+            self.min
+        } else if volume > self.max {
+            self.max
+        } else {
+            volume
+        }
+    }
+}
+
+/// Controls an endpoint actually implements. Lock options for missing ones
+/// would silently fail, so the menu disables them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceCapabilities {
+    pub volume: bool,
+    pub mute: bool,
+}
+
+impl DeviceCapabilities {
+    pub const ALL: Self = Self {
+        volume: true,
+        mute: true,
+    };
+}
+
+/// What [`AudioDevice::set_volume_checked`] ended up doing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetVolumeOutcome {
+    Applied,
+    /// The target was out of bounds, so the nearest bound was applied instead.
+    Clamped(VolumeScalar),
+    /// The device reports a different level than the one just applied.
+    VerificationFailed {
+        expected: VolumeScalar,
+        actual: VolumeScalar,
+    },
+}
+
+/// What [`enforce_volume_lock`] did about a device's volume.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeLockOutcome {
+    /// The volume was already at the locked level.
+    Unchanged,
+    /// The locked level was written and confirmed, so the device should
+    /// report a volume change.
+    Restored,
+    /// The write went through but the device kept another level.
+    Refused,
+    /// Writing the volume failed; the error was logged.
+    Failed,
 }
 
 pub trait AudioDevice {
@@ -24,10 +99,36 @@ pub trait AudioDevice {
     fn name(&self) -> String;
     fn volume(&self) -> anyhow::Result<VolumeScalar>;
     fn set_volume(&self, volume: VolumeScalar) -> anyhow::Result<()>;
+
+    /// Sets the volume to `target` clamped to `bounds`, then reads it back to
+    /// confirm the device took it. Levels are compared at whole percents.
+    fn set_volume_checked(
+        &self,
+        target: VolumeScalar,
+        bounds: VolumeBounds,
+    ) -> anyhow::Result<SetVolumeOutcome> {
+        let applied = bounds.clamp(target);
+        self.set_volume(applied)?;
+        let actual = self.volume()?;
+        Ok(if actual.to_percent() != applied.to_percent() {
+            SetVolumeOutcome::VerificationFailed {
+                expected: applied,
+                actual,
+            }
+        } else if applied == target {
+            SetVolumeOutcome::Applied
+        } else {
+            SetVolumeOutcome::Clamped(applied)
+        })
+    }
     fn is_muted(&self) -> anyhow::Result<bool>;
     fn set_mute(&self, muted: bool) -> anyhow::Result<()>;
     fn is_active(&self) -> anyhow::Result<bool>;
 
+    fn capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities::ALL
+    }
+
     fn watch_volume(
         &self,
         callback: Box<dyn Fn(Option<VolumeScalar>) + Send + Sync>,
@@ -41,16 +142,69 @@ pub use self::windows::WindowsAudioBackend as AudioBackendImpl;
 
 mod migration;
 mod priority;
+mod recovery;
 
-pub use migration::migrate_device_ids;
+pub use migration::{migrate_device_ids, sync_device_names};
 pub use priority::enforce_priorities;
+pub use recovery::{
+    AudioServiceMonitor, AudioServiceWait, ServiceProbeOutcome, ServiceWaitOutcome,
+};
 
+use crate::audit::{self, AuditEvent, AuditRecord, AuditTrigger};
 use crate::notification::NotificationThrottler;
+use crate::types::DeviceSettings;
+
+/// Applies every lock of a device for one observed volume, always in this order:
+///
+/// 1. Volume lock: the locked level is restored.
+/// 2. Unmute lock: the device is unmuted.
+///
+/// The level is restored before unmuting so a muted device never becomes
+/// audible at the wrong level. Each step only acts when the device differs
+/// from its target, so applying the locks again to the resulting state, as
+/// happens when our own changes fire the volume callback, changes nothing.
+///
+/// Returns what the volume lock did, which is `Unchanged` without one.
+pub fn enforce_device_locks(
+    device: &dyn AudioDevice,
+    settings: &DeviceSettings,
+    observed_volume: VolumeScalar,
+    throttler: &mut NotificationThrottler,
+) -> VolumeLockOutcome {
+    let display_name = settings.display_name();
+    let mut volume_outcome = VolumeLockOutcome::Unchanged;
+    if settings.volume_lock.is_locked {
+        volume_outcome = enforce_volume_lock(
+            device.id(),
+            device,
+            &settings.name,
+            &display_name,
+            settings.volume_lock,
+            observed_volume,
+            throttler,
+        );
+    }
+    if settings.unmute_lock.is_locked {
+        check_and_unmute_device(
+            device,
+            &settings.name,
+            &display_name,
+            settings.device_type,
+            settings.unmute_lock.notify,
+            throttler,
+        );
+    }
+    volume_outcome
+}
 
 /// Best-effort unmute enforcement. Logs errors internally — callers do not
 /// need to handle failures since this is a background enforcement operation.
+/// `device_name` goes to logs and the event history, `display_name` (with the
+/// tag) to the notification.
 pub fn check_and_unmute_device(
     device: &dyn AudioDevice,
+    device_name: &str,
+    display_name: &str,
     device_type: DeviceType,
     notify: bool,
     throttler: &mut NotificationThrottler,
@@ -58,7 +212,7 @@ pub fn check_and_unmute_device(
     let is_muted = match device.is_muted() {
         Ok(m) => m,
         Err(e) => {
-            log::warn!("Failed to check mute state of {}: {e:#}", device.name());
+            log::warn!("Failed to check mute state of {device_name}: {e:#}");
             return;
         }
     };
@@ -66,15 +220,23 @@ pub fn check_and_unmute_device(
         return;
     }
     if let Err(e) = device.set_mute(false) {
-        log::error!("Failed to unmute {}: {e:#}", device.name());
+        log::error!("Failed to unmute {device_name}: {e:#}");
         return;
     }
-    let device_name = device.name();
     log::info!("Unmuted {device_name} due to lock settings");
+    audit::record(&AuditRecord {
+        event: AuditEvent::Unmute,
+        trigger: AuditTrigger::UnmuteLock,
+        device_id: device.id().clone(),
+        device_name: device_name.to_string(),
+        role: None,
+        before: "muted".to_string(),
+        after: "unmuted".to_string(),
+    });
     if notify {
         let (notification_title, notification_suffix) =
             get_unmute_notification_details(device_type);
-        let message = format!("{device_name} {notification_suffix}");
+        let message = format!("{display_name} {notification_suffix}");
         throttler.send_if_not_throttled(
             &format!("unmute_{id}", id = device.id()),
             notification_title,
@@ -83,38 +245,62 @@ pub fn check_and_unmute_device(
     }
 }
 
+/// Restores the locked level if the device moved away from it. Names are used
+/// as in [`check_and_unmute_device`].
 pub fn enforce_volume_lock(
     device_id: &DeviceId,
     device: &dyn AudioDevice,
     device_name: &str,
+    display_name: &str,
     lock: crate::types::VolumeLockPolicy,
     new_volume: VolumeScalar,
     throttler: &mut NotificationThrottler,
-) {
+) -> VolumeLockOutcome {
     let new_volume_percent = new_volume.to_percent();
     let target_volume_percent = lock.target_percent;
     if new_volume_percent == target_volume_percent {
-        return;
+        return VolumeLockOutcome::Unchanged;
     }
 
-    let target_volume = target_volume_percent.to_scalar();
-
-    if let Err(e) = device.set_volume(target_volume) {
-        log::error!("Failed to set volume of {device_name} to {target_volume_percent}%: {e:#}");
-        return;
-    }
+    let target_volume_percent = match device
+        .set_volume_checked(target_volume_percent.to_scalar(), VolumeBounds::FULL)
+    {
+        Ok(SetVolumeOutcome::Applied) => target_volume_percent,
+        Ok(SetVolumeOutcome::Clamped(applied)) => applied.to_percent(),
+        Ok(SetVolumeOutcome::VerificationFailed { actual, .. }) => {
+            log::warn!(
+                "Volume of {device_name} is {}% after setting it to {target_volume_percent}%",
+                actual.to_percent()
+            );
+            return VolumeLockOutcome::Refused;
+        }
+        Err(e) => {
+            log::error!("Failed to set volume of {device_name} to {target_volume_percent}%: {e:#}");
+            return VolumeLockOutcome::Failed;
+        }
+    };
     log::info!(
         "Restored volume of {device_name} from {new_volume_percent}% to {target_volume_percent}%"
     );
+    audit::record(&AuditRecord {
+        event: AuditEvent::VolumeRestore,
+        trigger: AuditTrigger::VolumeLock,
+        device_id: device_id.clone(),
+        device_name: device_name.to_string(),
+        role: None,
+        before: format!("{new_volume_percent}%"),
+        after: format!("{target_volume_percent}%"),
+    });
     if lock.notify {
         throttler.send_if_not_throttled(
             &format!("volume_restore_{device_id}"),
             "Volume Restored",
             &format!(
-                "The volume of {device_name} has been restored from {new_volume_percent}% to {target_volume_percent}%."
+                "The volume of {display_name} has been restored from {new_volume_percent}% to {target_volume_percent}%."
             ),
         );
     }
+    VolumeLockOutcome::Restored
 }
 
 fn get_unmute_notification_details(device_type: DeviceType) -> (&'static str, &'static str) {
@@ -125,34 +311,18 @@ fn get_unmute_notification_details(device_type: DeviceType) -> (&'static str, &'
     (title, "was unmuted due to Keep unmuted setting.")
 }
 
-/// Returns a list of `(device_id, new_name, device_type)` tuples for all
-/// known devices, so the caller can apply updates to persistent state.
-pub fn collect_device_names(backend: &impl AudioBackend) -> Vec<(DeviceId, String, DeviceType)> {
-    [DeviceType::Output, DeviceType::Input]
-        .into_iter()
-        .flat_map(|device_type| {
-            backend
-                .devices(device_type)
-                .unwrap_or_else(|e| {
-                    log::warn!("Failed to get {device_type:?} devices: {e:#}");
-                    Vec::new()
-                })
-                .into_iter()
-                .map(move |device| (device.id().clone(), device.name(), device_type))
-        })
-        .collect()
-}
-
 #[cfg(test)]
 pub(crate) mod tests {
     use super::*;
     use crate::config::PersistentState;
     use crate::notification::NotificationThrottler;
+    use crate::notification::tests::recording_throttler;
     use crate::types::{
         DeviceId, DeviceSettings, TemporaryPriorities, VolumePercent, VolumeScalar,
     };
     use std::cell::RefCell;
     use std::collections::HashMap;
+    use std::rc::Rc;
 
     pub(crate) struct MockDevice {
         pub(crate) id: DeviceId,
@@ -161,6 +331,17 @@ pub(crate) mod tests {
         pub(crate) device_type: DeviceType,
         pub(crate) volume: RefCell<f32>,
         pub(crate) muted: RefCell<bool>,
+        /// Every change made to the device, in order, e.g. `"volume 0.5"` or `"mute false"`.
+        pub(crate) changes: RefCell<Vec<String>>,
+        /// If true, volume changes are recorded but the level stays the same.
+        pub(crate) ignores_volume_changes: bool,
+        pub(crate) capabilities: DeviceCapabilities,
+        /// Count of live volume callback registrations shared with the backend
+        /// that handed out this copy. Released when the device is dropped.
+        watches: Option<Rc<RefCell<usize>>>,
+        watching: RefCell<bool>,
+        /// Set by the backend that handed out this copy, see `MockAudioBackend::lose_endpoints`.
+        endpoint_lost: Option<Rc<RefCell<bool>>>,
     }
 
     impl MockDevice {
@@ -172,6 +353,22 @@ pub(crate) mod tests {
                 device_type: DeviceType::Output,
                 volume: RefCell::new(1.0),
                 muted: RefCell::new(false),
+                changes: RefCell::new(Vec::new()),
+                ignores_volume_changes: false,
+                capabilities: DeviceCapabilities::ALL,
+                watches: None,
+                watching: RefCell::new(false),
+                endpoint_lost: None,
+            }
+        }
+    }
+
+    impl Drop for MockDevice {
+        fn drop(&mut self) {
+            if *self.watching.borrow()
+                && let Some(watches) = &self.watches
+            {
+                *watches.borrow_mut() -= 1;
             }
         }
     }
@@ -184,10 +381,22 @@ pub(crate) mod tests {
             self.name.clone()
         }
         fn volume(&self) -> anyhow::Result<VolumeScalar> {
+            if self
+                .endpoint_lost
+                .as_ref()
+                .is_some_and(|lost| *lost.borrow())
+            {
+                return Err(anyhow::anyhow!(INJECTED_SERVICE_FAILURE));
+            }
             Ok(VolumeScalar::from(*self.volume.borrow()))
         }
         fn set_volume(&self, volume: VolumeScalar) -> anyhow::Result<()> {
-            *self.volume.borrow_mut() = volume.as_f32();
+            if !self.ignores_volume_changes {
+                *self.volume.borrow_mut() = volume.as_f32();
+            }
+            self.changes
+                .borrow_mut()
+                .push(format!("volume {}", volume.as_f32()));
             Ok(())
         }
         fn is_muted(&self) -> anyhow::Result<bool> {
@@ -195,19 +404,30 @@ pub(crate) mod tests {
         }
         fn set_mute(&self, muted: bool) -> anyhow::Result<()> {
             *self.muted.borrow_mut() = muted;
+            self.changes.borrow_mut().push(format!("mute {muted}"));
             Ok(())
         }
         fn is_active(&self) -> anyhow::Result<bool> {
             Ok(self.active)
         }
+        fn capabilities(&self) -> DeviceCapabilities {
+            self.capabilities
+        }
         fn watch_volume(
             &self,
             _callback: Box<dyn Fn(Option<VolumeScalar>) + Send + Sync>,
         ) -> anyhow::Result<()> {
+            if let Some(watches) = &self.watches
+                && !self.watching.replace(true)
+            {
+                *watches.borrow_mut() += 1;
+            }
             Ok(())
         }
     }
 
+    const INJECTED_SERVICE_FAILURE: &str = "Injected audio service failure";
+
     pub(crate) struct MockAudioBackend {
         pub(crate) devices: Vec<MockDevice>,
         pub(crate) default_console: RefCell<HashMap<DeviceType, String>>,
@@ -217,6 +437,18 @@ pub(crate) mod tests {
         pub(crate) failing_device_ids: RefCell<Vec<String>>,
         /// If true, `set_default_device` will return `Err`.
         pub(crate) set_default_fails: RefCell<bool>,
+        /// If true, enumerating devices fails until `reconnect` is called.
+        pub(crate) service_down: RefCell<bool>,
+        /// Shared with the devices handed out since the last `reconnect`, which
+        /// fail once it is set, see [`Self::lose_endpoints`].
+        endpoints_lost: RefCell<Rc<RefCell<bool>>>,
+        /// If true, `reconnect` will return `Err`.
+        pub(crate) reconnect_fails: RefCell<bool>,
+        pub(crate) reconnect_count: RefCell<u32>,
+        /// Volume callbacks registered on handed-out devices that are still alive.
+        pub(crate) live_watches: Rc<RefCell<usize>>,
+        /// How many devices were handed out, as a measure of the work done.
+        pub(crate) handed_out_count: RefCell<usize>,
     }
 
     impl MockAudioBackend {
@@ -228,9 +460,35 @@ pub(crate) mod tests {
                 default_communications: RefCell::new(HashMap::new()),
                 failing_device_ids: RefCell::new(Vec::new()),
                 set_default_fails: RefCell::new(false),
+                service_down: RefCell::new(false),
+                endpoints_lost: RefCell::new(Rc::new(RefCell::new(false))),
+                reconnect_fails: RefCell::new(false),
+                reconnect_count: RefCell::new(0),
+                live_watches: Rc::new(RefCell::new(0)),
+                handed_out_count: RefCell::new(0),
             }
         }
 
+        /// A fresh copy of `device`, like the new COM object each lookup returns.
+        fn handed_out(&self, device: &MockDevice) -> Box<dyn AudioDevice> {
+            *self.handed_out_count.borrow_mut() += 1;
+            let mut copy = MockDevice::new(&device.id, &device.name, device.active);
+            copy.device_type = device.device_type;
+            copy.capabilities = device.capabilities;
+            copy.ignores_volume_changes = device.ignores_volume_changes;
+            *copy.muted.borrow_mut() = *device.muted.borrow();
+            copy.watches = Some(Rc::clone(&self.live_watches));
+            copy.endpoint_lost = Some(Rc::clone(&self.endpoints_lost.borrow()));
+            Box::new(copy)
+        }
+
+        /// Makes every device handed out so far fail with a service error, like
+        /// the endpoints the app holds when the audio service restarts while
+        /// enumeration keeps working.
+        pub(crate) fn lose_endpoints(&self) {
+            *self.endpoints_lost.borrow().borrow_mut() = true;
+        }
+
         pub(crate) fn set_default(&self, device_id: &str, device_type: DeviceType) {
             self.default_console
                 .borrow_mut()
@@ -243,13 +501,14 @@ pub(crate) mod tests {
 
     impl AudioBackend for MockAudioBackend {
         fn devices(&self, device_type: DeviceType) -> anyhow::Result<Vec<Box<dyn AudioDevice>>> {
+            if *self.service_down.borrow() {
+                return Err(anyhow::anyhow!(INJECTED_SERVICE_FAILURE));
+            }
             Ok(self
                 .devices
                 .iter()
                 .filter(|d| d.device_type == device_type)
-                .map(|d| {
-                    Box::new(MockDevice::new(&d.id, &d.name, d.active)) as Box<dyn AudioDevice>
-                })
+                .map(|d| self.handed_out(d))
                 .collect())
         }
 
@@ -260,9 +519,7 @@ pub(crate) mod tests {
             self.devices
                 .iter()
                 .find(|d| d.id == **id)
-                .map(|d| {
-                    Box::new(MockDevice::new(&d.id, &d.name, d.active)) as Box<dyn AudioDevice>
-                })
+                .map(|d| self.handed_out(d))
                 .ok_or_else(|| anyhow::anyhow!("Device not found: {id}"))
         }
 
@@ -319,6 +576,21 @@ pub(crate) mod tests {
         ) -> anyhow::Result<()> {
             Ok(())
         }
+
+        fn reconnect(&self) -> anyhow::Result<()> {
+            *self.reconnect_count.borrow_mut() += 1;
+            if *self.reconnect_fails.borrow() {
+                return Err(anyhow::anyhow!("Injected reconnect failure"));
+            }
+            *self.service_down.borrow_mut() = false;
+            // Devices handed out before stay dead, like stale COM objects
+            *self.endpoints_lost.borrow_mut() = Rc::new(RefCell::new(false));
+            Ok(())
+        }
+
+        fn is_service_error(&self, error: &anyhow::Error) -> bool {
+            error.to_string().contains(INJECTED_SERVICE_FAILURE)
+        }
     }
 
     pub(crate) fn make_device_settings(name: &str, device_type: DeviceType) -> DeviceSettings {
@@ -339,7 +611,14 @@ pub(crate) mod tests {
         *device.muted.borrow_mut() = true;
         let mut throttler = NotificationThrottler::new();
 
-        check_and_unmute_device(&device, DeviceType::Output, false, &mut throttler);
+        check_and_unmute_device(
+            &device,
+            "Speaker",
+            "Speaker",
+            DeviceType::Output,
+            false,
+            &mut throttler,
+        );
         assert!(!*device.muted.borrow());
     }
 
@@ -348,10 +627,279 @@ pub(crate) mod tests {
         let device = MockDevice::new("dev1", "Speaker", true);
         let mut throttler = NotificationThrottler::new();
 
-        check_and_unmute_device(&device, DeviceType::Output, false, &mut throttler);
+        check_and_unmute_device(
+            &device,
+            "Speaker",
+            "Speaker",
+            DeviceType::Output,
+            false,
+            &mut throttler,
+        );
+        assert!(!*device.muted.borrow());
+    }
+
+    // --- enforce_device_locks tests ---
+
+    fn fully_locked_settings(target_percent: f32) -> DeviceSettings {
+        DeviceSettings {
+            volume_lock: crate::types::VolumeLockPolicy {
+                is_locked: true,
+                target_percent: VolumePercent::from(target_percent),
+                notify: true,
+            },
+            unmute_lock: crate::types::UnmuteLockPolicy {
+                is_locked: true,
+                notify: true,
+            },
+            ..DeviceSettings::new("Speaker".to_string(), DeviceType::Output)
+        }
+    }
+
+    #[test]
+    fn enforce_device_locks_restores_volume_before_unmuting() {
+        let device = MockDevice::new("dev1", "Speaker", true);
+        *device.volume.borrow_mut() = 1.0;
+        *device.muted.borrow_mut() = true;
+        let mut throttler = NotificationThrottler::new();
+
+        enforce_device_locks(
+            &device,
+            &fully_locked_settings(50.0),
+            device.volume().unwrap(),
+            &mut throttler,
+        );
+
+        assert_eq!(*device.changes.borrow(), ["volume 0.5", "mute false"]);
+    }
+
+    #[test]
+    fn tag_is_shown_in_notifications_but_not_recorded() {
+        let device = MockDevice::new("tagged-dev", "Speaker", true);
+        *device.volume.borrow_mut() = 1.0;
+        *device.muted.borrow_mut() = true;
+        let mut settings = fully_locked_settings(50.0);
+        settings.tag = Some("🎧".to_string());
+        let (mut throttler, notifier) = recording_throttler();
+
+        enforce_device_locks(&device, &settings, device.volume().unwrap(), &mut throttler);
+
+        // This test thread's own history holds exactly what it recorded
+        let recorded: Vec<_> = audit::tests::recorded()
+            .into_iter()
+            .map(|record| (record.event, record.device_name))
+            .collect();
+        assert_eq!(
+            recorded,
+            [
+                (AuditEvent::VolumeRestore, "Speaker".to_string()),
+                (AuditEvent::Unmute, "Speaker".to_string()),
+            ]
+        );
+        assert!(
+            notifier
+                .messages()
+                .iter()
+                .all(|message| message.contains("🎧 Speaker"))
+        );
+        assert_eq!(notifier.messages().len(), 2);
+    }
+
+    #[test]
+    fn enforce_device_locks_is_idempotent() {
+        let device = MockDevice::new("dev1", "Speaker", true);
+        *device.volume.borrow_mut() = 0.2;
+        *device.muted.borrow_mut() = true;
+        let settings = fully_locked_settings(80.0);
+        let mut throttler = NotificationThrottler::new();
+
+        enforce_device_locks(&device, &settings, device.volume().unwrap(), &mut throttler);
+        let after_first = device.changes.borrow().len();
+        for _ in 0..3 {
+            enforce_device_locks(&device, &settings, device.volume().unwrap(), &mut throttler);
+        }
+
+        assert_eq!(device.changes.borrow().len(), after_first);
+        assert_eq!(*device.volume.borrow(), 0.8_f32);
         assert!(!*device.muted.borrow());
     }
 
+    #[test]
+    fn enforce_device_locks_does_not_flap_on_own_callbacks() {
+        // Every change we make fires the volume callback again; feeding the
+        // resulting state back must settle after a single round of changes.
+        let device = MockDevice::new("dev1", "Speaker", true);
+        *device.volume.borrow_mut() = 0.33;
+        *device.muted.borrow_mut() = true;
+        let settings = fully_locked_settings(60.0);
+        let mut throttler = NotificationThrottler::new();
+
+        let mut pending_events = 1;
+        let mut rounds = 0;
+        while pending_events > 0 {
+            rounds += 1;
+            assert!(rounds < 5, "enforcement kept changing the device");
+            let before = device.changes.borrow().len();
+            enforce_device_locks(&device, &settings, device.volume().unwrap(), &mut throttler);
+            pending_events = device.changes.borrow().len() - before;
+        }
+
+        assert_eq!(*device.changes.borrow(), ["volume 0.6", "mute false"]);
+    }
+
+    #[test]
+    fn enforce_device_locks_skips_unlocked_constraints() {
+        let device = MockDevice::new("dev1", "Speaker", true);
+        *device.volume.borrow_mut() = 0.1;
+        *device.muted.borrow_mut() = true;
+        let mut throttler = NotificationThrottler::new();
+
+        enforce_device_locks(
+            &device,
+            &DeviceSettings::new("Speaker".to_string(), DeviceType::Output),
+            device.volume().unwrap(),
+            &mut throttler,
+        );
+
+        assert!(device.changes.borrow().is_empty());
+    }
+
+    // --- set_volume_checked tests ---
+
+    #[test]
+    fn set_volume_checked_applies_in_bounds_target() {
+        let device = MockDevice::new("dev1", "Speaker", true);
+        let outcome = device
+            .set_volume_checked(VolumeScalar::from(0.4), VolumeBounds::FULL)
+            .unwrap();
+        assert_eq!(outcome, SetVolumeOutcome::Applied);
+        assert_eq!(*device.changes.borrow(), ["volume 0.4"]);
+    }
+
+    #[test]
+    fn set_volume_checked_clamps_to_bounds() {
+        let device = MockDevice::new("dev1", "Speaker", true);
+        let bounds = VolumeBounds {
+            min: VolumeScalar::from(0.2),
+            max: VolumeScalar::from(0.8),
+        };
+
+        let outcome = device
+            .set_volume_checked(VolumeScalar::from(0.95), bounds)
+            .unwrap();
+        assert_eq!(outcome, SetVolumeOutcome::Clamped(VolumeScalar::from(0.8)));
+
+        let outcome = device
+            .set_volume_checked(VolumeScalar::from(0.05), bounds)
+            .unwrap();
+        assert_eq!(outcome, SetVolumeOutcome::Clamped(VolumeScalar::from(0.2)));
+        assert_eq!(*device.changes.borrow(), ["volume 0.8", "volume 0.2"]);
+    }
+
+    #[test]
+    fn set_volume_checked_detects_ignored_change() {
+        let mut device = MockDevice::new("dev1", "Speaker", true);
+        device.ignores_volume_changes = true;
+        *device.volume.borrow_mut() = 0.3;
+
+        let outcome = device
+            .set_volume_checked(VolumeScalar::from(0.7), VolumeBounds::FULL)
+            .unwrap();
+        assert_eq!(
+            outcome,
+            SetVolumeOutcome::VerificationFailed {
+                expected: VolumeScalar::from(0.7),
+                actual: VolumeScalar::from(0.3),
+            }
+        );
+    }
+
+    #[test]
+    fn enforce_volume_lock_does_not_record_unverified_restore() {
+        let mut device = MockDevice::new("dev1", "Speaker", true);
+        device.ignores_volume_changes = true;
+        *device.volume.borrow_mut() = 0.3;
+        let mut settings = fully_locked_settings(70.0);
+        settings.unmute_lock.is_locked = false;
+        let (mut throttler, notifier) = recording_throttler();
+
+        let outcome =
+            enforce_device_locks(&device, &settings, device.volume().unwrap(), &mut throttler);
+
+        assert_eq!(outcome, VolumeLockOutcome::Refused);
+        assert_eq!(*device.changes.borrow(), ["volume 0.7"]);
+        assert!(notifier.titles().is_empty());
+    }
+
+    #[test]
+    fn scripted_tampering_notifies_once_per_lock_within_cooldown() {
+        let device = MockDevice::new("dev1", "Speaker", true);
+        let settings = fully_locked_settings(50.0);
+        let (mut throttler, notifier) = recording_throttler();
+
+        // An app keeps turning the volume up and muting the device
+        for tampered_volume in [0.9, 1.0, 0.8] {
+            *device.volume.borrow_mut() = tampered_volume;
+            *device.muted.borrow_mut() = true;
+            enforce_device_locks(&device, &settings, device.volume().unwrap(), &mut throttler);
+            // Our own changes fire the callback again
+            enforce_device_locks(&device, &settings, device.volume().unwrap(), &mut throttler);
+        }
+
+        assert_eq!(
+            notifier.titles(),
+            ["Volume Restored", "Output Device Unmuted"]
+        );
+        assert_eq!(
+            notifier.messages()[0],
+            "The volume of Speaker has been restored from 90% to 50%."
+        );
+    }
+
+    #[test]
+    fn scripted_tampering_without_notify_is_silent() {
+        let device = MockDevice::new("dev1", "Speaker", true);
+        let mut settings = fully_locked_settings(50.0);
+        settings.volume_lock.notify = false;
+        settings.unmute_lock.notify = false;
+        let (mut throttler, notifier) = recording_throttler();
+
+        *device.volume.borrow_mut() = 0.9;
+        *device.muted.borrow_mut() = true;
+        enforce_device_locks(&device, &settings, device.volume().unwrap(), &mut throttler);
+
+        assert_eq!(*device.changes.borrow(), ["volume 0.5", "mute false"]);
+        assert!(notifier.titles().is_empty());
+    }
+
+    #[test]
+    fn scripted_tampering_in_eco_mode_is_batched() {
+        let speaker = MockDevice::new("spk", "Speaker", true);
+        let mut mic = MockDevice::new("mic", "Microphone", true);
+        mic.device_type = DeviceType::Input;
+        let mut mic_settings = fully_locked_settings(80.0);
+        mic_settings.device_type = DeviceType::Input;
+        mic_settings.name = "Microphone".to_string();
+        let (mut throttler, notifier) = recording_throttler();
+        throttler.set_batching(true);
+
+        *speaker.volume.borrow_mut() = 0.9;
+        enforce_device_locks(
+            &speaker,
+            &fully_locked_settings(50.0),
+            speaker.volume().unwrap(),
+            &mut throttler,
+        );
+        *mic.muted.borrow_mut() = true;
+        enforce_device_locks(&mic, &mic_settings, mic.volume().unwrap(), &mut throttler);
+        assert!(notifier.titles().is_empty());
+
+        throttler.flush_batch();
+        assert_eq!(
+            notifier.titles(),
+            [format!("3 {} Events", crate::consts::APP_NAME)]
+        );
+    }
+
     // --- get_unmute_notification_details tests ---
 
     #[test]
@@ -1,6 +1,7 @@
+use crate::audit::{self, AuditEvent, AuditRecord, AuditTrigger};
 use crate::config::PersistentState;
 use crate::notification::NotificationThrottler;
-use crate::types::{DeviceId, DeviceRole, DeviceType, TemporaryPriorities};
+use crate::types::{DeviceId, DeviceRole, DeviceSettings, DeviceType, TemporaryPriorities};
 
 use super::AudioBackend;
 
@@ -21,21 +22,53 @@ pub fn enforce_priorities(
     }
 }
 
-fn is_default_device(
+fn current_default_id(
     backend: &impl AudioBackend,
     device_type: DeviceType,
     role: DeviceRole,
-    target_id: &DeviceId,
-) -> bool {
+) -> Option<DeviceId> {
     match backend.default_device(device_type, role) {
-        Ok(d) => *target_id == *d.id(),
+        Ok(d) => Some(d.id().clone()),
         Err(e) => {
             log::warn!("Failed to get default {device_type} {role} device: {e:#}");
-            false
+            None
         }
     }
 }
 
+fn device_name_or_unknown(backend: &impl AudioBackend, device_id: &DeviceId) -> String {
+    backend.device_by_id(device_id).map_or_else(
+        |e| {
+            log::warn!("Could not get name for device {device_id}: {e:#}");
+            "Unknown Device".to_string()
+        },
+        |d| d.name(),
+    )
+}
+
+/// Switches the default device for `role` and records the change in the audit log.
+fn switch_default_device(
+    backend: &impl AudioBackend,
+    device_type: DeviceType,
+    role: DeviceRole,
+    target_id: &DeviceId,
+    previous_id: Option<&DeviceId>,
+) {
+    if let Err(e) = backend.set_default_device(target_id, role) {
+        log::error!("Failed to set default {role} {device_type} device to {target_id}: {e:#}");
+        return;
+    }
+    audit::record(&AuditRecord {
+        event: AuditEvent::DefaultDeviceSwitch,
+        trigger: AuditTrigger::PriorityList,
+        device_id: target_id.clone(),
+        device_name: device_name_or_unknown(backend, target_id),
+        role: Some(role),
+        before: previous_id.map_or_else(|| "none".to_string(), ToString::to_string),
+        after: target_id.to_string(),
+    });
+}
+
 fn enforce_priority_for_type(
     backend: &impl AudioBackend,
     device_type: DeviceType,
@@ -55,38 +88,37 @@ fn enforce_priority_for_type(
     let mut switched = false;
 
     // Enforce Console and Multimedia roles together
-    if !is_default_device(backend, device_type, DeviceRole::Console, &target_id) {
+    let previous_id = current_default_id(backend, device_type, DeviceRole::Console);
+    if previous_id.as_ref() != Some(&target_id) {
         log::info!("Enforcing {device_type} priority: Switching to {target_id}");
         for role in [DeviceRole::Console, DeviceRole::Multimedia] {
-            if let Err(e) = backend.set_default_device(&target_id, role) {
-                log::error!(
-                    "Failed to set default {role} {device_type} device to {target_id}: {e:#}"
-                );
-            }
+            switch_default_device(backend, device_type, role, &target_id, previous_id.as_ref());
         }
         switched = true;
     }
 
     // Enforce Communications role if enabled
-    if state.switch_communication_device(device_type)
-        && !is_default_device(backend, device_type, DeviceRole::Communications, &target_id)
-    {
-        log::info!("Enforcing {device_type} priority (Communication): Switching to {target_id}");
-        if let Err(e) = backend.set_default_device(&target_id, DeviceRole::Communications) {
-            log::error!(
-                "Failed to set default {device_type} communications device to {target_id}: {e:#}"
+    if state.switch_communication_device(device_type) {
+        let previous_id = current_default_id(backend, device_type, DeviceRole::Communications);
+        if previous_id.as_ref() != Some(&target_id) {
+            log::info!(
+                "Enforcing {device_type} priority (Communication): Switching to {target_id}"
+            );
+            switch_default_device(
+                backend,
+                device_type,
+                DeviceRole::Communications,
+                &target_id,
+                previous_id.as_ref(),
             );
+            switched = true;
         }
-        switched = true;
     }
 
     if switched && state.notify_on_priority_restore(device_type) {
-        let device_name = backend.device_by_id(&target_id).map_or_else(
-            |e| {
-                log::warn!("Could not get name for device {target_id}: {e:#}");
-                "Unknown Device".to_string()
-            },
-            |d| d.name(),
+        let device_name = state.device_settings(&target_id).map_or_else(
+            || device_name_or_unknown(backend, &target_id),
+            DeviceSettings::display_name,
         );
         let title = match device_type {
             DeviceType::Output => "Default Output Device Restored",
@@ -0,0 +1,258 @@
+use super::{AudioBackend, AudioDevice};
+use crate::utils::Backoff;
+use std::time::{Duration, Instant};
+
+/// Consecutive failed probes before the audio service is considered restarted.
+const FAILURES_BEFORE_RECONNECT: u32 = 2;
+
+/// Failed attempts to connect at startup before the audio service is reported
+/// unavailable, about 23 seconds with the default delays.
+const FAILURES_BEFORE_REPORT: u32 = 6;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ServiceProbeOutcome {
+    Healthy,
+    /// The probe failed, but not often enough yet to attempt a reconnect.
+    Degraded,
+    Reconnected,
+    ReconnectFailed,
+}
+
+/// Detects when the Windows audio service restarted, which invalidates every COM
+/// object the backend holds, and reconnects the backend when that happens.
+#[derive(Default)]
+pub struct AudioServiceMonitor {
+    consecutive_failures: u32,
+}
+
+impl AudioServiceMonitor {
+    /// Probes the device enumerator and the volume of `endpoint`, a device the
+    /// app holds on to. Enumeration can keep working after the service
+    /// restarted while the endpoints obtained before are dead, so both are
+    /// checked. Only errors that point at the service count: a single device
+    /// failing, e.g. because it was just unplugged, says nothing about it.
+    pub fn probe(
+        &mut self,
+        backend: &impl AudioBackend,
+        endpoint: Option<&dyn AudioDevice>,
+    ) -> ServiceProbeOutcome {
+        let enumerates = backend
+            .devices(crate::types::DeviceType::Output)
+            .inspect_err(|e| log::warn!("Audio service probe failed: {e:#}"))
+            .is_ok();
+        let endpoint_alive = endpoint.is_none_or(|device| match device.volume() {
+            Ok(_) => true,
+            Err(e) if backend.is_service_error(&e) => {
+                log::warn!("Audio service probe of {} failed: {e:#}", device.name());
+                false
+            }
+            Err(_) => true,
+        });
+        if enumerates && endpoint_alive {
+            self.consecutive_failures = 0;
+            return ServiceProbeOutcome::Healthy;
+        }
+
+        self.consecutive_failures += 1;
+        if self.consecutive_failures < FAILURES_BEFORE_RECONNECT {
+            return ServiceProbeOutcome::Degraded;
+        }
+        match backend.reconnect() {
+            Ok(()) => {
+                self.consecutive_failures = 0;
+                ServiceProbeOutcome::Reconnected
+            }
+            Err(e) => {
+                log::warn!("Failed to reconnect to the audio service: {e:#}");
+                ServiceProbeOutcome::ReconnectFailed
+            }
+        }
+    }
+}
+
+pub enum ServiceWaitOutcome {
+    Connected,
+    /// No attempt was due, or it failed again.
+    Waiting,
+    /// The attempt failed often enough that the service should be reported
+    /// unavailable. Returned only once; attempts go on after it.
+    Unavailable(anyhow::Error),
+}
+
+/// Connects the backend to an audio service that is not up yet, as happens
+/// when the app is started at login, with a growing delay between attempts.
+pub struct AudioServiceWait {
+    backoff: Backoff,
+    next_attempt: Instant,
+}
+
+impl AudioServiceWait {
+    /// Makes the first attempt due right away.
+    pub fn new(initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            backoff: Backoff::new(initial_delay, max_delay),
+            next_attempt: Instant::now(),
+        }
+    }
+
+    pub fn next_attempt(&self) -> Instant {
+        self.next_attempt
+    }
+
+    /// Tries to connect `backend` if the next attempt is due.
+    pub fn attempt(&mut self, backend: &impl AudioBackend) -> ServiceWaitOutcome {
+        if Instant::now() < self.next_attempt {
+            return ServiceWaitOutcome::Waiting;
+        }
+        let Err(e) = backend.reconnect() else {
+            return ServiceWaitOutcome::Connected;
+        };
+        let delay = self.backoff.failed();
+        self.next_attempt = Instant::now() + delay;
+        log::warn!("Audio service is not available yet, retrying in {delay:?}: {e:#}");
+        if self.backoff.failures() == FAILURES_BEFORE_REPORT {
+            ServiceWaitOutcome::Unavailable(e)
+        } else {
+            ServiceWaitOutcome::Waiting
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::tests::{MockAudioBackend, MockDevice};
+    use crate::types::DeviceId;
+
+    #[test]
+    fn healthy_backend_is_never_reconnected() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("id1", "Speakers", true)]);
+        let mut monitor = AudioServiceMonitor::default();
+        for _ in 0..5 {
+            assert_eq!(monitor.probe(&backend, None), ServiceProbeOutcome::Healthy);
+        }
+        assert_eq!(*backend.reconnect_count.borrow(), 0);
+    }
+
+    #[test]
+    fn reconnects_after_repeated_failures() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("id1", "Speakers", true)]);
+        *backend.service_down.borrow_mut() = true;
+        let mut monitor = AudioServiceMonitor::default();
+
+        assert_eq!(monitor.probe(&backend, None), ServiceProbeOutcome::Degraded);
+        assert_eq!(*backend.reconnect_count.borrow(), 0);
+        assert_eq!(
+            monitor.probe(&backend, None),
+            ServiceProbeOutcome::Reconnected
+        );
+        assert_eq!(*backend.reconnect_count.borrow(), 1);
+        assert_eq!(monitor.probe(&backend, None), ServiceProbeOutcome::Healthy);
+    }
+
+    #[test]
+    fn single_failure_is_forgiven_after_success() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("id1", "Speakers", true)]);
+        let mut monitor = AudioServiceMonitor::default();
+
+        *backend.service_down.borrow_mut() = true;
+        assert_eq!(monitor.probe(&backend, None), ServiceProbeOutcome::Degraded);
+        *backend.service_down.borrow_mut() = false;
+        assert_eq!(monitor.probe(&backend, None), ServiceProbeOutcome::Healthy);
+        *backend.service_down.borrow_mut() = true;
+        assert_eq!(monitor.probe(&backend, None), ServiceProbeOutcome::Degraded);
+        assert_eq!(*backend.reconnect_count.borrow(), 0);
+    }
+
+    #[test]
+    fn reconnects_when_held_endpoints_die_but_enumeration_works() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("id1", "Speakers", true)]);
+        let endpoint = backend.device_by_id(&DeviceId::from("id1")).unwrap();
+        let mut monitor = AudioServiceMonitor::default();
+        assert_eq!(
+            monitor.probe(&backend, Some(endpoint.as_ref())),
+            ServiceProbeOutcome::Healthy
+        );
+
+        backend.lose_endpoints();
+        assert_eq!(
+            monitor.probe(&backend, Some(endpoint.as_ref())),
+            ServiceProbeOutcome::Degraded
+        );
+        assert_eq!(
+            monitor.probe(&backend, Some(endpoint.as_ref())),
+            ServiceProbeOutcome::Reconnected
+        );
+        // Devices handed out after the reconnect work again
+        let endpoint = backend.device_by_id(&DeviceId::from("id1")).unwrap();
+        assert_eq!(
+            monitor.probe(&backend, Some(endpoint.as_ref())),
+            ServiceProbeOutcome::Healthy
+        );
+    }
+
+    #[test]
+    fn failed_reconnect_is_retried_on_next_probe() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("id1", "Speakers", true)]);
+        *backend.service_down.borrow_mut() = true;
+        *backend.reconnect_fails.borrow_mut() = true;
+        let mut monitor = AudioServiceMonitor::default();
+
+        monitor.probe(&backend, None);
+        assert_eq!(
+            monitor.probe(&backend, None),
+            ServiceProbeOutcome::ReconnectFailed
+        );
+        *backend.reconnect_fails.borrow_mut() = false;
+        assert_eq!(
+            monitor.probe(&backend, None),
+            ServiceProbeOutcome::Reconnected
+        );
+        assert_eq!(*backend.reconnect_count.borrow(), 2);
+    }
+
+    #[test]
+    fn waits_for_the_service_and_reports_it_unavailable_once() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("id1", "Speakers", true)]);
+        *backend.reconnect_fails.borrow_mut() = true;
+        let mut wait = AudioServiceWait::new(Duration::ZERO, Duration::ZERO);
+
+        let mut reports = 0;
+        for _ in 0..FAILURES_BEFORE_REPORT * 2 {
+            match wait.attempt(&backend) {
+                ServiceWaitOutcome::Unavailable(_) => reports += 1,
+                ServiceWaitOutcome::Waiting => {}
+                ServiceWaitOutcome::Connected => panic!("connected while the service is down"),
+            }
+        }
+        assert_eq!(reports, 1);
+        assert_eq!(
+            *backend.reconnect_count.borrow(),
+            FAILURES_BEFORE_REPORT * 2
+        );
+
+        *backend.reconnect_fails.borrow_mut() = false;
+        assert!(matches!(
+            wait.attempt(&backend),
+            ServiceWaitOutcome::Connected
+        ));
+    }
+
+    #[test]
+    fn waits_out_the_delay_before_the_next_attempt() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("id1", "Speakers", true)]);
+        *backend.reconnect_fails.borrow_mut() = true;
+        let mut wait = AudioServiceWait::new(Duration::from_secs(60), Duration::from_secs(60));
+
+        assert!(matches!(
+            wait.attempt(&backend),
+            ServiceWaitOutcome::Waiting
+        ));
+        assert!(matches!(
+            wait.attempt(&backend),
+            ServiceWaitOutcome::Waiting
+        ));
+        assert_eq!(*backend.reconnect_count.borrow(), 1);
+        assert!(wait.next_attempt() > Instant::now());
+    }
+}
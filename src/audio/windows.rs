@@ -1,24 +1,28 @@
 #![allow(clippy::inline_always)]
 
-use super::{AudioBackend, AudioDevice, windows_com_policy_config};
+use super::{AudioBackend, AudioDevice, DeviceCapabilities, windows_com_policy_config};
 use crate::types::{DeviceId, DeviceRole, DeviceType, VolumeScalar};
+use anyhow::Context;
 use regex_lite::Regex;
 use std::ffi::OsStr;
 use std::os::windows::ffi::OsStrExt;
 use std::sync::{LazyLock, Mutex};
 use windows::Win32::Devices::FunctionDiscovery::PKEY_Device_FriendlyName;
-use windows::Win32::Foundation::PROPERTYKEY;
+use windows::Win32::Foundation::{
+    E_NOTIMPL, PROPERTYKEY, RPC_E_DISCONNECTED, RPC_E_SERVER_DIED, RPC_E_SERVER_DIED_DNE,
+};
 use windows::Win32::Media::Audio::Endpoints::{
     IAudioEndpointVolume, IAudioEndpointVolumeCallback, IAudioEndpointVolumeCallback_Impl,
 };
 use windows::Win32::Media::Audio::{
-    AUDIO_VOLUME_NOTIFICATION_DATA, DEVICE_STATE, DEVICE_STATE_ACTIVE, EDataFlow, ERole, IMMDevice,
-    IMMDeviceEnumerator, IMMNotificationClient, IMMNotificationClient_Impl, MMDeviceEnumerator,
-    eCapture, eCommunications, eConsole, eMultimedia, eRender,
+    AUDCLNT_E_SERVICE_NOT_RUNNING, AUDIO_VOLUME_NOTIFICATION_DATA, DEVICE_STATE,
+    DEVICE_STATE_ACTIVE, EDataFlow, ERole, IMMDevice, IMMDeviceEnumerator, IMMNotificationClient,
+    IMMNotificationClient_Impl, MMDeviceEnumerator, eCapture, eCommunications, eConsole,
+    eMultimedia, eRender,
 };
 use windows::Win32::System::Com::StructuredStorage::PropVariantToStringAlloc;
 use windows::Win32::System::Com::{CLSCTX_INPROC_SERVER, CoCreateInstance, STGM_READ};
-use windows::core::{PCWSTR, implement};
+use windows::core::{HRESULT, PCWSTR, implement};
 
 /// Encodes a string slice as a null-terminated UTF-16 wide string for Win32 APIs.
 fn encode_wide_null(s: &str) -> Vec<u16> {
@@ -29,25 +33,51 @@ fn encode_wide_null(s: &str) -> Vec<u16> {
 }
 
 pub struct WindowsAudioBackend {
-    enumerator: IMMDeviceEnumerator,
+    /// Set by `reconnect` once the audio service is up, and replaced by it
+    /// when the service restarts.
+    enumerator: Mutex<Option<IMMDeviceEnumerator>>,
     /// Prevents the COM callback from dropping — the field is written to in
     /// `register_device_change_callback` and must remain alive for the COM callback.
     device_change_callback: Mutex<Option<IMMNotificationClient>>,
 }
 
 impl WindowsAudioBackend {
-    pub fn new(_com_token: &crate::platform::ComToken) -> anyhow::Result<Self> {
-        let enumerator: IMMDeviceEnumerator =
-            // SAFETY: COM is initialized via CoInitializeEx (enforced by ComToken at construction);
-            // MMDeviceEnumerator is a well-known COM CLSID that returns a valid interface pointer.
-            unsafe { CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_INPROC_SERVER)? };
-        Ok(Self {
-            enumerator,
+    /// Creates a backend that is not connected to the audio service yet, which
+    /// may still be starting at login. `reconnect` connects it.
+    pub fn new(_com_token: &crate::platform::ComToken) -> Self {
+        Self {
+            enumerator: Mutex::new(None),
             device_change_callback: Mutex::new(None),
-        })
+        }
+    }
+
+    /// Returns a reference-counted handle to the current enumerator.
+    fn enumerator(&self) -> anyhow::Result<IMMDeviceEnumerator> {
+        // Recover from mutex poisoning — the enumerator itself is still valid.
+        let enumerator = match self.enumerator.lock() {
+            Ok(g) => g.clone(),
+            Err(e) => e.into_inner().clone(),
+        };
+        enumerator.context("not connected to the audio service yet")
     }
 }
 
+/// Errors returned by COM objects of an audio service that stopped or restarted.
+const SERVICE_ERRORS: [HRESULT; 5] = [
+    AUDCLNT_E_SERVICE_NOT_RUNNING,
+    RPC_E_DISCONNECTED,
+    RPC_E_SERVER_DIED,
+    RPC_E_SERVER_DIED_DNE,
+    // RPC_S_SERVER_UNAVAILABLE
+    HRESULT::from_win32(1722),
+];
+
+fn create_enumerator() -> anyhow::Result<IMMDeviceEnumerator> {
+    // SAFETY: COM is initialized via CoInitializeEx (enforced by ComToken at construction of
+    // the backend); MMDeviceEnumerator is a well-known COM CLSID that returns a valid interface pointer.
+    Ok(unsafe { CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_INPROC_SERVER)? })
+}
+
 pub struct WindowsAudioDevice {
     device: IMMDevice,
     endpoint: IAudioEndpointVolume,
@@ -81,7 +111,7 @@ impl AudioBackend for WindowsAudioBackend {
         };
         // SAFETY: enumerator obtained from CoCreateInstance; COM manages the returned collection.
         let collection = unsafe {
-            self.enumerator
+            self.enumerator()?
                 .EnumAudioEndpoints(endpoint_type, DEVICE_STATE_ACTIVE)?
         };
         // SAFETY: collection is a valid COM pointer from EnumAudioEndpoints above.
@@ -98,7 +128,7 @@ impl AudioBackend for WindowsAudioBackend {
     fn device_by_id(&self, id: &DeviceId) -> anyhow::Result<Box<dyn AudioDevice>> {
         let wide = encode_wide_null(id);
         // SAFETY: wide is a null-terminated UTF-16 string on the stack, valid for this call.
-        let device = unsafe { self.enumerator.GetDevice(PCWSTR(wide.as_ptr()))? };
+        let device = unsafe { self.enumerator()?.GetDevice(PCWSTR(wide.as_ptr()))? };
         Ok(Box::new(WindowsAudioDevice::new(device)?))
     }
 
@@ -118,7 +148,7 @@ impl AudioBackend for WindowsAudioBackend {
         };
         // SAFETY: COM was initialized via CoInitializeEx (guaranteed by ComToken);
         // enumerator is a valid COM pointer obtained from CoCreateInstance in new().
-        let device = unsafe { self.enumerator.GetDefaultAudioEndpoint(flow, role)? };
+        let device = unsafe { self.enumerator()?.GetDefaultAudioEndpoint(flow, role)? };
         Ok(Box::new(WindowsAudioDevice::new(device)?))
     }
 
@@ -148,9 +178,12 @@ impl AudioBackend for WindowsAudioBackend {
         callback: Box<dyn Fn() + Send + Sync>,
     ) -> anyhow::Result<()> {
         let cb: IMMNotificationClient = AudioDevicesChangedCallback { callback }.into();
-        // SAFETY: Both pointers are valid: enumerator from CoCreateInstance, callback from
-        // windows::core::implement. COM ref-counting keeps both alive for the registration duration.
-        unsafe { self.enumerator.RegisterEndpointNotificationCallback(&cb)? };
+        // Until the backend is connected, `reconnect` registers the stored callback
+        if let Ok(enumerator) = self.enumerator() {
+            // SAFETY: Both pointers are valid: enumerator from CoCreateInstance, callback from
+            // windows::core::implement. COM ref-counting keeps both alive for the registration duration.
+            unsafe { enumerator.RegisterEndpointNotificationCallback(&cb)? };
+        }
         // Recover from mutex poisoning — the callback must be stored regardless.
         let mut guard = match self.device_change_callback.lock() {
             Ok(g) => g,
@@ -159,6 +192,35 @@ impl AudioBackend for WindowsAudioBackend {
         *guard = Some(cb);
         Ok(())
     }
+
+    fn is_service_error(&self, error: &anyhow::Error) -> bool {
+        error
+            .downcast_ref::<windows::core::Error>()
+            .is_some_and(|e| SERVICE_ERRORS.contains(&e.code()))
+    }
+
+    fn reconnect(&self) -> anyhow::Result<()> {
+        let enumerator = create_enumerator()?;
+        let callback = match self.device_change_callback.lock() {
+            Ok(g) => g.clone(),
+            Err(e) => e.into_inner().clone(),
+        };
+        if let Some(cb) = &callback {
+            if let Ok(old) = self.enumerator() {
+                // SAFETY: The old registration belongs to an enumerator that may be disconnected
+                // from the restarted service, so failing to unregister it is expected and ignored.
+                let _ = unsafe { old.UnregisterEndpointNotificationCallback(cb) };
+            }
+            // SAFETY: Same as in register_device_change_callback; the stored callback is a valid
+            // COM object kept alive by device_change_callback.
+            unsafe { enumerator.RegisterEndpointNotificationCallback(cb)? };
+        }
+        match self.enumerator.lock() {
+            Ok(mut g) => *g = Some(enumerator),
+            Err(e) => *e.into_inner() = Some(enumerator),
+        }
+        Ok(())
+    }
 }
 
 impl AudioDevice for WindowsAudioDevice {
@@ -203,6 +265,23 @@ impl AudioDevice for WindowsAudioDevice {
         Ok(state == DEVICE_STATE_ACTIVE)
     }
 
+    /// Probes each control by reading it. `QueryHardwareSupport` isn't used
+    /// because Windows emulates volume and mute in software when the hardware
+    /// lacks them, so only a control that returns `E_NOTIMPL` is unsupported.
+    fn capabilities(&self) -> DeviceCapabilities {
+        // SAFETY: endpoint obtained from IMMDevice::Activate; COM manages its lifetime.
+        let (volume, mute) = unsafe {
+            (
+                self.endpoint.GetMasterVolumeLevelScalar().map(drop),
+                self.endpoint.GetMute().map(drop),
+            )
+        };
+        DeviceCapabilities {
+            volume: is_implemented(&volume),
+            mute: is_implemented(&mute),
+        }
+    }
+
     fn watch_volume(
         &self,
         callback: Box<dyn Fn(Option<VolumeScalar>) + Send + Sync>,
@@ -215,6 +294,10 @@ impl AudioDevice for WindowsAudioDevice {
     }
 }
 
+fn is_implemented(result: &windows::core::Result<()>) -> bool {
+    !matches!(result, Err(e) if e.code() == E_NOTIMPL)
+}
+
 #[implement(IMMNotificationClient)]
 pub struct AudioDevicesChangedCallback {
     pub callback: Box<dyn Fn() + Send + Sync>,
@@ -0,0 +1,611 @@
+use crate::storage::JsonlStore;
+use crate::types::{DeviceId, DeviceRole};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How many records are kept in memory for the event history.
+const MAX_HISTORY_ENTRIES: usize = 500;
+
+/// How large the event history file may grow before its oldest records are dropped.
+const MAX_HISTORY_FILE_BYTES: u64 = 1024 * 1024;
+
+/// What changed in the audio state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEvent {
+    #[serde(rename = "default_switch")]
+    DefaultDeviceSwitch,
+    VolumeRestore,
+    Unmute,
+    Forget,
+}
+
+impl AuditEvent {
+    pub const ALL: [AuditEvent; 4] = [
+        Self::DefaultDeviceSwitch,
+        Self::VolumeRestore,
+        Self::Unmute,
+        Self::Forget,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::DefaultDeviceSwitch => "Default device switch",
+            Self::VolumeRestore => "Volume restore",
+            Self::Unmute => "Unmute",
+            Self::Forget => "Forget device",
+        }
+    }
+}
+
+impl fmt::Display for AuditEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DefaultDeviceSwitch => f.write_str("default_switch"),
+            Self::VolumeRestore => f.write_str("volume_restore"),
+            Self::Unmute => f.write_str("unmute"),
+            Self::Forget => f.write_str("forget"),
+        }
+    }
+}
+
+/// Why the change was made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditTrigger {
+    PriorityList,
+    VolumeLock,
+    UnmuteLock,
+    User,
+}
+
+impl fmt::Display for AuditTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PriorityList => f.write_str("priority_list"),
+            Self::VolumeLock => f.write_str("volume_lock"),
+            Self::UnmuteLock => f.write_str("unmute_lock"),
+            Self::User => f.write_str("user"),
+        }
+    }
+}
+
+/// A before/after record of a change the app made to the audio state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub event: AuditEvent,
+    pub trigger: AuditTrigger,
+    pub device_id: DeviceId,
+    pub device_name: String,
+    pub role: Option<DeviceRole>,
+    pub before: String,
+    pub after: String,
+}
+
+/// Single-line `key=value` format. String values are quoted and escaped, so
+/// every record can be parsed back unambiguously from the log.
+impl fmt::Display for AuditRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "event={} trigger={} device={:?} device_id={:?}",
+            self.event, self.trigger, self.device_name, &*self.device_id
+        )?;
+        if let Some(role) = self.role {
+            write!(f, " role={role}")?;
+        }
+        write!(f, " before={:?} after={:?}", self.before, self.after)
+    }
+}
+
+/// An event history: the most recent records, oldest first, and the store they
+/// are persisted in. Any thread may record; the lock is only held to copy
+/// records in or out.
+#[derive(Default)]
+pub struct AuditLog {
+    history: Mutex<VecDeque<HistoryEntry>>,
+    store: OnceLock<JsonlStore>,
+}
+
+impl AuditLog {
+    pub const fn new() -> Self {
+        Self {
+            history: Mutex::new(VecDeque::new()),
+            store: OnceLock::new(),
+        }
+    }
+
+    /// Writes the record to the log under the `audit` target and keeps it in
+    /// the history.
+    pub fn record(&self, record: &AuditRecord) {
+        log::info!(target: "audit", "{record}");
+        let entry = HistoryEntry {
+            at: SystemTime::now(),
+            record: record.clone(),
+        };
+        if let Some(store) = self.store.get()
+            && let Err(e) = store.append(&entry)
+        {
+            log::warn!("Failed to persist event history record: {e:#}");
+        }
+        if let Ok(mut history) = self.history.lock() {
+            push_bounded(&mut history, entry);
+        }
+    }
+
+    /// Persists the history in `path` from now on and loads the records kept
+    /// there by earlier runs, ahead of the ones recorded so far.
+    pub fn open_store(&self, path: PathBuf) -> anyhow::Result<()> {
+        let store = JsonlStore::new(path, MAX_HISTORY_FILE_BYTES);
+        let stored: Vec<HistoryEntry> = store.load()?;
+        if let Ok(mut history) = self.history.lock() {
+            let current = std::mem::take(&mut *history);
+            for entry in stored.into_iter().chain(current) {
+                push_bounded(&mut history, entry);
+            }
+        }
+        if self.store.set(store).is_err() {
+            anyhow::bail!("the event history store is already open");
+        }
+        Ok(())
+    }
+
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        self.history
+            .lock()
+            .map(|history| history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Runs `f` with the app's event history. Under test, each test thread gets a
+/// history of its own, so tests only see the records they made.
+fn with_log<R>(f: impl FnOnce(&AuditLog) -> R) -> R {
+    #[cfg(not(test))]
+    {
+        static LOG: AuditLog = AuditLog::new();
+        f(&LOG)
+    }
+    #[cfg(test)]
+    {
+        thread_local! {
+            static LOG: AuditLog = const { AuditLog::new() };
+        }
+        LOG.with(f)
+    }
+}
+
+/// Records a change in the app's event history.
+pub fn record(record: &AuditRecord) {
+    with_log(|log| log.record(record));
+}
+
+/// Persists the app's event history in `path`, see [`AuditLog::open_store`].
+pub fn open_store(path: PathBuf) -> anyhow::Result<()> {
+    with_log(|log| log.open_store(path))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub at: SystemTime,
+    pub record: AuditRecord,
+}
+
+fn push_bounded(history: &mut VecDeque<HistoryEntry>, entry: HistoryEntry) {
+    if history.len() == MAX_HISTORY_ENTRIES {
+        history.pop_front();
+    }
+    history.push_back(entry);
+}
+
+/// The records in the app's event history, oldest first.
+pub fn history() -> Vec<HistoryEntry> {
+    with_log(AuditLog::history)
+}
+
+/// Narrows the event history to one kind of event and/or one device.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub event: Option<AuditEvent>,
+    pub device: Option<(DeviceId, String)>,
+}
+
+impl HistoryFilter {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        self.event.is_none_or(|event| event == record.event)
+            && self
+                .device
+                .as_ref()
+                .is_none_or(|(device_id, _)| *device_id == record.device_id)
+    }
+
+    fn describe(&self) -> String {
+        let event = self.event.map_or("All events", AuditEvent::label);
+        match &self.device {
+            Some((_, name)) => format!("{event} for {name}"),
+            None => event.to_string(),
+        }
+    }
+}
+
+/// What the event history window offers to filter by: every kind of event,
+/// and every device in the history plus the one a filter was opened with.
+#[derive(Debug)]
+pub struct HistoryChoices {
+    events: Vec<Option<AuditEvent>>,
+    devices: Vec<Option<(DeviceId, String)>>,
+}
+
+impl HistoryChoices {
+    pub fn new(entries: &[HistoryEntry], filter: &HistoryFilter) -> Self {
+        // Newest name per device, in case it was renamed
+        let mut names: HashMap<&DeviceId, &str> = HashMap::new();
+        for entry in entries {
+            names.insert(&entry.record.device_id, &entry.record.device_name);
+        }
+        let mut devices: Vec<(DeviceId, String)> = names
+            .into_iter()
+            .map(|(id, name)| (id.clone(), name.to_string()))
+            .collect();
+        if let Some((id, name)) = &filter.device
+            && !devices.iter().any(|(known, _)| known == id)
+        {
+            devices.push((id.clone(), name.clone()));
+        }
+        devices.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+        Self {
+            events: std::iter::once(None)
+                .chain(AuditEvent::ALL.map(Some))
+                .collect(),
+            devices: std::iter::once(None)
+                .chain(devices.into_iter().map(Some))
+                .collect(),
+        }
+    }
+
+    pub fn event_labels(&self) -> Vec<String> {
+        self.events
+            .iter()
+            .map(|event| event.map_or("All events", AuditEvent::label).to_string())
+            .collect()
+    }
+
+    pub fn device_labels(&self) -> Vec<String> {
+        self.devices
+            .iter()
+            .map(|device| {
+                device
+                    .as_ref()
+                    .map_or("All devices", |(_, name)| name)
+                    .to_string()
+            })
+            .collect()
+    }
+
+    /// Indices of the event and device choices that make up `filter`.
+    pub fn position_of(&self, filter: &HistoryFilter) -> [usize; 2] {
+        let event = self.events.iter().position(|&event| event == filter.event);
+        let device = self.devices.iter().position(|device| {
+            device.as_ref().map(|(id, _)| id) == filter.device.as_ref().map(|(id, _)| id)
+        });
+        [event.unwrap_or(0), device.unwrap_or(0)]
+    }
+
+    /// The filter made up of the event and device choices at `selected`.
+    pub fn filter_at(&self, selected: &[usize]) -> HistoryFilter {
+        let [event, device] = selected else {
+            return HistoryFilter::default();
+        };
+        HistoryFilter {
+            event: self.events.get(*event).copied().flatten(),
+            device: self.devices.get(*device).cloned().flatten(),
+        }
+    }
+}
+
+/// Renders the history as a plain-text timeline, newest first.
+pub fn render_timeline(entries: &[HistoryEntry], filter: &HistoryFilter) -> String {
+    let mut out = format!("Event history: {} (times in UTC)\n\n", filter.describe());
+    let mut any = false;
+    for entry in entries.iter().rev().filter(|e| filter.matches(&e.record)) {
+        any = true;
+        let record = &entry.record;
+        let role = record
+            .role
+            .map(|role| format!(" [{role}]"))
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "{}  {}  {}{role}  {} -> {}  ({})",
+            format_utc(entry.at),
+            record.event.label(),
+            record.device_name,
+            record.before,
+            record.after,
+            record.trigger,
+        );
+    }
+    if !any {
+        out.push_str("No events recorded yet.\n");
+    }
+    out
+}
+
+/// Formats a timestamp as `YYYY-MM-DD HH:MM:SS` in UTC.
+fn format_utc(at: SystemTime) -> String {
+    let secs = at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    // Civil-from-days conversion, see https://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// The records this test thread made in the app's event history.
+    pub fn recorded() -> Vec<AuditRecord> {
+        history().into_iter().map(|entry| entry.record).collect()
+    }
+
+    fn make_entry(event: AuditEvent, device_id: &str, secs: u64) -> HistoryEntry {
+        HistoryEntry {
+            at: UNIX_EPOCH + Duration::from_secs(secs),
+            record: AuditRecord {
+                event,
+                trigger: AuditTrigger::VolumeLock,
+                device_id: device_id.into(),
+                device_name: format!("Device {device_id}"),
+                role: None,
+                before: "35%".into(),
+                after: "50%".into(),
+            },
+        }
+    }
+
+    #[test]
+    fn format_utc_known_timestamps() {
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01 00:00:00");
+        assert_eq!(
+            format_utc(UNIX_EPOCH + Duration::from_secs(1_709_210_096)),
+            "2024-02-29 12:34:56"
+        );
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = VecDeque::new();
+        for i in 0..MAX_HISTORY_ENTRIES + 10 {
+            push_bounded(
+                &mut history,
+                make_entry(AuditEvent::Unmute, "mic", i as u64),
+            );
+        }
+        assert_eq!(history.len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(history[0].at, UNIX_EPOCH + Duration::from_secs(10));
+    }
+
+    /// Records `per_thread` entries from each of `threads` threads at once and
+    /// returns, per thread, the indexes of its records that were kept, in
+    /// history order.
+    fn record_concurrently(log: &AuditLog, threads: usize, per_thread: usize) -> Vec<Vec<usize>> {
+        std::thread::scope(|scope| {
+            for t in 0..threads {
+                scope.spawn(move || {
+                    for i in 0..per_thread {
+                        let id = format!("t{t}-{i}");
+                        log.record(&make_entry(AuditEvent::Unmute, &id, 0).record);
+                    }
+                });
+            }
+        });
+        let entries = log.history();
+        (0..threads)
+            .map(|t| {
+                let prefix = format!("t{t}-");
+                entries
+                    .iter()
+                    .filter_map(|e| e.record.device_id.strip_prefix(&prefix))
+                    .map(|i| i.parse().unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn concurrent_records_are_all_kept_in_order() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 25;
+        let log = AuditLog::new();
+
+        let kept = record_concurrently(&log, THREADS, PER_THREAD);
+
+        assert_eq!(log.history().len(), THREADS * PER_THREAD);
+        let expected: Vec<usize> = (0..PER_THREAD).collect();
+        for indexes in kept {
+            assert_eq!(indexes, expected);
+        }
+    }
+
+    #[test]
+    fn concurrent_records_past_the_bound_keep_the_newest() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = MAX_HISTORY_ENTRIES / 4;
+        let log = AuditLog::new();
+
+        let kept = record_concurrently(&log, THREADS, PER_THREAD);
+
+        assert_eq!(log.history().len(), MAX_HISTORY_ENTRIES);
+        // Only the oldest records of each thread were dropped
+        for indexes in kept {
+            let first = PER_THREAD - indexes.len();
+            assert_eq!(indexes, (first..PER_THREAD).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn stored_records_come_before_ones_made_before_opening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let earlier_run = AuditLog::new();
+        earlier_run.open_store(path.clone()).unwrap();
+        earlier_run.record(&make_entry(AuditEvent::Forget, "stored", 0).record);
+
+        let log = AuditLog::new();
+        log.record(&make_entry(AuditEvent::Unmute, "early", 0).record);
+        log.open_store(path.clone()).unwrap();
+        log.record(&make_entry(AuditEvent::Unmute, "late", 0).record);
+
+        let ids: Vec<_> = log
+            .history()
+            .into_iter()
+            .map(|e| e.record.device_id.to_string())
+            .collect();
+        assert_eq!(ids, ["stored", "early", "late"]);
+        assert!(log.open_store(path).is_err());
+    }
+
+    #[test]
+    fn each_test_thread_has_its_own_history() {
+        record(&make_entry(AuditEvent::Unmute, "this-thread", 0).record);
+        std::thread::spawn(|| assert!(history().is_empty()))
+            .join()
+            .unwrap();
+        let ids: Vec<_> = recorded().into_iter().map(|r| r.device_id).collect();
+        assert_eq!(ids, [DeviceId::from("this-thread")]);
+    }
+
+    #[test]
+    fn history_entry_roundtrips_through_json() {
+        let mut entry = make_entry(AuditEvent::DefaultDeviceSwitch, "dev1", 90);
+        entry.record.role = Some(DeviceRole::Communications);
+        entry.record.trigger = AuditTrigger::PriorityList;
+
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(json.contains(r#""event":"default_switch""#));
+        assert!(json.contains(r#""trigger":"priority_list""#));
+
+        let loaded: HistoryEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.at, entry.at);
+        assert_eq!(loaded.record, entry.record);
+    }
+
+    #[test]
+    fn timeline_is_newest_first_and_filtered() {
+        let entries = [
+            make_entry(AuditEvent::VolumeRestore, "a", 60),
+            make_entry(AuditEvent::Unmute, "a", 120),
+            make_entry(AuditEvent::VolumeRestore, "b", 180),
+        ];
+
+        let all = render_timeline(&entries, &HistoryFilter::default());
+        let lines: Vec<&str> = all.lines().skip(2).collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("1970-01-01 00:03:00  Volume restore  Device b"));
+        assert!(lines[2].ends_with("Device a  35% -> 50%  (volume_lock)"));
+
+        let filter = HistoryFilter {
+            event: Some(AuditEvent::VolumeRestore),
+            device: Some(("a".into(), "Device a".into())),
+        };
+        let filtered = render_timeline(&entries, &filter);
+        assert!(filtered.starts_with("Event history: Volume restore for Device a"));
+        assert_eq!(filtered.lines().skip(2).count(), 1);
+    }
+
+    #[test]
+    fn history_choices_round_trip_filters() {
+        let mut renamed = make_entry(AuditEvent::Unmute, "a", 240);
+        renamed.record.device_name = "Renamed a".into();
+        let entries = [
+            make_entry(AuditEvent::VolumeRestore, "b", 60),
+            make_entry(AuditEvent::VolumeRestore, "a", 120),
+            renamed,
+        ];
+        let forgotten = HistoryFilter {
+            event: None,
+            device: Some(("c".into(), "Device c".into())),
+        };
+        let choices = HistoryChoices::new(&entries, &forgotten);
+
+        assert_eq!(choices.event_labels().len(), AuditEvent::ALL.len() + 1);
+        assert_eq!(
+            choices.device_labels(),
+            ["All devices", "Device b", "Device c", "Renamed a"]
+        );
+        assert_eq!(choices.position_of(&forgotten), [0, 2]);
+        let unmutes = HistoryFilter {
+            event: Some(AuditEvent::Unmute),
+            device: None,
+        };
+        let position = choices.position_of(&unmutes);
+        let filter = choices.filter_at(&position);
+        assert_eq!(filter.event, Some(AuditEvent::Unmute));
+        assert!(filter.device.is_none());
+        assert!(choices.filter_at(&[99, 99]).device.is_none());
+    }
+
+    #[test]
+    fn timeline_without_matches_says_so() {
+        let filter = HistoryFilter {
+            event: Some(AuditEvent::Unmute),
+            device: None,
+        };
+        assert!(render_timeline(&[], &filter).contains("No events recorded yet."));
+    }
+
+    #[test]
+    fn default_switch_record_format() {
+        let record = AuditRecord {
+            event: AuditEvent::DefaultDeviceSwitch,
+            trigger: AuditTrigger::PriorityList,
+            device_id: "{0.0.0.00000000}.{abc}".into(),
+            device_name: "Speakers (USB Audio)".into(),
+            role: Some(DeviceRole::Console),
+            before: "{0.0.0.00000000}.{old}".into(),
+            after: "{0.0.0.00000000}.{abc}".into(),
+        };
+        assert_eq!(
+            record.to_string(),
+            r#"event=default_switch trigger=priority_list device="Speakers (USB Audio)" device_id="{0.0.0.00000000}.{abc}" role=Console before="{0.0.0.00000000}.{old}" after="{0.0.0.00000000}.{abc}""#
+        );
+    }
+
+    #[test]
+    fn record_without_role_escapes_quotes() {
+        let record = AuditRecord {
+            event: AuditEvent::VolumeRestore,
+            trigger: AuditTrigger::VolumeLock,
+            device_id: "mic".into(),
+            device_name: r#"Mic "Pro""#.into(),
+            role: None,
+            before: "35%".into(),
+            after: "50%".into(),
+        };
+        assert_eq!(
+            record.to_string(),
+            r#"event=volume_restore trigger=volume_lock device="Mic \"Pro\"" device_id="mic" before="35%" after="50%""#
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use crate::platform::registered_auto_launch_command;
+use std::path::{Path, PathBuf};
+
+/// What the startup check found about the auto-launch entry.
+#[derive(Debug, PartialEq, Eq)]
+pub enum AutoLaunchCheck {
+    /// No entry, or one that starts this executable.
+    Ok,
+    /// The entry starts an executable that no longer exists.
+    Stale(PathBuf),
+    /// The entry starts another copy of the app that still exists.
+    OtherCopy(PathBuf),
+}
+
+/// Compares the registered auto-launch entry named `app_name` with the
+/// running executable.
+pub fn check_auto_launch(app_name: &str, executable_path: &Path) -> AutoLaunchCheck {
+    let Some(command) = registered_auto_launch_command(app_name) else {
+        return AutoLaunchCheck::Ok;
+    };
+    classify(&command, executable_path, |path| path.exists())
+}
+
+fn classify(
+    command: &str,
+    executable_path: &Path,
+    exists: impl Fn(&Path) -> bool,
+) -> AutoLaunchCheck {
+    let target = command_target(command);
+    // Windows paths are case-insensitive
+    if target.to_string_lossy().to_lowercase() == executable_path.to_string_lossy().to_lowercase() {
+        AutoLaunchCheck::Ok
+    } else if exists(&target) {
+        AutoLaunchCheck::OtherCopy(target)
+    } else {
+        AutoLaunchCheck::Stale(target)
+    }
+}
+
+/// Extracts the executable from a Run entry command line, which may be quoted
+/// or, as written by the auto-launch crate, an unquoted path followed by arguments.
+fn command_target(command: &str) -> PathBuf {
+    let command = command.trim();
+    if let Some(quoted) = command.strip_prefix('"') {
+        return PathBuf::from(quoted.split('"').next().unwrap_or(quoted));
+    }
+    let end = command
+        .to_ascii_lowercase()
+        .find(".exe")
+        .map_or(command.len(), |position| position + ".exe".len());
+    PathBuf::from(&command[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXE: &str = r"C:\Apps\Volume Locker\VolumeLocker.exe";
+
+    #[test]
+    fn extracts_target_from_command_lines() {
+        assert_eq!(command_target(&format!("{EXE} ")), PathBuf::from(EXE));
+        assert_eq!(
+            command_target(&format!(r#"{EXE} --config-dir "D:\My Profile""#)),
+            PathBuf::from(EXE)
+        );
+        assert_eq!(
+            command_target(&format!(r#""{EXE}" --flag"#)),
+            PathBuf::from(EXE)
+        );
+    }
+
+    #[test]
+    fn matching_entry_is_ok_regardless_of_case() {
+        let command = EXE.to_uppercase();
+        assert_eq!(
+            classify(&command, Path::new(EXE), |_| true),
+            AutoLaunchCheck::Ok
+        );
+    }
+
+    #[test]
+    fn entry_for_moved_executable_is_stale() {
+        let old = r"C:\Old Place\VolumeLocker.exe";
+        assert_eq!(
+            classify(old, Path::new(EXE), |_| false),
+            AutoLaunchCheck::Stale(PathBuf::from(old))
+        );
+    }
+
+    #[test]
+    fn entry_for_existing_copy_is_reported() {
+        let other = r"D:\Portable\VolumeLocker.exe";
+        assert_eq!(
+            classify(other, Path::new(EXE), |_| true),
+            AutoLaunchCheck::OtherCopy(PathBuf::from(other))
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// A map whose entries expire after a fixed time-to-live and whose size is
+/// capped, so long-running processes don't accumulate stale keys forever.
+/// When full, the oldest entry is evicted to make room for a new one.
+pub struct TtlCache<K, V> {
+    entries: HashMap<K, (Instant, V)>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl<K: Eq + Hash + Clone, V> TtlCache<K, V> {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Returns the value for `key` if it was inserted less than `ttl` ago.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_at(key, Instant::now())
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns when `key` was inserted, if it hasn't expired yet.
+    #[cfg(test)]
+    pub fn inserted_at<Q>(&self, key: &Q) -> Option<Instant>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = Instant::now();
+        self.entries
+            .get(key)
+            .filter(|(at, _)| !self.is_expired(*at, now))
+            .map(|(at, _)| *at)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.insert_at(key, value, Instant::now());
+    }
+
+    /// Inserts `value` as if it had been inserted at `at`.
+    pub fn insert_at(&mut self, key: K, value: V, at: Instant) {
+        let now = Instant::now();
+        let ttl = self.ttl;
+        self.entries
+            .retain(|_, (inserted, _)| now.saturating_duration_since(*inserted) < ttl);
+
+        if !self.entries.contains_key(&key)
+            && self.entries.len() >= self.capacity
+            && let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (inserted, _))| *inserted)
+                .map(|(k, _)| k.clone())
+        {
+            self.entries.remove(&oldest);
+        }
+
+        self.entries.insert(key, (at, value));
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn get_at<Q>(&self, key: &Q, now: Instant) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries
+            .get(key)
+            .filter(|(at, _)| !self.is_expired(*at, now))
+            .map(|(_, value)| value)
+    }
+
+    fn is_expired(&self, inserted: Instant, now: Instant) -> bool {
+        now.saturating_duration_since(inserted) >= self.ttl
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn returns_fresh_entries() {
+        let mut cache = TtlCache::new(Duration::from_secs(5), 10);
+        cache.insert("a".to_string(), 1);
+        assert_eq!(cache.get("a"), Some(&1));
+        assert!(cache.contains_key("a"));
+    }
+
+    #[test]
+    fn hides_expired_entries() {
+        let mut cache = TtlCache::new(Duration::from_secs(5), 10);
+        cache.insert_at("a".to_string(), 1, Instant::now() - Duration::from_secs(6));
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.inserted_at("a"), None);
+    }
+
+    #[test]
+    fn insert_prunes_expired_entries() {
+        let mut cache = TtlCache::new(Duration::from_secs(5), 10);
+        let long_ago = Instant::now() - Duration::from_secs(60);
+        for key in ["a", "b", "c"] {
+            cache.insert_at(key.to_string(), (), long_ago);
+        }
+        cache.insert("d".to_string(), ());
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains_key("d"));
+    }
+
+    #[test]
+    fn evicts_oldest_entry_when_full() {
+        let mut cache = TtlCache::new(Duration::from_secs(60), 2);
+        let now = Instant::now();
+        cache.insert_at("old".to_string(), (), now - Duration::from_secs(2));
+        cache.insert_at("newer".to_string(), (), now - Duration::from_secs(1));
+        cache.insert("newest".to_string(), ());
+
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains_key("old"));
+        assert!(cache.contains_key("newer"));
+        assert!(cache.contains_key("newest"));
+    }
+
+    #[test]
+    fn reinserting_existing_key_does_not_evict() {
+        let mut cache = TtlCache::new(Duration::from_secs(60), 2);
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+        cache.insert("a".to_string(), 3);
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("a"), Some(&3));
+        assert_eq!(cache.get("b"), Some(&2));
+    }
+}
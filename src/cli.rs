@@ -0,0 +1,180 @@
+use crate::activation::{Route, is_route, parse_route};
+use crate::config::{read_state_file, save_state};
+use crate::ipc::{self, InstanceMessage};
+use crate::platform::SingleInstanceGuard;
+use crate::profile::instance_id;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+/// What the process should do, as requested on the command line.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Start the tray application (no arguments).
+    Run,
+    /// Validate a state file and install it as the app's configuration.
+    Apply { config_path: PathBuf },
+    /// Start the tray application and then follow an activation route.
+    Activate(Route),
+}
+
+/// Removes the global `--config-dir <path>` option from the arguments, which
+/// may appear anywhere, and returns its value with the remaining arguments.
+pub fn take_config_dir(
+    args: impl IntoIterator<Item = String>,
+) -> anyhow::Result<(Option<PathBuf>, Vec<String>)> {
+    let mut config_dir = None;
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config-dir" {
+            config_dir = Some(args.next().context("--config-dir requires a path")?);
+        } else if let Some(path) = arg.strip_prefix("--config-dir=") {
+            config_dir = Some(path.to_string());
+        } else {
+            rest.push(arg);
+        }
+    }
+    Ok((config_dir.map(PathBuf::from), rest))
+}
+
+/// Parses the command-line arguments, excluding the executable name.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Command> {
+    let mut args = args.into_iter();
+    let Some(command) = args.next() else {
+        return Ok(Command::Run);
+    };
+
+    if is_route(&command) {
+        if let Some(arg) = args.next() {
+            anyhow::bail!("unexpected argument after route: {arg}");
+        }
+        return Ok(Command::Activate(parse_route(&command)?));
+    }
+
+    match command.as_str() {
+        "apply" => {
+            let mut config_path = None;
+            while let Some(arg) = args.next() {
+                if arg == "--config" {
+                    config_path = Some(args.next().context("--config requires a path")?);
+                } else if let Some(path) = arg.strip_prefix("--config=") {
+                    config_path = Some(path.to_string());
+                } else {
+                    anyhow::bail!("unexpected argument for apply: {arg}");
+                }
+            }
+            let config_path = config_path.context("apply requires --config <path>")?;
+            Ok(Command::Apply {
+                config_path: PathBuf::from(config_path),
+            })
+        }
+        other => anyhow::bail!("unknown command: {other}"),
+    }
+}
+
+/// Validates the state file at `config_path` and installs it as the app's
+/// configuration. A running instance is handed the file, since it would
+/// otherwise overwrite it with its own state on next save; if there is none,
+/// the file is installed atomically on disk.
+pub fn apply_config(config_path: &Path) -> anyhow::Result<()> {
+    let state = read_state_file(config_path)?;
+
+    // The running instance reads the file from its own working directory
+    let absolute_path =
+        std::path::absolute(config_path).context("failed to resolve configuration path")?;
+    if ipc::forward(&InstanceMessage::Apply(absolute_path))? {
+        println!(
+            "Applied configuration from '{}' to the running instance ({} devices tracked)",
+            config_path.display(),
+            state.device_count()
+        );
+        return Ok(());
+    }
+
+    let _instance = SingleInstanceGuard::acquire(&instance_id())
+        .context("Volume Locker is starting up, try again in a moment")?;
+
+    save_state(&state).context("failed to install configuration")?;
+    println!(
+        "Applied configuration from '{}' ({} devices tracked)",
+        config_path.display(),
+        state.device_count()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn no_arguments_runs_app() {
+        assert_eq!(parse_args(args(&[])).unwrap(), Command::Run);
+    }
+
+    #[test]
+    fn apply_with_separate_config_path() {
+        assert_eq!(
+            parse_args(args(&["apply", "--config", "state.json"])).unwrap(),
+            Command::Apply {
+                config_path: PathBuf::from("state.json")
+            }
+        );
+    }
+
+    #[test]
+    fn apply_with_inline_config_path() {
+        assert_eq!(
+            parse_args(args(&["apply", "--config=C:\\state.json"])).unwrap(),
+            Command::Apply {
+                config_path: PathBuf::from("C:\\state.json")
+            }
+        );
+    }
+
+    #[test]
+    fn apply_without_config_is_an_error() {
+        assert!(parse_args(args(&["apply"])).is_err());
+        assert!(parse_args(args(&["apply", "--config"])).is_err());
+    }
+
+    #[test]
+    fn route_starts_app_with_activation() {
+        assert_eq!(
+            parse_args(args(&["volume-locker:action:pause/30m"])).unwrap(),
+            Command::Activate(Route::Pause(std::time::Duration::from_secs(30 * 60)))
+        );
+        assert!(parse_args(args(&["action:pause/soon"])).is_err());
+        assert!(parse_args(args(&["action:check-updates", "extra"])).is_err());
+    }
+
+    #[test]
+    fn config_dir_is_taken_from_anywhere() {
+        let (config_dir, rest) = take_config_dir(args(&[
+            "apply",
+            "--config-dir",
+            "D:\\rdp",
+            "--config=a.json",
+        ]))
+        .unwrap();
+        assert_eq!(config_dir, Some(PathBuf::from("D:\\rdp")));
+        assert_eq!(rest, args(&["apply", "--config=a.json"]));
+
+        let (config_dir, rest) = take_config_dir(args(&["--config-dir=profile"])).unwrap();
+        assert_eq!(config_dir, Some(PathBuf::from("profile")));
+        assert!(rest.is_empty());
+
+        assert_eq!(take_config_dir(args(&[])).unwrap(), (None, Vec::new()));
+        assert!(take_config_dir(args(&["--config-dir"])).is_err());
+    }
+
+    #[test]
+    fn unknown_command_is_an_error() {
+        assert!(parse_args(args(&["frobnicate"])).is_err());
+        assert!(parse_args(args(&["apply", "--force"])).is_err());
+    }
+}
@@ -1,14 +1,24 @@
 mod persistence;
-
-pub use persistence::{load_state, save_state};
+mod soundswitch;
+mod transaction;
+mod tunables;
+
+pub use persistence::{
+    load_state, offer_state_relink, read_state_file, remember_state_location, save_state,
+    save_state_to, state_file_path,
+};
+pub use soundswitch::{parse_soundswitch_config, soundswitch_config_path};
+pub use transaction::{ConfigDelta, transact};
+pub use tunables::Tunables;
 
 use crate::types::DeviceSettings;
 use crate::types::{DeviceId, DeviceType};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 /// Per-device-type preferences (one instance for output, one for input).
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub(crate) struct PerTypeSettings {
     pub priority_list: Vec<DeviceId>,
     pub notify_on_priority_restore: bool,
@@ -28,6 +38,18 @@ struct PersistentStateFlat {
     switch_communication_device_output: bool,
     switch_communication_device_input: bool,
     check_updates_on_launch: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    device_hook_url: Option<String>,
+    skip_registry_writes: bool,
+    minimal_footprint: bool,
+    eco_mode: bool,
+    mute_inputs: bool,
+    alert_live_mics: bool,
+    yield_to_conflicting_apps: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    acknowledged_conflicting_apps: Vec<String>,
+    #[serde(skip_serializing_if = "Tunables::is_default")]
+    tunables: Tunables,
 }
 
 impl Default for PersistentStateFlat {
@@ -52,6 +74,15 @@ impl From<PersistentStateFlat> for PersistentState {
                 switch_communication_device: flat.switch_communication_device_input,
             },
             check_updates_on_launch: flat.check_updates_on_launch,
+            device_hook_url: flat.device_hook_url,
+            skip_registry_writes: flat.skip_registry_writes,
+            minimal_footprint: flat.minimal_footprint,
+            eco_mode: flat.eco_mode,
+            mute_inputs: flat.mute_inputs,
+            alert_live_mics: flat.alert_live_mics,
+            yield_to_conflicting_apps: flat.yield_to_conflicting_apps,
+            acknowledged_conflicting_apps: flat.acknowledged_conflicting_apps,
+            tunables: flat.tunables,
         }
     }
 }
@@ -67,20 +98,58 @@ impl From<PersistentState> for PersistentStateFlat {
             switch_communication_device_output: state.output.switch_communication_device,
             switch_communication_device_input: state.input.switch_communication_device,
             check_updates_on_launch: state.check_updates_on_launch,
+            device_hook_url: state.device_hook_url,
+            skip_registry_writes: state.skip_registry_writes,
+            minimal_footprint: state.minimal_footprint,
+            eco_mode: state.eco_mode,
+            mute_inputs: state.mute_inputs,
+            alert_live_mics: state.alert_live_mics,
+            yield_to_conflicting_apps: state.yield_to_conflicting_apps,
+            acknowledged_conflicting_apps: state.acknowledged_conflicting_apps,
+            tunables: state.tunables,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(from = "PersistentStateFlat", into = "PersistentStateFlat")]
 pub struct PersistentState {
     pub(crate) devices: HashMap<DeviceId, DeviceSettings>,
     pub(crate) output: PerTypeSettings,
     pub(crate) input: PerTypeSettings,
     pub check_updates_on_launch: bool,
+    /// URL that receives a JSON POST when a priority list device appears or
+    /// disappears. Only configurable by editing the state file.
+    pub device_hook_url: Option<String>,
+    /// Skips registering the app's AUMID in the registry, at the cost of
+    /// notifications showing generic branding.
+    pub skip_registry_writes: bool,
+    /// Keeps the app from writing anything but its state file: no AUMID
+    /// registration, auto-launch entry or update download. Implies
+    /// `skip_registry_writes`.
+    pub minimal_footprint: bool,
+    /// Reduces background work while Windows battery saver is on.
+    pub eco_mode: bool,
+    /// Set by "Mute all inputs": unmute locks on input devices are not enforced,
+    /// so privacy wins over the lock until the user turns it off.
+    pub mute_inputs: bool,
+    /// Shows a notification the first time in a session that an unmute lock
+    /// unmutes each input device, so a microphone never goes live unnoticed.
+    pub alert_live_mics: bool,
+    /// Pauses priority enforcement while a known conflicting device switcher runs.
+    pub yield_to_conflicting_apps: bool,
+    /// Conflicting apps the user was already warned about, so the warning is shown once.
+    pub acknowledged_conflicting_apps: Vec<String>,
+    /// Timings only configurable by editing the state file.
+    pub tunables: Tunables,
 }
 
 impl PersistentState {
+    /// Whether the app may register its AUMID in the registry.
+    pub fn allows_registry_writes(&self) -> bool {
+        !self.skip_registry_writes && !self.minimal_footprint
+    }
+
     fn per_type(&self, dt: DeviceType) -> &PerTypeSettings {
         match dt {
             DeviceType::Output => &self.output,
@@ -123,6 +192,25 @@ impl PersistentState {
         self.devices.get(device_id)
     }
 
+    /// Returns the settings to enforce for a device, which leave out unmute
+    /// locks on input devices while all inputs are muted.
+    pub fn enforced_device_settings(
+        &self,
+        device_id: &DeviceId,
+    ) -> Option<Cow<'_, DeviceSettings>> {
+        let settings = self.devices.get(device_id)?;
+        if self.mute_inputs
+            && settings.device_type == DeviceType::Input
+            && settings.unmute_lock.is_locked
+        {
+            let mut settings = settings.clone();
+            settings.unmute_lock.is_locked = false;
+            Some(Cow::Owned(settings))
+        } else {
+            Some(Cow::Borrowed(settings))
+        }
+    }
+
     pub fn device_settings_mut(&mut self, device_id: &DeviceId) -> Option<&mut DeviceSettings> {
         self.devices.get_mut(device_id)
     }
@@ -162,13 +250,25 @@ impl PersistentState {
         self.devices.iter()
     }
 
+    /// Removes every trace of a device: its settings entry and its position in
+    /// both priority lists. Returns `true` if anything was removed.
+    pub fn forget_device(&mut self, device_id: &DeviceId) -> bool {
+        let mut removed = self.devices.remove(device_id).is_some();
+        for per_type in [&mut self.output, &mut self.input] {
+            let len_before = per_type.priority_list.len();
+            per_type.priority_list.retain(|id| id != device_id);
+            removed |= per_type.priority_list.len() != len_before;
+        }
+        removed
+    }
+
     /// Removes a device's settings entry if it has no active locks/notifications
     /// and is not referenced by any priority list.
     pub fn remove_device_if_unused(&mut self, device_id: &DeviceId) {
         let is_prunable = self
             .devices
             .get(device_id)
-            .is_some_and(|s| !s.has_active_locks_or_notifications());
+            .is_some_and(|s| !s.has_user_settings());
         if !is_prunable {
             return;
         }
@@ -193,6 +293,15 @@ impl Default for PersistentState {
                 ..PerTypeSettings::default()
             },
             check_updates_on_launch: true,
+            device_hook_url: None,
+            skip_registry_writes: false,
+            minimal_footprint: false,
+            eco_mode: false,
+            mute_inputs: false,
+            alert_live_mics: false,
+            yield_to_conflicting_apps: false,
+            acknowledged_conflicting_apps: Vec::new(),
+            tunables: Tunables::default(),
         }
     }
 }
@@ -217,6 +326,58 @@ mod tests {
         assert!(state.output.switch_communication_device);
         assert!(state.input.switch_communication_device);
         assert!(state.check_updates_on_launch);
+        assert!(!state.skip_registry_writes);
+        assert!(!state.minimal_footprint);
+        assert!(!state.eco_mode);
+        assert!(!state.mute_inputs);
+        assert!(!state.alert_live_mics);
+        assert!(!state.yield_to_conflicting_apps);
+        assert!(state.acknowledged_conflicting_apps.is_empty());
+        assert!(state.tunables.is_default());
+    }
+
+    #[test]
+    fn mute_inputs_suspends_only_input_unmute_locks() {
+        let mut state = PersistentState::default();
+        for (id, device_type) in [("mic", DeviceType::Input), ("spk", DeviceType::Output)] {
+            let mut settings = DeviceSettings::new(id.to_string(), device_type);
+            settings.unmute_lock.is_locked = true;
+            settings.volume_lock.is_locked = true;
+            state.devices.insert(id.into(), settings);
+        }
+        let enforced = |state: &PersistentState, id: &str| {
+            state
+                .enforced_device_settings(&id.into())
+                .unwrap()
+                .into_owned()
+        };
+
+        assert!(enforced(&state, "mic").unmute_lock.is_locked);
+
+        state.mute_inputs = true;
+        let mic = enforced(&state, "mic");
+        assert!(!mic.unmute_lock.is_locked);
+        assert!(mic.volume_lock.is_locked);
+        assert!(enforced(&state, "spk").unmute_lock.is_locked);
+        // The stored settings are untouched, so turning it off restores the lock
+        assert!(
+            state
+                .device_settings(&"mic".into())
+                .unwrap()
+                .unmute_lock
+                .is_locked
+        );
+    }
+
+    #[test]
+    fn minimal_footprint_disallows_registry_writes() {
+        let mut state = PersistentState::default();
+        assert!(state.allows_registry_writes());
+        state.minimal_footprint = true;
+        assert!(!state.allows_registry_writes());
+        state.minimal_footprint = false;
+        state.skip_registry_writes = true;
+        assert!(!state.allows_registry_writes());
     }
 
     #[test]
@@ -238,6 +399,7 @@ mod tests {
                     unmute_lock: UnmuteLockPolicy::default(),
                     device_type: DeviceType::Output,
                     name: "Test Device".into(),
+                    tag: None,
                 },
             )]),
             ..Default::default()
@@ -312,6 +474,24 @@ mod tests {
         assert!(state.switch_communication_device(DeviceType::Output));
     }
 
+    #[test]
+    fn forget_device_purges_settings_and_priority_lists() {
+        let mut state = PersistentState::default();
+        state.devices.insert(
+            "dev1".into(),
+            DeviceSettings::new("Dock".into(), DeviceType::Output),
+        );
+        state.output.priority_list = vec!["dev0".into(), "dev1".into()];
+        state.input.priority_list = vec!["dev1".into()];
+
+        assert!(state.forget_device(&"dev1".into()));
+        assert!(!state.devices.contains_key("dev1"));
+        assert_eq!(state.output.priority_list, vec!["dev0"]);
+        assert!(state.input.priority_list.is_empty());
+
+        assert!(!state.forget_device(&"dev1".into()));
+    }
+
     #[test]
     fn file_roundtrip_preserves_state() {
         let dir = std::env::temp_dir().join("volume_locker_test_roundtrip");
@@ -331,6 +511,18 @@ mod tests {
                 ..PerTypeSettings::default()
             },
             check_updates_on_launch: false,
+            device_hook_url: Some("http://localhost:8123/hook".into()),
+            skip_registry_writes: true,
+            minimal_footprint: true,
+            eco_mode: true,
+            mute_inputs: true,
+            alert_live_mics: true,
+            yield_to_conflicting_apps: true,
+            acknowledged_conflicting_apps: vec!["SoundSwitch.exe".into()],
+            tunables: Tunables {
+                check_interval_secs: 30,
+                ..Tunables::default()
+            },
             devices: HashMap::from([(
                 "dev_a".into(),
                 DeviceSettings {
@@ -345,6 +537,7 @@ mod tests {
                     },
                     device_type: DeviceType::Output,
                     name: "Speakers".into(),
+                    tag: None,
                 },
             )]),
         };
@@ -362,6 +555,18 @@ mod tests {
         assert!(loaded.output.notify_on_priority_restore);
         assert!(!loaded.input.switch_communication_device);
         assert!(!loaded.check_updates_on_launch);
+        assert_eq!(
+            loaded.device_hook_url.as_deref(),
+            Some("http://localhost:8123/hook")
+        );
+        assert!(loaded.skip_registry_writes);
+        assert!(loaded.minimal_footprint);
+        assert!(loaded.eco_mode);
+        assert!(loaded.mute_inputs);
+        assert!(loaded.alert_live_mics);
+        assert!(loaded.yield_to_conflicting_apps);
+        assert_eq!(loaded.acknowledged_conflicting_apps, ["SoundSwitch.exe"]);
+        assert_eq!(loaded.tunables.check_interval_secs, 30);
 
         let dev = loaded.devices.get("dev_a").unwrap();
         assert!(dev.volume_lock.is_locked);
@@ -1,24 +1,77 @@
 use super::PersistentState;
-use crate::consts::STATE_FILE_NAME;
-use crate::utils::get_executable_directory;
+use crate::consts::{APP_NAME, STATE_FILE_NAME};
+use crate::platform::{confirm, read_state_breadcrumb, write_state_breadcrumb};
+use crate::profile::data_directory;
 use anyhow::Context;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-fn get_state_file_path() -> anyhow::Result<PathBuf> {
-    Ok(get_executable_directory()?.join(STATE_FILE_NAME))
+pub fn state_file_path() -> anyhow::Result<PathBuf> {
+    Ok(data_directory()?.join(STATE_FILE_NAME))
 }
 
 pub fn save_state(state: &PersistentState) -> anyhow::Result<()> {
-    save_state_to(&get_state_file_path()?, state)
+    save_state_to(&state_file_path()?, state)
 }
 
 pub fn load_state() -> anyhow::Result<PersistentState> {
-    load_state_from(&get_state_file_path()?)
+    load_state_from(&state_file_path()?)
+}
+
+/// When there is no state file yet but the app last ran from another directory
+/// that has one, e.g. because the app folder was moved, offers to copy it here
+/// so the app doesn't appear to have been reset.
+pub fn offer_state_relink() -> anyhow::Result<()> {
+    let current_dir = data_directory()?;
+    let Some(previous_state) = relink_candidate(&current_dir, read_state_breadcrumb().as_deref())
+    else {
+        return Ok(());
+    };
+    let message = format!(
+        "{APP_NAME} has no settings in '{}' yet, but found settings from a previous location:\n\n{}\n\nCopy them here?",
+        current_dir.display(),
+        previous_state.display()
+    );
+    if !confirm("Settings Found", &message) {
+        log::info!("Not adopting state from {}", previous_state.display());
+        return Ok(());
+    }
+    // Validate before copying, so a broken file doesn't stop the app from starting
+    read_state_file(&previous_state)?;
+    fs::copy(&previous_state, current_dir.join(STATE_FILE_NAME))
+        .with_context(|| format!("failed to copy '{}'", previous_state.display()))?;
+    log::info!("Adopted state from {}", previous_state.display());
+    Ok(())
+}
+
+/// Returns the state file of `previous_dir` if it should be offered for
+/// `current_dir`: the directories differ, the current one has no state file
+/// and the previous one does.
+fn relink_candidate(current_dir: &Path, previous_dir: Option<&Path>) -> Option<PathBuf> {
+    let previous_dir = previous_dir?;
+    if previous_dir == current_dir || current_dir.join(STATE_FILE_NAME).exists() {
+        return None;
+    }
+    let previous_state = previous_dir.join(STATE_FILE_NAME);
+    previous_state.is_file().then_some(previous_state)
+}
+
+/// Records the current data directory, so a later move can be detected.
+pub fn remember_state_location() -> anyhow::Result<()> {
+    write_state_breadcrumb(&data_directory()?)
+}
+
+/// Reads and validates a state file that is expected to exist, e.g. one
+/// provided by the user on the command line.
+pub fn read_state_file(path: &Path) -> anyhow::Result<PersistentState> {
+    let data = fs::read_to_string(path)
+        .with_context(|| format!("failed to read state file '{}'", path.display()))?;
+    serde_json::from_str(&data)
+        .with_context(|| format!("failed to parse state file '{}'", path.display()))
 }
 
 /// Writes `state` to `path` via a temp file + rename for crash safety.
-pub(crate) fn save_state_to(path: &std::path::Path, state: &PersistentState) -> anyhow::Result<()> {
+pub fn save_state_to(path: &std::path::Path, state: &PersistentState) -> anyhow::Result<()> {
     let tmp_path = path.with_extension("json.tmp");
 
     let json = serde_json::to_string_pretty(state).context("failed to serialize state")?;
@@ -66,6 +119,40 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn relink_offers_previous_state_when_current_has_none() {
+        let current = TempDir::new().unwrap();
+        let previous = TempDir::new().unwrap();
+        fs::write(previous.path().join(STATE_FILE_NAME), "{}").unwrap();
+
+        assert_eq!(
+            relink_candidate(current.path(), Some(previous.path())),
+            Some(previous.path().join(STATE_FILE_NAME))
+        );
+    }
+
+    #[test]
+    fn relink_skipped_when_not_needed() {
+        let current = TempDir::new().unwrap();
+        let previous = TempDir::new().unwrap();
+
+        // No breadcrumb, same directory, or nothing to adopt
+        assert_eq!(relink_candidate(current.path(), None), None);
+        assert_eq!(relink_candidate(current.path(), Some(current.path())), None);
+        assert_eq!(
+            relink_candidate(current.path(), Some(previous.path())),
+            None
+        );
+
+        // The current directory already has its own state
+        fs::write(previous.path().join(STATE_FILE_NAME), "{}").unwrap();
+        fs::write(current.path().join(STATE_FILE_NAME), "{}").unwrap();
+        assert_eq!(
+            relink_candidate(current.path(), Some(previous.path())),
+            None
+        );
+    }
+
     #[test]
     fn load_creates_default_if_missing() {
         let dir = TempDir::new().unwrap();
@@ -124,6 +211,28 @@ mod tests {
         assert!(err_msg.contains("failed to parse state file"));
     }
 
+    #[test]
+    fn read_state_file_requires_existing_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("missing.json");
+
+        let err_msg = format!("{:#}", read_state_file(&path).unwrap_err());
+        assert!(err_msg.contains("failed to read state file"));
+    }
+
+    #[test]
+    fn read_state_file_parses_valid_state() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("state.json");
+
+        let mut state = PersistentState::default();
+        state.input.priority_list = vec!["mic".into()];
+        save_state_to(&path, &state).unwrap();
+
+        let loaded = read_state_file(&path).unwrap();
+        assert_eq!(loaded.input.priority_list, vec!["mic"]);
+    }
+
     #[test]
     fn save_state_uses_atomic_write() {
         let dir = TempDir::new().unwrap();
@@ -0,0 +1,250 @@
+use super::PersistentState;
+use crate::types::{DeviceId, DeviceType};
+use anyhow::Context;
+use serde_json::Value;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Returns where SoundSwitch keeps its settings, if `%APPDATA%` is known.
+pub fn soundswitch_config_path() -> Option<PathBuf> {
+    std::env::var_os("APPDATA").map(|appdata| {
+        PathBuf::from(appdata)
+            .join("SoundSwitch")
+            .join("SoundSwitchConfiguration.json")
+    })
+}
+
+/// Device preferences read from a SoundSwitch configuration.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SoundSwitchImport {
+    /// Selected playback devices, in SoundSwitch's cycling order.
+    pub output: Vec<(DeviceId, String)>,
+    /// Selected recording devices, in SoundSwitch's cycling order.
+    pub input: Vec<(DeviceId, String)>,
+    /// Whether SoundSwitch also switches the default communication device.
+    pub switch_communication_device: Option<bool>,
+}
+
+/// Parses a SoundSwitch configuration. Supports both the `SelectedDevices`
+/// list of current versions and the per-type ID lists of older ones.
+pub fn parse_soundswitch_config(json: &str) -> anyhow::Result<SoundSwitchImport> {
+    let config: Value =
+        serde_json::from_str(json).context("failed to parse SoundSwitch configuration")?;
+    let mut import = SoundSwitchImport {
+        switch_communication_device: config.get("ChangeCommunications").and_then(Value::as_bool),
+        ..SoundSwitchImport::default()
+    };
+
+    for device in config
+        .get("SelectedDevices")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let Some(id) = device.get("Id").and_then(Value::as_str) else {
+            continue;
+        };
+        let name = device.get("Name").and_then(Value::as_str).unwrap_or(id);
+        // NAudio's DataFlow enum, serialized either by value or by name
+        let list = match device.get("Type") {
+            Some(Value::Number(n)) if n.as_u64() == Some(0) => &mut import.output,
+            Some(Value::Number(n)) if n.as_u64() == Some(1) => &mut import.input,
+            Some(Value::String(s)) if matches!(s.as_str(), "Render" | "Playback") => {
+                &mut import.output
+            }
+            Some(Value::String(s)) if matches!(s.as_str(), "Capture" | "Recording") => {
+                &mut import.input
+            }
+            _ => continue,
+        };
+        push_unique(list, id, name);
+    }
+
+    for (key, device_type) in [
+        ("SelectedPlaybackDeviceListId", DeviceType::Output),
+        ("SelectedRecordingDeviceListId", DeviceType::Input),
+    ] {
+        let list = match device_type {
+            DeviceType::Output => &mut import.output,
+            DeviceType::Input => &mut import.input,
+        };
+        for id in config
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            push_unique(list, id, id);
+        }
+    }
+
+    Ok(import)
+}
+
+fn push_unique(list: &mut Vec<(DeviceId, String)>, id: &str, name: &str) {
+    if !list.iter().any(|(existing, _)| existing == id) {
+        list.push((id.into(), name.to_string()));
+    }
+}
+
+impl SoundSwitchImport {
+    pub fn is_empty(&self) -> bool {
+        self.output.is_empty() && self.input.is_empty()
+    }
+
+    fn devices(&self, device_type: DeviceType) -> &[(DeviceId, String)] {
+        match device_type {
+            DeviceType::Output => &self.output,
+            DeviceType::Input => &self.input,
+        }
+    }
+
+    /// Describes what [`Self::apply_to`] would change, for the confirmation prompt.
+    pub fn preview(&self) -> String {
+        let mut preview = String::new();
+        for (device_type, heading) in [
+            (DeviceType::Output, "Default output device priority"),
+            (DeviceType::Input, "Default input device priority"),
+        ] {
+            let devices = self.devices(device_type);
+            if devices.is_empty() {
+                continue;
+            }
+            let _ = writeln!(preview, "{heading}:");
+            for (position, (_, name)) in devices.iter().enumerate() {
+                let _ = writeln!(preview, "  {}. {name}", position + 1);
+            }
+            preview.push('\n');
+        }
+        if let Some(switch) = self.switch_communication_device {
+            let _ = writeln!(
+                preview,
+                "Also switch default communication device: {}",
+                if switch { "yes" } else { "no" }
+            );
+        }
+        preview.push_str(
+            "\nThese devices will be placed at the top of the priority lists, above the devices already in them.",
+        );
+        preview
+    }
+
+    /// Puts the imported devices at the top of the priority lists, keeping the
+    /// devices already in them below, and copies the communication preference.
+    pub fn apply_to(&self, state: &mut PersistentState) {
+        for device_type in [DeviceType::Output, DeviceType::Input] {
+            let devices = self.devices(device_type);
+            if devices.is_empty() {
+                continue;
+            }
+            let list = state.priority_list_mut(device_type);
+            list.retain(|id| !devices.iter().any(|(imported, _)| imported == id));
+            list.splice(0..0, devices.iter().map(|(id, _)| id.clone()));
+
+            for (id, name) in devices {
+                state.ensure_device_settings(id.clone(), name.clone(), device_type);
+            }
+            if let Some(switch) = self.switch_communication_device {
+                state.set_switch_communication_device(device_type, switch);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENT_CONFIG: &str = r#"{
+        "ChangeCommunications": false,
+        "SelectedDevices": [
+            {"Id": "{0.0.0.00000000}.{speakers}", "Name": "Speakers", "Type": 0},
+            {"Id": "{0.0.1.00000000}.{mic}", "Name": "Microphone", "Type": "Capture"},
+            {"Id": "{0.0.0.00000000}.{headset}", "Name": "Headset", "Type": "Render"},
+            {"Id": "{0.0.0.00000000}.{speakers}", "Name": "Speakers", "Type": 0},
+            {"Name": "No ID", "Type": 0}
+        ]
+    }"#;
+
+    #[test]
+    fn parses_selected_devices_in_order() {
+        let import = parse_soundswitch_config(CURRENT_CONFIG).unwrap();
+        assert_eq!(
+            import.output,
+            [
+                ("{0.0.0.00000000}.{speakers}".into(), "Speakers".to_string()),
+                ("{0.0.0.00000000}.{headset}".into(), "Headset".to_string()),
+            ]
+        );
+        assert_eq!(
+            import.input,
+            [("{0.0.1.00000000}.{mic}".into(), "Microphone".to_string())]
+        );
+        assert_eq!(import.switch_communication_device, Some(false));
+    }
+
+    #[test]
+    fn parses_legacy_id_lists() {
+        let import = parse_soundswitch_config(
+            r#"{"SelectedPlaybackDeviceListId": ["out1", "out2"], "SelectedRecordingDeviceListId": ["in1"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            import.output,
+            [
+                ("out1".into(), "out1".to_string()),
+                ("out2".into(), "out2".to_string())
+            ]
+        );
+        assert_eq!(import.input, [("in1".into(), "in1".to_string())]);
+        assert_eq!(import.switch_communication_device, None);
+    }
+
+    #[test]
+    fn empty_or_invalid_configs() {
+        assert!(parse_soundswitch_config("{}").unwrap().is_empty());
+        assert!(parse_soundswitch_config("not json").is_err());
+    }
+
+    #[test]
+    fn apply_puts_imported_devices_first() {
+        let mut state = PersistentState::default();
+        state
+            .priority_list_mut(DeviceType::Output)
+            .extend(["existing".into(), "{0.0.0.00000000}.{headset}".into()]);
+
+        let import = parse_soundswitch_config(CURRENT_CONFIG).unwrap();
+        import.apply_to(&mut state);
+
+        assert_eq!(
+            state.priority_list(DeviceType::Output),
+            [
+                "{0.0.0.00000000}.{speakers}",
+                "{0.0.0.00000000}.{headset}",
+                "existing"
+            ]
+        );
+        assert_eq!(
+            state.priority_list(DeviceType::Input),
+            ["{0.0.1.00000000}.{mic}"]
+        );
+        assert_eq!(
+            state
+                .device_settings(&"{0.0.1.00000000}.{mic}".into())
+                .unwrap()
+                .name,
+            "Microphone"
+        );
+        assert!(!state.switch_communication_device(DeviceType::Output));
+        assert!(!state.switch_communication_device(DeviceType::Input));
+    }
+
+    #[test]
+    fn preview_lists_devices_by_position() {
+        let preview = parse_soundswitch_config(CURRENT_CONFIG).unwrap().preview();
+        assert!(preview.contains("Default output device priority:\n  1. Speakers\n  2. Headset\n"));
+        assert!(preview.contains("Default input device priority:\n  1. Microphone\n"));
+        assert!(preview.contains("Also switch default communication device: no"));
+    }
+}
@@ -0,0 +1,224 @@
+use super::{PerTypeSettings, PersistentState};
+use crate::types::{DeviceId, DeviceType};
+
+/// Describes what changed in the persistent state during a transaction, so
+/// subsystems can react to the parts they care about instead of refreshing
+/// everything on every save.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigDelta {
+    /// Devices whose settings were added, removed or modified.
+    pub devices: Vec<DeviceId>,
+    /// Device types whose priority list or priority preferences changed.
+    pub priorities: Vec<DeviceType>,
+    /// Whether app-wide preferences changed.
+    pub app_preferences: bool,
+}
+
+impl ConfigDelta {
+    pub fn between(before: &PersistentState, after: &PersistentState) -> Self {
+        // Muting all inputs changes what is enforced on every input device
+        let mute_inputs_changed = before.mute_inputs != after.mute_inputs;
+        let mut devices: Vec<DeviceId> = before
+            .devices
+            .keys()
+            .chain(after.devices.keys())
+            .filter(|id| {
+                before.devices.get(*id) != after.devices.get(*id)
+                    || (mute_inputs_changed
+                        && after
+                            .devices
+                            .get(*id)
+                            .is_some_and(|settings| settings.device_type == DeviceType::Input))
+            })
+            .cloned()
+            .collect();
+        devices.sort();
+        devices.dedup();
+
+        // Yielding to conflicting apps pauses priorities of both types
+        let yield_changed = before.yield_to_conflicting_apps != after.yield_to_conflicting_apps;
+        let priorities = [DeviceType::Output, DeviceType::Input]
+            .into_iter()
+            .filter(|&device_type| {
+                yield_changed || before.per_type(device_type) != after.per_type(device_type)
+            })
+            .collect();
+
+        Self {
+            devices,
+            priorities,
+            app_preferences: app_preferences(before) != app_preferences(after),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty() && self.priorities.is_empty() && !self.app_preferences
+    }
+
+    /// Returns `true` if watched devices and priorities need to be re-evaluated.
+    pub fn affects_enforcement(&self) -> bool {
+        !self.devices.is_empty() || !self.priorities.is_empty()
+    }
+}
+
+/// Returns a copy of the state without device settings and per-type settings,
+/// so that any other field, including ones added later, counts as an app preference.
+fn app_preferences(state: &PersistentState) -> PersistentState {
+    let mut preferences = state.clone();
+    preferences.devices.clear();
+    preferences.output = PerTypeSettings::default();
+    preferences.input = PerTypeSettings::default();
+    preferences
+}
+
+/// Runs `f` against the persistent state and returns its result together with
+/// a [`ConfigDelta`] describing everything `f` changed.
+pub fn transact<R>(
+    state: &mut PersistentState,
+    f: impl FnOnce(&mut PersistentState) -> R,
+) -> (R, ConfigDelta) {
+    let snapshot = state.clone();
+    let result = f(state);
+    let delta = ConfigDelta::between(&snapshot, state);
+    (result, delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DeviceSettings;
+
+    #[test]
+    fn no_changes_yields_empty_delta() {
+        let mut state = PersistentState::default();
+        let ((), delta) = transact(&mut state, |_| {});
+        assert!(delta.is_empty());
+        assert!(!delta.affects_enforcement());
+    }
+
+    #[test]
+    fn device_changes_are_recorded() {
+        let mut state = PersistentState::default();
+        state.devices.insert(
+            "dev1".into(),
+            DeviceSettings::new("Speakers".into(), DeviceType::Output),
+        );
+        state.devices.insert(
+            "dev2".into(),
+            DeviceSettings::new("Headphones".into(), DeviceType::Output),
+        );
+
+        let ((), delta) = transact(&mut state, |state| {
+            state.devices.remove("dev1");
+            state.devices.get_mut("dev2").unwrap().volume_lock.is_locked = true;
+            state.devices.insert(
+                "dev3".into(),
+                DeviceSettings::new("Mic".into(), DeviceType::Input),
+            );
+        });
+
+        assert_eq!(delta.devices, vec!["dev1", "dev2", "dev3"]);
+        assert!(delta.priorities.is_empty());
+        assert!(!delta.app_preferences);
+        assert!(delta.affects_enforcement());
+    }
+
+    #[test]
+    fn priority_changes_are_recorded_per_type() {
+        let mut state = PersistentState::default();
+        let ((), delta) = transact(&mut state, |state| {
+            state
+                .priority_list_mut(DeviceType::Input)
+                .push("mic".into());
+        });
+
+        assert_eq!(delta.priorities, vec![DeviceType::Input]);
+        assert!(delta.devices.is_empty());
+        assert!(delta.affects_enforcement());
+    }
+
+    #[test]
+    fn app_preferences_do_not_affect_enforcement() {
+        let mut state = PersistentState::default();
+        let ((), delta) = transact(&mut state, |state| {
+            state.check_updates_on_launch = !state.check_updates_on_launch;
+        });
+
+        assert!(delta.app_preferences);
+        assert!(!delta.is_empty());
+        assert!(!delta.affects_enforcement());
+    }
+
+    #[test]
+    fn every_app_preference_is_recorded() {
+        let changes: [fn(&mut PersistentState); 11] = [
+            |s| s.check_updates_on_launch = !s.check_updates_on_launch,
+            |s| s.device_hook_url = Some("http://localhost/hook".into()),
+            |s| s.skip_registry_writes = !s.skip_registry_writes,
+            |s| s.minimal_footprint = !s.minimal_footprint,
+            |s| s.eco_mode = !s.eco_mode,
+            |s| s.mute_inputs = !s.mute_inputs,
+            |s| s.alert_live_mics = !s.alert_live_mics,
+            |s| s.yield_to_conflicting_apps = !s.yield_to_conflicting_apps,
+            |s| s.acknowledged_conflicting_apps.push("App.exe".into()),
+            |s| s.tunables.check_interval_secs += 1,
+            |s| s.tunables.notification_cooldown_secs += 1,
+        ];
+        for change in changes {
+            let mut state = PersistentState::default();
+            let ((), delta) = transact(&mut state, change);
+            assert!(delta.app_preferences, "change not recorded: {state:?}");
+            assert!(delta.devices.is_empty());
+        }
+    }
+
+    #[test]
+    fn device_and_priority_changes_are_not_app_preferences() {
+        let mut state = PersistentState::default();
+        let ((), delta) = transact(&mut state, |state| {
+            state.devices.insert(
+                "dev1".into(),
+                DeviceSettings::new("Speakers".into(), DeviceType::Output),
+            );
+            state
+                .priority_list_mut(DeviceType::Output)
+                .push("dev1".into());
+            state.set_notify_on_priority_restore(DeviceType::Input, true);
+        });
+
+        assert!(!delta.app_preferences);
+    }
+
+    #[test]
+    fn yield_to_conflicting_apps_affects_both_priorities() {
+        let mut state = PersistentState::default();
+        let ((), delta) = transact(&mut state, |state| {
+            state.yield_to_conflicting_apps = true;
+        });
+
+        assert_eq!(
+            delta.priorities,
+            vec![DeviceType::Output, DeviceType::Input]
+        );
+        assert!(delta.affects_enforcement());
+    }
+
+    #[test]
+    fn mute_inputs_affects_input_devices() {
+        let mut state = PersistentState::default();
+        state.devices.insert(
+            "mic".into(),
+            DeviceSettings::new("Mic".into(), DeviceType::Input),
+        );
+        state.devices.insert(
+            "spk".into(),
+            DeviceSettings::new("Speakers".into(), DeviceType::Output),
+        );
+        let ((), delta) = transact(&mut state, |state| {
+            state.mute_inputs = true;
+        });
+
+        assert_eq!(delta.devices, vec![DeviceId::from("mic")]);
+        assert!(delta.affects_enforcement());
+    }
+}
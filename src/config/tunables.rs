@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Timings that can be adjusted by editing the state file while the app is not
+/// running. Missing fields keep their defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tunables {
+    /// How long the same notification is held back after it was shown.
+    pub notification_cooldown_secs: u64,
+    /// How often background checks run: audio service health, conflicting
+    /// apps, pause expiry and notification retries.
+    pub check_interval_secs: u64,
+    /// How often background checks run while battery saver reduces background work.
+    pub battery_saver_check_interval_secs: u64,
+}
+
+impl Default for Tunables {
+    fn default() -> Self {
+        Self {
+            notification_cooldown_secs: 5,
+            check_interval_secs: 10,
+            battery_saver_check_interval_secs: 60,
+        }
+    }
+}
+
+impl Tunables {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn notification_cooldown(&self) -> Duration {
+        Duration::from_secs(self.notification_cooldown_secs)
+    }
+
+    /// Never shorter than a second, so a zero can't make the event loop spin.
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval_secs.max(1))
+    }
+
+    /// Never shorter than a second, so a zero can't make the event loop spin.
+    pub fn battery_saver_check_interval(&self) -> Duration {
+        Duration::from_secs(self.battery_saver_check_interval_secs.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_keep_defaults() {
+        let tunables: Tunables = serde_json::from_str(r#"{"check_interval_secs": 30}"#).unwrap();
+        assert_eq!(tunables.check_interval(), Duration::from_secs(30));
+        assert_eq!(tunables.notification_cooldown(), Duration::from_secs(5));
+        assert_eq!(
+            tunables.battery_saver_check_interval(),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn zero_intervals_are_clamped() {
+        let tunables = Tunables {
+            notification_cooldown_secs: 0,
+            check_interval_secs: 0,
+            battery_saver_check_interval_secs: 0,
+        };
+        assert_eq!(tunables.notification_cooldown(), Duration::ZERO);
+        assert_eq!(tunables.check_interval(), Duration::from_secs(1));
+        assert_eq!(
+            tunables.battery_saver_check_interval(),
+            Duration::from_secs(1)
+        );
+    }
+}
@@ -0,0 +1,54 @@
+/// Processes known to switch default devices or change volumes on their own,
+/// which makes priority enforcement fight them.
+const KNOWN_CONFLICTING_APPS: [&str; 3] =
+    ["SoundSwitch.exe", "EarTrumpet.exe", "NahimicService.exe"];
+
+/// Returns the known conflicting apps found among `running` process names.
+pub fn find_conflicting_apps<S: AsRef<str>>(running: &[S]) -> Vec<&'static str> {
+    KNOWN_CONFLICTING_APPS
+        .into_iter()
+        .filter(|app| {
+            running
+                .iter()
+                .any(|name| name.as_ref().eq_ignore_ascii_case(app))
+        })
+        .collect()
+}
+
+/// Returns the conflicting apps the user has not been warned about yet.
+pub fn unacknowledged<'a>(conflicts: &[&'a str], acknowledged: &[String]) -> Vec<&'a str> {
+    conflicts
+        .iter()
+        .copied()
+        .filter(|app| !acknowledged.iter().any(|a| a.eq_ignore_ascii_case(app)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_known_apps_case_insensitively() {
+        let running = ["explorer.exe", "soundswitch.exe", "EarTrumpet.exe"];
+        assert_eq!(
+            find_conflicting_apps(&running),
+            ["SoundSwitch.exe", "EarTrumpet.exe"]
+        );
+    }
+
+    #[test]
+    fn no_conflicts_when_none_running() {
+        assert!(find_conflicting_apps(&["explorer.exe", "VolumeLocker.exe"]).is_empty());
+    }
+
+    #[test]
+    fn unacknowledged_skips_already_warned_apps() {
+        let conflicts = ["SoundSwitch.exe", "EarTrumpet.exe"];
+        let acknowledged = vec!["soundswitch.exe".to_string()];
+        assert_eq!(
+            unacknowledged(&conflicts, &acknowledged),
+            ["EarTrumpet.exe"]
+        );
+    }
+}
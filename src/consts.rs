@@ -1,11 +1,30 @@
-pub const APP_NAME: &str = "Volume Locker";
-pub const APP_AUMID: &str = "FelipeSantos.VolumeLocker";
-pub const APP_UID: &str = "25fc6555-723f-414b-9fa0-b4b658d85b43";
+/// Returns the build-time override if set, the default otherwise. See `build.rs`.
+const fn branding(value: Option<&'static str>, default: &'static str) -> &'static str {
+    match value {
+        Some(value) => value,
+        None => default,
+    }
+}
+
+pub const APP_NAME: &str = branding(option_env!("VOLUME_LOCKER_APP_NAME"), "Volume Locker");
+pub const APP_AUMID: &str = branding(
+    option_env!("VOLUME_LOCKER_APP_AUMID"),
+    "FelipeSantos.VolumeLocker",
+);
+pub const APP_UID: &str = branding(
+    option_env!("VOLUME_LOCKER_APP_UID"),
+    "25fc6555-723f-414b-9fa0-b4b658d85b43",
+);
 pub const STATE_FILE_NAME: &str = "VolumeLockerState.json";
 pub const LOG_FILE_NAME: &str = "VolumeLocker.log";
-pub const PNG_ICON_BYTES: &[u8] = include_bytes!("../icons/volume-locked.png");
+pub const EVENTS_FILE_NAME: &str = "VolumeLockerEvents.jsonl";
+pub const PNG_ICON_BYTES: &[u8] =
+    include_bytes!(concat!(env!("BRANDING_ICONS_DIR"), "/volume-locked.png"));
 pub const PNG_ICON_FILE_NAME: &str = "VolumeLocker.png";
 
-pub const GITHUB_REPO_URL: &str = "https://github.com/felipecrs/volume-locker";
+pub const GITHUB_REPO_URL: &str = branding(
+    option_env!("VOLUME_LOCKER_REPO_URL"),
+    "https://github.com/felipecrs/volume-locker",
+);
 pub const GITHUB_RELEASE_ASSET: &str = "VolumeLocker.exe";
 pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,140 @@
+use simplelog::{Config, LevelFilter, SharedLogger};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long an error keeps the status at "Needs attention".
+const RECENT_ERROR_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// How long a volume change notification may take to arrive after the app
+/// wrote a volume before callbacks are considered to have stopped.
+const VOLUME_CALLBACK_GRACE: Duration = Duration::from_secs(30);
+
+/// The most recent error logged anywhere in the app, with when it happened.
+static LAST_ERROR: Mutex<Option<(Instant, String)>> = Mutex::new(None);
+
+/// Point-in-time summary of the enforcement subsystems, shown in the tray menu.
+pub struct HealthSnapshot {
+    pub watched_devices: usize,
+    pub locked_devices: usize,
+    pub last_enforcement: Option<Instant>,
+    pub last_error: Option<(Instant, String)>,
+    /// Since when a volume write is waiting for its change notification.
+    pub awaiting_volume_callback: Option<Instant>,
+}
+
+impl HealthSnapshot {
+    /// Healthy unless an error was logged recently or volume change
+    /// notifications stopped arriving.
+    pub fn is_healthy(&self, now: Instant) -> bool {
+        let recent_error = self
+            .last_error
+            .as_ref()
+            .is_some_and(|(at, _)| now.saturating_duration_since(*at) < RECENT_ERROR_WINDOW);
+        !recent_error && !self.volume_callbacks_stalled(now)
+    }
+
+    /// Whether the app changed a volume and its change notification never arrived.
+    pub fn volume_callbacks_stalled(&self, now: Instant) -> bool {
+        self.awaiting_volume_callback
+            .is_some_and(|since| now.saturating_duration_since(since) > VOLUME_CALLBACK_GRACE)
+    }
+}
+
+pub fn last_error() -> Option<(Instant, String)> {
+    LAST_ERROR.lock().ok().and_then(|guard| guard.clone())
+}
+
+/// Logger that remembers the last error record, so it can be surfaced in the menu.
+pub struct LastErrorLogger {
+    config: Config,
+}
+
+impl LastErrorLogger {
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            config: Config::default(),
+        })
+    }
+}
+
+impl log::Log for LastErrorLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Error
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata())
+            && let Ok(mut last_error) = LAST_ERROR.lock()
+        {
+            *last_error = Some((Instant::now(), record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for LastErrorLogger {
+    fn level(&self) -> LevelFilter {
+        LevelFilter::Error
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn log::Log> {
+        Box::new(*self)
+    }
+}
+
+/// Formats how long ago something happened, e.g. "5s ago" or "3h ago".
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0 => "just now".to_string(),
+        1..60 => format!("{secs}s ago"),
+        60..3600 => format!("{}m ago", secs / 60),
+        _ => format!("{}h ago", secs / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_elapsed_picks_largest_unit() {
+        assert_eq!(format_elapsed(Duration::from_millis(500)), "just now");
+        assert_eq!(format_elapsed(Duration::from_secs(42)), "42s ago");
+        assert_eq!(format_elapsed(Duration::from_secs(125)), "2m ago");
+        assert_eq!(format_elapsed(Duration::from_secs(7300)), "2h ago");
+    }
+
+    fn healthy_snapshot(now: Instant) -> HealthSnapshot {
+        HealthSnapshot {
+            watched_devices: 1,
+            locked_devices: 1,
+            last_enforcement: Some(now),
+            last_error: None,
+            awaiting_volume_callback: None,
+        }
+    }
+
+    #[test]
+    fn snapshot_is_unhealthy_after_recent_error() {
+        let now = Instant::now();
+        let mut snapshot = healthy_snapshot(now);
+        assert!(snapshot.is_healthy(now));
+        snapshot.last_error = Some((now, "boom".into()));
+        assert!(!snapshot.is_healthy(now));
+        assert!(snapshot.is_healthy(now + RECENT_ERROR_WINDOW));
+    }
+
+    #[test]
+    fn snapshot_is_unhealthy_when_volume_callbacks_stop() {
+        let now = Instant::now();
+        let mut snapshot = healthy_snapshot(now);
+        snapshot.awaiting_volume_callback = Some(now);
+        assert!(snapshot.is_healthy(now + VOLUME_CALLBACK_GRACE));
+        assert!(!snapshot.is_healthy(now + VOLUME_CALLBACK_GRACE * 2));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::audio::AudioBackend;
+use crate::config::PersistentState;
+use crate::types::{DeviceId, DeviceType};
+use crate::update::create_agent;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceLifecycle {
+    Activated,
+    Deactivated,
+}
+
+/// Payload posted to the device hook URL when a device from a priority list
+/// appears or disappears.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PriorityDeviceEvent {
+    pub event: DeviceLifecycle,
+    pub device_id: DeviceId,
+    pub device_name: String,
+    pub device_type: DeviceType,
+    /// One-based position of the device in its priority list.
+    pub priority: usize,
+}
+
+/// Tracks which priority list devices are active to detect transitions
+/// between device change notifications.
+#[derive(Default)]
+pub struct PriorityDeviceTracker {
+    /// Whether each device on a priority list was active at the previous check.
+    active: Option<HashMap<DeviceId, bool>>,
+}
+
+impl PriorityDeviceTracker {
+    /// Re-checks the priority list devices and returns the transitions since the
+    /// previous call. The first call only records the initial state, and so does
+    /// the first check of a device that was just added to a list.
+    pub fn update(
+        &mut self,
+        backend: &impl AudioBackend,
+        state: &PersistentState,
+    ) -> Vec<PriorityDeviceEvent> {
+        let mut entries = Vec::new();
+        for device_type in [DeviceType::Output, DeviceType::Input] {
+            for (index, device_id) in state.priority_list(device_type).iter().enumerate() {
+                let device = backend.device_by_id(device_id).ok();
+                let is_active = device
+                    .as_ref()
+                    .is_some_and(|d| d.is_active().unwrap_or(false));
+                let device_name = device.map(|d| d.name());
+                entries.push((device_id, device_type, index + 1, is_active, device_name));
+            }
+        }
+
+        let current: HashMap<DeviceId, bool> = entries
+            .iter()
+            .map(|(id, _, _, is_active, _)| ((*id).clone(), *is_active))
+            .collect();
+
+        let Some(previous) = self.active.replace(current) else {
+            return Vec::new();
+        };
+
+        entries
+            .into_iter()
+            .filter_map(
+                |(device_id, device_type, priority, is_active, device_name)| {
+                    let event = match (previous.get(device_id)?, is_active) {
+                        (false, true) => DeviceLifecycle::Activated,
+                        (true, false) => DeviceLifecycle::Deactivated,
+                        _ => return None,
+                    };
+                    let device_name = device_name
+                        .or_else(|| state.device_settings(device_id).map(|s| s.name.clone()))
+                        .unwrap_or_else(|| "Unknown Device".to_string());
+                    Some(PriorityDeviceEvent {
+                        event,
+                        device_id: device_id.clone(),
+                        device_name,
+                        device_type,
+                        priority,
+                    })
+                },
+            )
+            .collect()
+    }
+}
+
+/// Posts `event` as JSON to `url` on a background thread. Failures are logged.
+pub fn send_device_hook(url: &str, event: &PriorityDeviceEvent) {
+    let body = match serde_json::to_string(event) {
+        Ok(body) => body,
+        Err(e) => {
+            log::error!("Failed to serialize device hook payload: {e:#}");
+            return;
+        }
+    };
+    let url = url.to_string();
+    std::thread::spawn(move || {
+        let result = create_agent()
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .send(body.as_str());
+        if let Err(e) = result {
+            log::warn!("Failed to call device hook {url}: {e:#}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::tests::{MockAudioBackend, MockDevice, make_device_settings};
+
+    fn make_state() -> PersistentState {
+        let mut state = PersistentState::default();
+        state.output.priority_list = vec!["monitors".into(), "speakers".into()];
+        state.devices.insert(
+            "monitors".into(),
+            make_device_settings("Studio Monitors", DeviceType::Output),
+        );
+        state
+    }
+
+    #[test]
+    fn first_update_only_records_state() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("monitors", "Monitors", true)]);
+        let mut tracker = PriorityDeviceTracker::default();
+        assert!(tracker.update(&backend, &make_state()).is_empty());
+    }
+
+    #[test]
+    fn reports_activation_and_deactivation() {
+        let mut backend = MockAudioBackend::new(vec![
+            MockDevice::new("monitors", "Monitors", false),
+            MockDevice::new("speakers", "Speakers", true),
+        ]);
+        let state = make_state();
+        let mut tracker = PriorityDeviceTracker::default();
+        tracker.update(&backend, &state);
+
+        backend.devices[0].active = true;
+        assert_eq!(
+            tracker.update(&backend, &state),
+            vec![PriorityDeviceEvent {
+                event: DeviceLifecycle::Activated,
+                device_id: "monitors".into(),
+                device_name: "Monitors".into(),
+                device_type: DeviceType::Output,
+                priority: 1,
+            }]
+        );
+
+        // No transition, no events
+        assert!(tracker.update(&backend, &state).is_empty());
+
+        backend.devices[1].active = false;
+        let events = tracker.update(&backend, &state);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, DeviceLifecycle::Deactivated);
+        assert_eq!(events[0].device_id, "speakers");
+        assert_eq!(events[0].priority, 2);
+        assert_eq!(events[0].device_name, "Speakers");
+    }
+
+    #[test]
+    fn adding_a_connected_device_to_a_list_is_not_an_activation() {
+        let backend = MockAudioBackend::new(vec![
+            MockDevice::new("monitors", "Monitors", true),
+            MockDevice::new("headset", "Headset", true),
+        ]);
+        let mut state = make_state();
+        let mut tracker = PriorityDeviceTracker::default();
+        tracker.update(&backend, &state);
+
+        state.output.priority_list.push("headset".into());
+        assert!(tracker.update(&backend, &state).is_empty());
+    }
+
+    #[test]
+    fn missing_device_falls_back_to_remembered_name() {
+        let mut backend =
+            MockAudioBackend::new(vec![MockDevice::new("monitors", "Monitors", true)]);
+        let state = make_state();
+        let mut tracker = PriorityDeviceTracker::default();
+        tracker.update(&backend, &state);
+
+        backend.devices.clear();
+        let events = tracker.update(&backend, &state);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, DeviceLifecycle::Deactivated);
+        assert_eq!(events[0].device_name, "Studio Monitors");
+    }
+
+    #[test]
+    fn payload_serialization() {
+        let event = PriorityDeviceEvent {
+            event: DeviceLifecycle::Deactivated,
+            device_id: "id".into(),
+            device_name: "Speakers".into(),
+            device_type: DeviceType::Output,
+            priority: 2,
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"deactivated","device_id":"id","device_name":"Speakers","device_type":"Output","priority":2}"#
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Hands requests from a later launch over to the instance that is already
+//! running, through a named pipe scoped to the instance ID.
+
+use crate::activation::{Route, parse_route};
+use crate::config::read_state_file;
+use crate::platform::{send_to_named_pipe, serve_named_pipe};
+use crate::profile::instance_id;
+use crate::types::UserEvent;
+use std::path::PathBuf;
+use tao::event_loop::EventLoopProxy;
+
+/// Reply sent back once the running instance accepted a message.
+const ACCEPTED_REPLY: &str = "ok";
+/// Prefix of the reply sent back when the running instance rejected a message.
+const REJECTED_PREFIX: &str = "error ";
+
+/// A request from a later launch to the running instance.
+#[derive(Debug, PartialEq, Eq)]
+pub enum InstanceMessage {
+    /// Follow an activation route.
+    Activate(Route),
+    /// Install the state file at this absolute path as the configuration.
+    Apply(PathBuf),
+}
+
+impl InstanceMessage {
+    fn encode(&self) -> String {
+        match self {
+            InstanceMessage::Activate(route) => format!("activate {route}"),
+            InstanceMessage::Apply(path) => format!("apply {}", path.display()),
+        }
+    }
+
+    fn decode(line: &str) -> anyhow::Result<Self> {
+        match line.trim_end().split_once(' ') {
+            Some(("activate", route)) => Ok(InstanceMessage::Activate(parse_route(route)?)),
+            Some(("apply", path)) if !path.is_empty() => {
+                Ok(InstanceMessage::Apply(PathBuf::from(path)))
+            }
+            _ => anyhow::bail!("unknown request: {line}"),
+        }
+    }
+}
+
+/// Sends `message` to the running instance. Returns `Ok(false)` if no instance
+/// is listening, and an error if the running instance rejected the message.
+pub fn forward(message: &InstanceMessage) -> anyhow::Result<bool> {
+    let Some(reply) = send_to_named_pipe(&instance_id(), &message.encode())? else {
+        return Ok(false);
+    };
+    let reply = reply.trim_end();
+    if reply == ACCEPTED_REPLY {
+        return Ok(true);
+    }
+    match reply.strip_prefix(REJECTED_PREFIX) {
+        Some(error) => anyhow::bail!("Volume Locker is running and refused the request: {error}"),
+        None => anyhow::bail!("Volume Locker is running but did not answer the request"),
+    }
+}
+
+/// Listens for messages from later launches in the background and passes
+/// them on to the event loop.
+pub fn listen(proxy: EventLoopProxy<UserEvent>) -> anyhow::Result<()> {
+    serve_named_pipe(&instance_id(), move |request| {
+        let result = InstanceMessage::decode(request).and_then(|message| {
+            log::info!("Received from a later launch: {message:?}");
+            let event = match message {
+                InstanceMessage::Activate(route) => UserEvent::Activate(route),
+                // Rejecting an invalid file here lets the later launch report it
+                InstanceMessage::Apply(path) => {
+                    UserEvent::ApplyConfiguration(Box::new(read_state_file(&path)?))
+                }
+            };
+            proxy
+                .send_event(event)
+                .map_err(|_| anyhow::anyhow!("Volume Locker is shutting down"))
+        });
+        match result {
+            Ok(()) => ACCEPTED_REPLY.to_string(),
+            Err(e) => format!("{REJECTED_PREFIX}{e:#}"),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn messages_survive_encoding() {
+        for message in [
+            InstanceMessage::Activate(Route::Pause(Duration::from_secs(30 * 60))),
+            InstanceMessage::Activate(Route::OpenDevice("{0.0.1.00000000}.{a b}".into())),
+            InstanceMessage::Apply(PathBuf::from(r"C:\Users\Jane Doe\state.json")),
+        ] {
+            let line = format!("{}\n", message.encode());
+            assert_eq!(InstanceMessage::decode(&line).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn unknown_requests_are_rejected() {
+        assert!(InstanceMessage::decode("shutdown now").is_err());
+        assert!(InstanceMessage::decode("activate action:nope").is_err());
+        assert!(InstanceMessage::decode("apply ").is_err());
+        assert!(InstanceMessage::decode("").is_err());
+    }
+}
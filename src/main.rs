@@ -3,47 +3,83 @@
     windows_subsystem = "windows"
 )]
 
+mod activation;
 mod app;
 mod audio;
+mod audit;
+mod autostart;
+mod cache;
+mod cli;
 mod config;
+mod conflicts;
 mod consts;
+mod health;
+mod hooks;
+mod ipc;
 mod notification;
 mod platform;
+mod profile;
+mod storage;
 mod types;
 mod ui;
 mod update;
 mod utils;
 
+use crate::activation::{Route, URI_SCHEME_NAME};
 use crate::app::{AppState, EventLoopRefs};
-use crate::audio::AudioBackend;
 use crate::audio::AudioBackendImpl;
-use crate::config::load_state;
-use crate::consts::{APP_NAME, APP_UID, LOG_FILE_NAME};
-use crate::notification::NotificationThrottler;
+use crate::autostart::{AutoLaunchCheck, check_auto_launch};
+use crate::config::{load_state, offer_state_relink, remember_state_location, state_file_path};
+use crate::consts::{APP_NAME, EVENTS_FILE_NAME, LOG_FILE_NAME};
+use crate::health::LastErrorLogger;
+use crate::ipc::InstanceMessage;
+use crate::notification::{Notifier, ToastNotifier, retry_failed_notifications};
 use crate::platform::{
-    NotificationDuration, SingleInstanceGuard, init_platform, is_directory_writable,
-    send_notification,
+    NotificationDuration, SingleInstanceGuard, attach_parent_console, init_platform,
+    is_directory_writable, register_uri_scheme,
 };
-use crate::types::{TemporaryPriorities, UserEvent};
-use crate::ui::MenuIdMap;
-use crate::utils::{get_executable_directory, get_executable_path_str};
+use crate::profile::{config_dir, data_directory, instance_id};
+use crate::types::UserEvent;
+use crate::utils::{get_executable_directory, get_executable_path, get_executable_path_str};
 use anyhow::Context;
 use auto_launch::{AutoLaunch, AutoLaunchBuilder};
 #[cfg(debug_assertions)]
 use simplelog::{ColorChoice, TermLogger, TerminalMode};
 use simplelog::{CombinedLogger, Config, LevelFilter, SharedLogger, WriteLogger};
 use std::fs::File;
+use std::rc::Rc;
+use std::time::Instant;
 use tao::{
     event::Event,
     event_loop::{ControlFlow, EventLoopBuilder},
 };
 use tray_icon::{
-    MouseButton, MouseButtonState, TrayIconEvent,
+    TrayIconEvent,
     menu::{CheckMenuItem, Menu, MenuEvent, MenuItem},
 };
 
 fn main() -> std::process::ExitCode {
-    if let Err(e) = run() {
+    let result = cli::take_config_dir(std::env::args().skip(1)).and_then(|(config_dir, args)| {
+        profile::init(config_dir.as_deref())?;
+        match cli::parse_args(args)? {
+            cli::Command::Run => run(None),
+            // A running instance follows the route itself
+            cli::Command::Activate(route) => {
+                if ipc::forward(&InstanceMessage::Activate(route.clone()))? {
+                    Ok(())
+                } else {
+                    run(Some(route))
+                }
+            }
+            cli::Command::Apply { config_path } => {
+                attach_parent_console();
+                cli::apply_config(&config_path)
+            }
+        }
+    });
+    if let Err(e) = result {
+        // Logging may not be set up yet, e.g. for a bad argument or a failed apply
+        attach_parent_console();
         eprintln!("Fatal error: {e:#}");
         log::error!("Fatal error: {e:#}");
         return std::process::ExitCode::FAILURE;
@@ -51,14 +87,15 @@ fn main() -> std::process::ExitCode {
     std::process::ExitCode::SUCCESS
 }
 
-fn setup_logging(executable_directory: &std::path::Path) -> anyhow::Result<()> {
-    let log_path = executable_directory.join(LOG_FILE_NAME);
+fn setup_logging(data_directory: &std::path::Path) -> anyhow::Result<()> {
+    let log_path = data_directory.join(LOG_FILE_NAME);
     let loggers: Vec<Box<dyn SharedLogger>> = vec![
         WriteLogger::new(
             LevelFilter::Info,
             Config::default(),
             File::create(&log_path).context("failed to create log file")?,
         ),
+        LastErrorLogger::new(),
         #[cfg(debug_assertions)]
         TermLogger::new(
             LevelFilter::Info,
@@ -77,14 +114,17 @@ fn setup_logging(executable_directory: &std::path::Path) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn ensure_writable_directory(executable_directory: &std::path::Path) -> anyhow::Result<()> {
+fn ensure_writable_directory(
+    executable_directory: &std::path::Path,
+    notifier: &dyn Notifier,
+) -> anyhow::Result<()> {
     if !is_directory_writable(executable_directory) {
         let error_title = "Volume Locker Directory Not Writable";
         let error_message = format!(
             "Please move Volume Locker to a directory that is writable or fix the permissions of '{}'.",
             executable_directory.display(),
         );
-        let _ = send_notification(error_title, &error_message, NotificationDuration::Long);
+        notifier.notify_or_log(error_title, &error_message, NotificationDuration::Long);
         anyhow::bail!("{error_title}: {error_message}");
     }
     Ok(())
@@ -110,26 +150,148 @@ fn wire_event_proxies(event_loop: &tao::event_loop::EventLoop<UserEvent>) {
 
 fn create_auto_launch() -> anyhow::Result<AutoLaunch> {
     let app_path = get_executable_path_str()?;
-    AutoLaunchBuilder::new()
-        .set_app_name(APP_NAME)
-        .set_app_path(&app_path)
+    let mut builder = AutoLaunchBuilder::new();
+    builder.set_app_path(&app_path);
+    // Each config directory gets its own startup entry that passes it along
+    let app_name = match config_dir() {
+        Some(config_dir) => {
+            builder.set_args(&["--config-dir", &quote_path_arg(config_dir)]);
+            format!("{APP_NAME} ({})", instance_id())
+        }
+        None => APP_NAME.to_string(),
+    };
+    builder
+        .set_app_name(&app_name)
         .build()
         .context("failed to build auto-launch")
 }
 
-fn run() -> anyhow::Result<()> {
+/// Quotes a path for the single command line of the Run entry, so paths with
+/// spaces survive. A backslash right before the closing quote would escape it,
+/// so trailing separators are stripped; a drive root keeps its separator doubled.
+fn quote_path_arg(path: &std::path::Path) -> String {
+    let path = path.display().to_string();
+    let trimmed = path.trim_end_matches(['\\', '/']);
+    if trimmed.ends_with(':') {
+        format!("\"{trimmed}\\\\\"")
+    } else {
+        format!("\"{trimmed}\"")
+    }
+}
+
+/// Makes sure the auto-launch entry still starts this executable. A stale
+/// entry left behind by moving or renaming the app is repaired; one that
+/// starts another existing copy is left alone and reported.
+fn verify_auto_launch(auto_launch: &AutoLaunch, allow_repair: bool, notifier: &dyn Notifier) {
+    let executable_path = match get_executable_path() {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("Failed to verify auto-launch entry: {e:#}");
+            return;
+        }
+    };
+    match check_auto_launch(auto_launch.get_app_name(), &executable_path) {
+        AutoLaunchCheck::Ok => {}
+        AutoLaunchCheck::Stale(target) if allow_repair => {
+            log::info!(
+                "Auto-launch entry points to missing '{}', repairing",
+                target.display()
+            );
+            if let Err(e) = auto_launch.enable() {
+                notifier.log_and_notify_error(
+                    "Failed to Repair Auto-Launch",
+                    &format!(
+                        "{APP_NAME} will not start with Windows until auto-launch is turned on again: {e:#}"
+                    ),
+                );
+            }
+        }
+        AutoLaunchCheck::Stale(target) => {
+            log::warn!(
+                "Auto-launch entry points to missing '{}', not repairing since registry writes are disabled",
+                target.display()
+            );
+        }
+        AutoLaunchCheck::OtherCopy(target) => {
+            log::warn!("Auto-launch starts another copy at '{}'", target.display());
+            notifier.notify_or_log(
+                "Another Copy Starts with Windows",
+                &format!(
+                    "Auto-launch starts the copy of {APP_NAME} at '{}'. Turn auto-launch off and on again here to start this copy instead.",
+                    target.display()
+                ),
+                NotificationDuration::Long,
+            );
+        }
+    }
+}
+
+fn run(mut activation: Option<Route>) -> anyhow::Result<()> {
     let executable_directory = get_executable_directory()?;
-    setup_logging(&executable_directory)?;
+    let data_directory = data_directory()?;
+    setup_logging(&data_directory)?;
+    let notifier: Rc<dyn Notifier> = Rc::new(ToastNotifier);
 
-    let com_token = init_platform(&executable_directory)?;
-    ensure_writable_directory(&executable_directory)?;
+    // Nothing may read, move or write settings or registration until this
+    // instance is known to be the only one
     let _instance =
-        SingleInstanceGuard::acquire(APP_UID).context("failed to acquire single instance lock")?;
+        SingleInstanceGuard::acquire(&instance_id()).context(if activation.is_some() {
+            "Volume Locker is starting up and can't take the route yet, try again in a moment"
+        } else {
+            "failed to acquire single instance lock"
+        })?;
+
+    // An explicit --config-dir is never moved implicitly
+    if config_dir().is_none()
+        && let Err(e) = offer_state_relink()
+    {
+        notifier.log_and_notify_error(
+            "Failed to Copy Settings",
+            &format!("Failed to copy settings from the previous location: {e:#}"),
+        );
+    }
+
+    let persistent_state = load_state()
+        .context("failed to load preferences — exiting to prevent overwriting your preferences")?;
+    log::info!(
+        "Loaded state ({} devices tracked)",
+        persistent_state.device_count()
+    );
+
+    let com_token = init_platform(
+        &executable_directory,
+        persistent_state.allows_registry_writes(),
+    )?;
+    ensure_writable_directory(&data_directory, notifier.as_ref())?;
+    if config_dir().is_none() && persistent_state.allows_registry_writes() {
+        if let Err(e) = remember_state_location() {
+            log::warn!("Failed to remember state location: {e:#}");
+        }
+        if let Err(e) = get_executable_path()
+            .and_then(|executable| register_uri_scheme(URI_SCHEME_NAME, &executable))
+        {
+            log::warn!("Failed to register the {URI_SCHEME_NAME}: protocol: {e:#}");
+        }
+    }
+    // The event history is only kept in memory in minimal footprint mode
+    if !persistent_state.minimal_footprint
+        && let Err(e) = audit::open_store(data_directory.join(EVENTS_FILE_NAME))
+    {
+        log::warn!("Failed to open the event history store: {e:#}");
+    }
 
     let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build();
     wire_event_proxies(&event_loop);
+    if let Err(e) = ipc::listen(event_loop.create_proxy()) {
+        log::warn!("Failed to listen for requests from later launches: {e:#}");
+    }
 
     let auto_launch = create_auto_launch()?;
+    verify_auto_launch(
+        &auto_launch,
+        persistent_state.allows_registry_writes(),
+        notifier.as_ref(),
+    );
 
     let output_devices_heading_item = MenuItem::new("Output devices", false, None);
     let input_devices_heading_item = MenuItem::new("Input devices", false, None);
@@ -151,81 +313,60 @@ fn run() -> anyhow::Result<()> {
     let locked_icon = tray_icon::Icon::from_resource_name("volume-locked-icon", None)
         .context("failed to load locked icon")?;
 
-    #[cfg(target_os = "windows")]
-    let backend =
-        AudioBackendImpl::new(&com_token).context("failed to initialize audio backend")?;
-
-    let proxy = event_loop.create_proxy();
-    backend
-        .register_device_change_callback(Box::new(move || {
-            if let Err(e) = proxy.send_event(UserEvent::DevicesChanged) {
-                log::warn!("Failed to send DevicesChanged event: {e:#}");
-            }
-        }))
-        .context("failed to register device change callback")?;
-
     let main_proxy = event_loop.create_proxy();
-
-    let persistent_state = load_state()
-        .context("failed to load preferences — exiting to prevent overwriting your preferences")?;
-    log::info!(
-        "Loaded state ({} devices tracked)",
-        persistent_state.device_count()
-    );
-
-    let mut app = AppState {
+    #[cfg(target_os = "windows")]
+    let backend = AudioBackendImpl::new(&com_token);
+    let mut app = AppState::new(
         persistent_state,
-        menu_id_map: MenuIdMap::new(),
-        watched_devices: Vec::new(),
-        notification_throttler: NotificationThrottler::new(),
-        temporary_priorities: TemporaryPriorities::default(),
-        update_info: None,
-        tray_icon: None,
+        state_file_path()?,
         backend,
-    };
+        Rc::clone(&notifier),
+    );
+    app.watch_device_changes(&main_proxy)?;
+    app.wait_for_audio_service(&main_proxy);
+    let mut next_service_probe = Instant::now() + app.persistent_state.tunables.check_interval();
 
     event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Wait;
-
-        let make_refs = || EventLoopRefs {
-            auto_launch: &auto_launch,
-            auto_launch_check_item: &auto_launch_check_item,
-            check_updates_on_launch_item: &check_updates_on_launch_item,
-            quit_item: &quit_item,
-            tray_menu: &tray_menu,
-            output_devices_heading_item: &output_devices_heading_item,
-            input_devices_heading_item: &input_devices_heading_item,
-        };
+        if Instant::now() >= next_service_probe {
+            app.check_audio_service(&main_proxy);
+            app.refresh_conflicting_apps(&main_proxy);
+            app.resume_if_pause_expired(&main_proxy);
+            retry_failed_notifications();
+            app.refresh_power_mode(&main_proxy);
+            let tunables = &app.persistent_state.tunables;
+            let interval = if app.eco_active {
+                tunables.battery_saver_check_interval()
+            } else {
+                tunables.check_interval()
+            };
+            next_service_probe = Instant::now() + interval;
+        }
+        if let Some(attempt) = app.next_audio_service_attempt() {
+            next_service_probe = next_service_probe.min(attempt);
+        }
+        *control_flow = ControlFlow::WaitUntil(next_service_probe);
 
         match event {
             Event::NewEvents(tao::event::StartCause::Init) => {
                 app.handle_init(&tray_menu, &unlocked_icon, &main_proxy);
+                if let Some(route) = activation.take() {
+                    app.handle_activation(route);
+                }
             }
 
-            Event::UserEvent(UserEvent::Menu(event)) => {
-                let refs = make_refs();
-                app.handle_menu_click(&event, &refs, &main_proxy, control_flow);
-            }
-
-            Event::UserEvent(UserEvent::TrayIcon(TrayIconEvent::Click {
-                button,
-                button_state: MouseButtonState::Down,
-                ..
-            })) if button == MouseButton::Right || button == MouseButton::Left => {
-                let refs = make_refs();
-                app.handle_tray_click(&refs);
-            }
-
-            Event::UserEvent(UserEvent::VolumeChanged(event)) => {
-                app.handle_volume_changed(event);
-            }
-
-            Event::UserEvent(UserEvent::DevicesChanged) => {
-                app.handle_devices_changed(&main_proxy, &locked_icon, &unlocked_icon);
-            }
-
-            Event::UserEvent(UserEvent::ConfigurationChanged) => {
-                app.handle_configuration_changed(&main_proxy);
+            Event::UserEvent(event) => {
+                let refs = EventLoopRefs {
+                    auto_launch: &auto_launch,
+                    auto_launch_check_item: &auto_launch_check_item,
+                    check_updates_on_launch_item: &check_updates_on_launch_item,
+                    quit_item: &quit_item,
+                    tray_menu: &tray_menu,
+                    output_devices_heading_item: &output_devices_heading_item,
+                    input_devices_heading_item: &input_devices_heading_item,
+                    locked_icon: &locked_icon,
+                    unlocked_icon: &unlocked_icon,
+                };
+                app.handle_user_event(event, &refs, &main_proxy, control_flow);
             }
 
             _ => {}
@@ -258,6 +399,7 @@ mod tests {
             &device_id,
             &device,
             "Speaker",
+            "Speaker",
             lock,
             VolumeScalar::from(0.5_f32),
             &mut throttler,
@@ -277,6 +419,7 @@ mod tests {
             &device_id,
             &device,
             "Speaker",
+            "Speaker",
             lock,
             VolumeScalar::from(1.0_f32),
             &mut throttler,
@@ -285,4 +428,20 @@ mod tests {
         // Volume should remain unchanged since it already matches target
         assert_eq!(*device.volume.borrow(), 1.0_f32);
     }
+
+    #[test]
+    fn quoted_config_dir_never_ends_in_an_escaped_quote() {
+        use super::quote_path_arg;
+        use std::path::Path;
+
+        assert_eq!(
+            quote_path_arg(Path::new(r"C:\Profiles\Work")),
+            r#""C:\Profiles\Work""#
+        );
+        assert_eq!(
+            quote_path_arg(Path::new(r"C:\Profiles\Work\")),
+            r#""C:\Profiles\Work""#
+        );
+        assert_eq!(quote_path_arg(Path::new(r"D:\")), r#""D:\\""#);
+    }
 }
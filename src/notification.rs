@@ -1,57 +1,452 @@
-use crate::platform::{NotificationDuration, send_notification};
-use std::collections::HashMap;
+use crate::cache::TtlCache;
+use crate::config::Tunables;
+use crate::consts::APP_NAME;
+use crate::platform::{
+    NotificationDuration, is_quiet_time, send_notification, send_notification_now,
+    show_notification,
+};
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
-pub fn log_and_notify_error(title: &str, message: &str) {
-    log::error!("{message}");
-    if let Err(e) = send_notification(title, message, NotificationDuration::Long) {
-        log::error!("Failed to send error notification: {e:#}");
+/// Upper bound on remembered notification keys, to keep memory bounded.
+const MAX_THROTTLED_KEYS: usize = 256;
+/// Delay before the first retry of a failed toast; doubles after each failure.
+const RETRY_INITIAL_DELAY: Duration = Duration::from_secs(10);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(2 * 60);
+/// How many times a failed toast is retried before it is only logged.
+const MAX_RETRIES: u32 = 4;
+/// Upper bound on queued toasts; the oldest is dropped when full.
+const MAX_QUEUED_NOTIFICATIONS: usize = 16;
+/// After this many toasts in a row were given up on, toasts are considered
+/// unavailable (e.g. disabled by policy) and failures are only logged.
+const GIVE_UPS_BEFORE_UNAVAILABLE: u32 = 3;
+
+static RETRY_QUEUE: Mutex<RetryQueue> = Mutex::new(RetryQueue::new());
+
+/// Shows toasts on behalf of the throttler, so tests can record them instead.
+pub trait Notifier {
+    fn notify(
+        &self,
+        title: &str,
+        message: &str,
+        duration: NotificationDuration,
+    ) -> anyhow::Result<()>;
+
+    /// Shows a toast, only logging a failure since there is nothing else to do about it.
+    fn notify_or_log(&self, title: &str, message: &str, duration: NotificationDuration) {
+        if let Err(e) = self.notify(title, message, duration) {
+            log::error!("Failed to show notification for {title}: {e:#}");
+        }
+    }
+
+    /// Shows a short toast answering something the user just did. Unlike other
+    /// short toasts it is not held back during quiet time, since the user is
+    /// waiting for it.
+    fn respond(&self, title: &str, message: &str) {
+        self.notify_or_log(title, message, NotificationDuration::Short);
+    }
+
+    /// Logs an error and reports it with a long toast.
+    fn log_and_notify_error(&self, title: &str, message: &str) {
+        log::error!("{message}");
+        if let Err(e) = self.notify(title, message, NotificationDuration::Long) {
+            log::error!("Failed to send error notification: {e:#}");
+        }
+    }
+}
+
+/// Shows toasts through the platform, honoring quiet time and retrying failures.
+pub struct ToastNotifier;
+
+impl Notifier for ToastNotifier {
+    fn notify(
+        &self,
+        title: &str,
+        message: &str,
+        duration: NotificationDuration,
+    ) -> anyhow::Result<()> {
+        send_notification(title, message, duration)
+    }
+
+    fn respond(&self, title: &str, message: &str) {
+        if let Err(e) = send_notification_now(title, message, NotificationDuration::Short) {
+            log::error!("Failed to show notification for {title}: {e:#}");
+        }
     }
 }
 
 /// Manages debounced notifications, preventing repeated notifications within a cooldown period.
-#[derive(Default)]
 pub struct NotificationThrottler {
-    last_times: HashMap<String, Instant>,
+    notifier: Rc<dyn Notifier>,
+    last_times: TtlCache<String, ()>,
+    /// While set, notifications are collected and shown together by [`Self::flush_batch`].
+    batching: bool,
+    batch: Vec<(String, String)>,
+}
+
+impl Default for NotificationThrottler {
+    fn default() -> Self {
+        Self::with_cooldown(Tunables::default().notification_cooldown())
+    }
 }
 
 impl NotificationThrottler {
+    /// Creates a throttler with the default cooldown whose toasts are only
+    /// recorded, so tests never show real ones.
+    #[cfg(test)]
     pub fn new() -> Self {
-        Self::default()
+        Self::with_notifier(
+            Tunables::default().notification_cooldown(),
+            Rc::new(tests::RecordingNotifier::default()),
+        )
+    }
+
+    /// Creates a throttler that suppresses a notification key for `cooldown` after it was shown.
+    pub fn with_cooldown(cooldown: Duration) -> Self {
+        Self::with_notifier(cooldown, Rc::new(ToastNotifier))
+    }
+
+    pub fn with_notifier(cooldown: Duration, notifier: Rc<dyn Notifier>) -> Self {
+        Self {
+            notifier,
+            last_times: TtlCache::new(cooldown, MAX_THROTTLED_KEYS),
+            batching: false,
+            batch: Vec::new(),
+        }
     }
 
     /// Returns `true` if the given key has not been seen within the cooldown period.
     pub fn should_notify(&mut self, key: &str) -> bool {
-        use std::collections::hash_map::Entry;
-        let now = Instant::now();
-        match self.last_times.entry(key.to_string()) {
-            Entry::Occupied(mut e) => {
-                if now.duration_since(*e.get()) > Duration::from_secs(5) {
-                    e.insert(now);
-                    true
-                } else {
-                    false
-                }
-            }
-            Entry::Vacant(e) => {
-                e.insert(now);
-                true
-            }
+        if self.last_times.contains_key(key) {
+            return false;
         }
+        self.last_times.insert(key.to_string(), ());
+        true
     }
 
     pub fn send_if_not_throttled(&mut self, key: &str, title: &str, message: &str) {
-        if self.should_notify(key)
-            && let Err(e) = send_notification(title, message, NotificationDuration::Short)
+        if !self.should_notify(key) {
+            return;
+        }
+        if self.batching {
+            self.batch.push((title.to_string(), message.to_string()));
+        } else if let Err(e) = self
+            .notifier
+            .notify(title, message, NotificationDuration::Short)
         {
             log::error!("Failed to show notification for {title}: {e:#}");
         }
     }
+
+    /// Starts or stops collecting notifications into a batch. Stopping shows
+    /// whatever was collected.
+    pub fn set_batching(&mut self, batching: bool) {
+        self.batching = batching;
+        if !batching {
+            self.flush_batch();
+        }
+    }
+
+    /// Shows the collected notifications as a single one.
+    pub fn flush_batch(&mut self) {
+        let Some((title, message)) = summarize_batch(&std::mem::take(&mut self.batch)) else {
+            return;
+        };
+        if let Err(e) = self
+            .notifier
+            .notify(&title, &message, NotificationDuration::Short)
+        {
+            log::error!("Failed to show batched notification: {e:#}");
+        }
+    }
+}
+
+/// Combines batched notifications into one title and message.
+fn summarize_batch(batch: &[(String, String)]) -> Option<(String, String)> {
+    match batch {
+        [] => None,
+        [(title, message)] => Some((title.clone(), message.clone())),
+        _ => Some((
+            format!("{} {APP_NAME} Events", batch.len()),
+            batch
+                .iter()
+                .map(|(_, message)| message.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )),
+    }
+}
+
+struct PendingNotification {
+    title: String,
+    message: String,
+    duration: NotificationDuration,
+    retries: u32,
+    next_attempt: Instant,
+}
+
+/// Failed toasts waiting to be shown again, with exponential backoff.
+struct RetryQueue {
+    pending: VecDeque<PendingNotification>,
+    consecutive_give_ups: u32,
+    /// Short toasts held back during quiet time, shown together once it ends.
+    held: VecDeque<(String, String)>,
+}
+
+impl RetryQueue {
+    const fn new() -> Self {
+        Self {
+            pending: VecDeque::new(),
+            consecutive_give_ups: 0,
+            held: VecDeque::new(),
+        }
+    }
+
+    /// Holds a short toast back until quiet time ends. The oldest is dropped when full.
+    fn hold(&mut self, title: &str, message: &str) {
+        if self.held.len() >= MAX_QUEUED_NOTIFICATIONS
+            && let Some((title, message)) = self.held.pop_front()
+        {
+            log_undelivered(&title, &message);
+        }
+        self.held
+            .push_back((title.to_string(), message.to_string()));
+    }
+
+    fn is_unavailable(&self) -> bool {
+        self.consecutive_give_ups >= GIVE_UPS_BEFORE_UNAVAILABLE
+    }
+
+    /// A toast was shown, so toasts work again after being considered unavailable.
+    fn record_success(&mut self) {
+        if self.is_unavailable() {
+            log::info!("Notifications are available again");
+        }
+        self.consecutive_give_ups = 0;
+    }
+
+    fn push(&mut self, title: &str, message: &str, duration: NotificationDuration, now: Instant) {
+        if self.is_unavailable() {
+            log_undelivered(title, message);
+            return;
+        }
+        if self.pending.len() >= MAX_QUEUED_NOTIFICATIONS
+            && let Some(dropped) = self.pending.pop_front()
+        {
+            log_undelivered(&dropped.title, &dropped.message);
+        }
+        self.pending.push_back(PendingNotification {
+            title: title.to_string(),
+            message: message.to_string(),
+            duration,
+            retries: 0,
+            next_attempt: now + RETRY_INITIAL_DELAY,
+        });
+    }
+
+    /// Removes and returns the notifications whose next attempt is due.
+    fn take_due(&mut self, now: Instant) -> Vec<PendingNotification> {
+        let (due, waiting) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|pending| pending.next_attempt <= now);
+        self.pending = waiting;
+        due.into()
+    }
+
+    /// Records the outcome of a retry, requeueing it with a longer delay or
+    /// giving up on it once it ran out of retries.
+    fn record_attempt(&mut self, mut pending: PendingNotification, shown: bool, now: Instant) {
+        if shown {
+            self.record_success();
+            return;
+        }
+        pending.retries += 1;
+        if pending.retries >= MAX_RETRIES {
+            self.consecutive_give_ups += 1;
+            log_undelivered(&pending.title, &pending.message);
+            if self.is_unavailable() {
+                log::warn!("Notifications seem unavailable, only logging them from now on");
+                for pending in self.pending.drain(..) {
+                    log_undelivered(&pending.title, &pending.message);
+                }
+            }
+            return;
+        }
+        let delay = RETRY_INITIAL_DELAY
+            .saturating_mul(1 << pending.retries)
+            .min(RETRY_MAX_DELAY);
+        pending.next_attempt = now + delay;
+        self.pending.push_back(pending);
+    }
+}
+
+fn log_undelivered(title: &str, message: &str) {
+    log::warn!("Notification could not be shown: {title}: {message}");
+}
+
+/// Queues a toast that failed to show so it is retried later.
+pub fn queue_for_retry(title: &str, message: &str, duration: NotificationDuration) {
+    if let Ok(mut queue) = RETRY_QUEUE.lock() {
+        queue.push(title, message, duration, Instant::now());
+    }
+}
+
+/// Holds a short toast back until quiet time ends, see [`retry_failed_notifications`].
+pub fn hold_until_quiet_time_ends(title: &str, message: &str) {
+    if let Ok(mut queue) = RETRY_QUEUE.lock() {
+        queue.hold(title, message);
+    }
+}
+
+/// Records that a toast was shown, so failures are queued for retry again if
+/// toasts had been considered unavailable.
+pub fn notification_succeeded() {
+    if let Ok(mut queue) = RETRY_QUEUE.lock() {
+        queue.record_success();
+    }
+}
+
+/// Shows again the queued toasts whose retry is due, and once quiet time is
+/// over, the toasts held back during it as a single one. Called periodically
+/// from the event loop.
+pub fn retry_failed_notifications() {
+    let now = Instant::now();
+    let quiet = is_quiet_time();
+    let (due, held) = match RETRY_QUEUE.lock() {
+        Ok(mut queue) => {
+            let (held_back, due): (Vec<_>, Vec<_>) =
+                queue.take_due(now).into_iter().partition(|pending| {
+                    quiet && matches!(pending.duration, NotificationDuration::Short)
+                });
+            for pending in held_back {
+                queue.hold(&pending.title, &pending.message);
+            }
+            let held = if quiet {
+                Vec::new()
+            } else {
+                queue.held.drain(..).collect()
+            };
+            (due, held)
+        }
+        Err(_) => return,
+    };
+    // The lock is not held while showing toasts, since a failure would queue again
+    if let Some((title, message)) = summarize_batch(&held) {
+        log::info!(
+            "Quiet time is over, showing {} held notifications",
+            held.len()
+        );
+        if let Err(e) = send_notification(&title, &message, NotificationDuration::Short) {
+            log::error!("Failed to show held notifications: {e:#}");
+        }
+    }
+    if due.is_empty() {
+        return;
+    }
+    let outcomes: Vec<_> = due
+        .into_iter()
+        .map(|pending| {
+            let shown = show_notification(&pending.title, &pending.message, pending.duration)
+                .inspect_err(|e| {
+                    log::warn!("Retry of notification {} failed: {e:#}", pending.title);
+                })
+                .is_ok();
+            (pending, shown)
+        })
+        .collect();
+    if let Ok(mut queue) = RETRY_QUEUE.lock() {
+        for (pending, shown) in outcomes {
+            queue.record_attempt(pending, shown, now);
+        }
+    }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Notifier that keeps the title and message of every toast instead of
+    /// showing it. Clones share the same record.
+    #[derive(Clone, Default)]
+    pub(crate) struct RecordingNotifier {
+        shown: Rc<RefCell<Vec<(String, String)>>>,
+    }
+
+    impl RecordingNotifier {
+        pub(crate) fn titles(&self) -> Vec<String> {
+            self.shown
+                .borrow()
+                .iter()
+                .map(|(title, _)| title.clone())
+                .collect()
+        }
+
+        pub(crate) fn messages(&self) -> Vec<String> {
+            self.shown
+                .borrow()
+                .iter()
+                .map(|(_, message)| message.clone())
+                .collect()
+        }
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn notify(
+            &self,
+            title: &str,
+            message: &str,
+            _duration: NotificationDuration,
+        ) -> anyhow::Result<()> {
+            self.shown
+                .borrow_mut()
+                .push((title.to_string(), message.to_string()));
+            Ok(())
+        }
+    }
+
+    /// Returns a throttler with the default cooldown and a handle to what it shows.
+    pub(crate) fn recording_throttler() -> (NotificationThrottler, RecordingNotifier) {
+        let notifier = RecordingNotifier::default();
+        let throttler = NotificationThrottler::with_notifier(
+            Tunables::default().notification_cooldown(),
+            Rc::new(notifier.clone()),
+        );
+        (throttler, notifier)
+    }
+
+    #[test]
+    fn throttler_shows_repeated_key_once_within_cooldown() {
+        let (mut throttler, notifier) = recording_throttler();
+        for _ in 0..3 {
+            throttler.send_if_not_throttled("a", "Volume Restored", "Speaker restored");
+        }
+        throttler.send_if_not_throttled("b", "Unmuted", "Microphone unmuted");
+        assert_eq!(notifier.titles(), ["Volume Restored", "Unmuted"]);
+    }
+
+    #[test]
+    fn throttler_shows_batch_as_one_toast_when_batching_stops() {
+        let (mut throttler, notifier) = recording_throttler();
+        throttler.set_batching(true);
+        throttler.send_if_not_throttled("a", "Volume Restored", "Speaker restored");
+        throttler.send_if_not_throttled("b", "Unmuted", "Microphone unmuted");
+        assert!(notifier.titles().is_empty());
+
+        throttler.set_batching(false);
+        assert_eq!(notifier.titles(), [format!("2 {APP_NAME} Events")]);
+        assert_eq!(
+            notifier.messages(),
+            ["Speaker restored\nMicrophone unmuted"]
+        );
+
+        // Nothing left to show
+        throttler.flush_batch();
+        assert_eq!(notifier.titles().len(), 1);
+    }
 
     #[test]
     fn throttler_records_key_on_first_send() {
@@ -64,24 +459,177 @@ mod tests {
     #[test]
     fn throttler_suppresses_within_cooldown() {
         let mut throttler = NotificationThrottler::new();
-        throttler.last_times.insert(
+        throttler.last_times.insert_at(
             "test_key".to_string(),
+            (),
             Instant::now() - Duration::from_secs(1),
         );
-        let before = *throttler.last_times.get("test_key").unwrap();
+        let before = throttler.last_times.inserted_at("test_key").unwrap();
         assert!(!throttler.should_notify("test_key"));
-        assert_eq!(*throttler.last_times.get("test_key").unwrap(), before);
+        assert_eq!(
+            throttler.last_times.inserted_at("test_key").unwrap(),
+            before
+        );
     }
 
     #[test]
     fn throttler_allows_after_cooldown_elapsed() {
         let mut throttler = NotificationThrottler::new();
-        throttler.last_times.insert(
-            "test_key".to_string(),
-            Instant::now() - Duration::from_secs(10),
-        );
-        let before = *throttler.last_times.get("test_key").unwrap();
+        let before = Instant::now() - Duration::from_secs(10);
+        throttler
+            .last_times
+            .insert_at("test_key".to_string(), (), before);
         assert!(throttler.should_notify("test_key"));
-        assert_ne!(*throttler.last_times.get("test_key").unwrap(), before);
+        assert_ne!(
+            throttler.last_times.inserted_at("test_key").unwrap(),
+            before
+        );
+    }
+
+    #[test]
+    fn throttler_memory_is_bounded() {
+        let mut throttler = NotificationThrottler::new();
+        for i in 0..(MAX_THROTTLED_KEYS * 4) {
+            assert!(throttler.should_notify(&format!("volume_restore_{i}")));
+        }
+        assert_eq!(throttler.last_times.len(), MAX_THROTTLED_KEYS);
+    }
+
+    #[test]
+    fn throttler_batches_while_batching() {
+        let mut throttler = NotificationThrottler::new();
+        throttler.set_batching(true);
+        throttler.send_if_not_throttled("a", "Volume Restored", "Speaker restored to 50%");
+        throttler.send_if_not_throttled("a", "Volume Restored", "Speaker restored to 50%");
+        throttler.send_if_not_throttled("b", "Unmuted", "Microphone unmuted");
+        assert_eq!(throttler.batch.len(), 2);
+    }
+
+    #[test]
+    fn batch_summary_combines_messages() {
+        assert_eq!(summarize_batch(&[]), None);
+        let single = [("Title".to_string(), "Message".to_string())];
+        assert_eq!(
+            summarize_batch(&single),
+            Some(("Title".to_string(), "Message".to_string()))
+        );
+        let batch = [
+            (
+                "Volume Restored".to_string(),
+                "Speaker restored".to_string(),
+            ),
+            ("Unmuted".to_string(), "Microphone unmuted".to_string()),
+        ];
+        assert_eq!(
+            summarize_batch(&batch),
+            Some((
+                format!("2 {APP_NAME} Events"),
+                "Speaker restored\nMicrophone unmuted".to_string()
+            ))
+        );
+    }
+
+    fn queued(queue: &mut RetryQueue, now: Instant) -> PendingNotification {
+        queue.push("Title", "Message", NotificationDuration::Short, now);
+        queue
+            .take_due(now + RETRY_INITIAL_DELAY)
+            .pop()
+            .expect("notification should be due")
+    }
+
+    #[test]
+    fn retry_queue_waits_before_first_retry() {
+        let mut queue = RetryQueue::new();
+        let now = Instant::now();
+        queue.push("Title", "Message", NotificationDuration::Short, now);
+        assert!(queue.take_due(now).is_empty());
+        assert_eq!(queue.take_due(now + RETRY_INITIAL_DELAY).len(), 1);
+        assert!(queue.pending.is_empty());
+    }
+
+    #[test]
+    fn retry_queue_backs_off_and_gives_up() {
+        let mut queue = RetryQueue::new();
+        let now = Instant::now();
+        let mut pending = queued(&mut queue, now);
+        let mut delays = Vec::new();
+        for _ in 1..MAX_RETRIES {
+            queue.record_attempt(pending, false, now);
+            pending = queue.pending.pop_front().expect("should be requeued");
+            delays.push(pending.next_attempt - now);
+        }
+        assert_eq!(
+            delays,
+            [
+                Duration::from_secs(20),
+                Duration::from_secs(40),
+                Duration::from_secs(80)
+            ]
+        );
+        queue.record_attempt(pending, false, now);
+        assert!(queue.pending.is_empty());
+        assert_eq!(queue.consecutive_give_ups, 1);
+    }
+
+    #[test]
+    fn retry_queue_is_capped() {
+        let mut queue = RetryQueue::new();
+        let now = Instant::now();
+        for i in 0..(MAX_QUEUED_NOTIFICATIONS + 3) {
+            queue.push(
+                &format!("Title {i}"),
+                "Message",
+                NotificationDuration::Long,
+                now,
+            );
+        }
+        assert_eq!(queue.pending.len(), MAX_QUEUED_NOTIFICATIONS);
+        assert_eq!(queue.pending.front().unwrap().title, "Title 3");
+    }
+
+    #[test]
+    fn retry_queue_only_logs_once_unavailable() {
+        let mut queue = RetryQueue::new();
+        let now = Instant::now();
+        for _ in 0..GIVE_UPS_BEFORE_UNAVAILABLE {
+            let mut pending = queued(&mut queue, now);
+            pending.retries = MAX_RETRIES - 1;
+            queue.record_attempt(pending, false, now);
+        }
+        assert!(queue.is_unavailable());
+        queue.push("Title", "Message", NotificationDuration::Long, now);
+        assert!(queue.pending.is_empty());
+    }
+
+    #[test]
+    fn retry_queue_success_resets_give_ups() {
+        let mut queue = RetryQueue::new();
+        let now = Instant::now();
+        queue.consecutive_give_ups = GIVE_UPS_BEFORE_UNAVAILABLE - 1;
+        let pending = queued(&mut queue, now);
+        queue.record_attempt(pending, true, now);
+        assert_eq!(queue.consecutive_give_ups, 0);
+    }
+
+    #[test]
+    fn held_notifications_are_capped() {
+        let mut queue = RetryQueue::new();
+        for i in 0..(MAX_QUEUED_NOTIFICATIONS + 2) {
+            queue.hold(&format!("Title {i}"), "Message");
+        }
+        assert_eq!(queue.held.len(), MAX_QUEUED_NOTIFICATIONS);
+        assert_eq!(queue.held.front().unwrap().0, "Title 2");
+        assert!(queue.pending.is_empty());
+    }
+
+    #[test]
+    fn retry_queue_success_clears_unavailable() {
+        let mut queue = RetryQueue::new();
+        let now = Instant::now();
+        queue.consecutive_give_ups = GIVE_UPS_BEFORE_UNAVAILABLE;
+        queue.record_success();
+        assert!(!queue.is_unavailable());
+        queue.push("Title", "Message", NotificationDuration::Long, now);
+        assert_eq!(queue.pending.len(), 1);
     }
 }
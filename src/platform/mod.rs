@@ -4,27 +4,145 @@ pub enum NotificationDuration {
     Long,
 }
 
+/// A drop-down list in a text viewer: its choices and which one is selected.
+pub struct ViewerFilter {
+    pub choices: Vec<String>,
+    pub selected: usize,
+}
+
+#[cfg(target_os = "windows")]
+mod text_viewer;
 #[cfg(target_os = "windows")]
 mod windows;
 
+#[cfg(target_os = "windows")]
+pub use self::text_viewer::show_text_viewer;
+
 #[cfg(target_os = "windows")]
 pub use self::windows::{
-    ComToken, SingleInstanceGuard, init_platform, is_directory_writable, open_device_settings,
-    open_devices_list, open_sound_control_panel, open_sound_settings, open_volume_mixer,
+    ComToken, SingleInstanceGuard, attach_parent_console, confirm, init_platform,
+    is_aumid_registered, is_battery_saver_on, is_directory_writable, is_quiet_time,
+    open_device_settings, open_devices_list, open_sound_control_panel, open_sound_settings,
+    open_volume_mixer, read_state_breadcrumb, register_uri_scheme, registered_auto_launch_command,
+    running_process_names, send_to_named_pipe, serve_named_pipe, write_state_breadcrumb,
 };
 
 #[cfg(not(target_os = "windows"))]
 pub struct ComToken(());
 
 #[cfg(not(target_os = "windows"))]
-pub fn init_platform(_executable_directory: &std::path::Path) -> anyhow::Result<ComToken> {
+pub fn init_platform(
+    _executable_directory: &std::path::Path,
+    _register_aumid: bool,
+) -> anyhow::Result<ComToken> {
     Ok(ComToken(()))
 }
 
+#[cfg(not(target_os = "windows"))]
+pub fn is_quiet_time() -> bool {
+    false
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn read_state_breadcrumb() -> Option<std::path::PathBuf> {
+    None
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn write_state_breadcrumb(_directory: &std::path::Path) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn registered_auto_launch_command(_app_name: &str) -> Option<String> {
+    None
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn register_uri_scheme(_scheme: &str, _executable: &std::path::Path) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn serve_named_pipe(
+    _name: &str,
+    _handle_request: impl Fn(&str) -> String + Send + 'static,
+) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn send_to_named_pipe(_name: &str, _request: &str) -> anyhow::Result<Option<String>> {
+    Ok(None)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn show_text_viewer(
+    _title: &str,
+    _filters: Vec<ViewerFilter>,
+    _render: impl Fn(&[usize]) -> String + 'static,
+) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn is_battery_saver_on() -> bool {
+    false
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn attach_parent_console() {}
+
+#[cfg(not(target_os = "windows"))]
+pub fn confirm(_title: &str, _message: &str) -> bool {
+    false
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn running_process_names() -> anyhow::Result<Vec<String>> {
+    Ok(Vec::new())
+}
+
+/// Shows a toast. Short notifications are informational, so they are held
+/// back while Windows is in quiet hours or Focus and shown once it ends; long
+/// ones report errors and always show. Toasts that fail to show are queued and
+/// retried later, which is logged here, so callers only see a failure they can
+/// do something about.
 pub fn send_notification(
     title: &str,
     message: &str,
     duration: NotificationDuration,
+) -> anyhow::Result<()> {
+    if matches!(duration, NotificationDuration::Short) && is_quiet_time() {
+        log::info!("Holding notification back during quiet time: {title}");
+        crate::notification::hold_until_quiet_time_ends(title, message);
+        return Ok(());
+    }
+    send_notification_now(title, message, duration)
+}
+
+/// Shows a toast even during quiet time, e.g. to answer something the user
+/// just did. Failures are queued and retried like for [`send_notification`].
+pub fn send_notification_now(
+    title: &str,
+    message: &str,
+    duration: NotificationDuration,
+) -> anyhow::Result<()> {
+    match show_notification(title, message, duration) {
+        Ok(()) => crate::notification::notification_succeeded(),
+        Err(e) => {
+            log::warn!("Failed to show notification {title}, retrying later: {e:#}");
+            crate::notification::queue_for_retry(title, message, duration);
+        }
+    }
+    Ok(())
+}
+
+/// Shows a toast right away, without quiet time checks or retries.
+pub fn show_notification(
+    title: &str,
+    message: &str,
+    duration: NotificationDuration,
 ) -> anyhow::Result<()> {
     let timeout = match duration {
         NotificationDuration::Short => notify_rust::Timeout::Default,
@@ -35,7 +153,9 @@ pub fn send_notification(
     notification.summary(title).body(message).timeout(timeout);
 
     #[cfg(target_os = "windows")]
-    notification.app_id(crate::consts::APP_AUMID);
+    if is_aumid_registered() {
+        notification.app_id(crate::consts::APP_AUMID);
+    }
 
     notification
         .show()
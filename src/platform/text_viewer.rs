@@ -0,0 +1,338 @@
+//! A plain window that shows read-only text, narrowed down by drop-down filters.
+
+use super::ViewerFilter;
+use std::cell::RefCell;
+use windows::Win32::Foundation::{
+    ERROR_CLASS_ALREADY_EXISTS, HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM,
+};
+use windows::Win32::Graphics::Gdi::{
+    CLEARTYPE_QUALITY, CLIP_DEFAULT_PRECIS, COLOR_BTNFACE, CreateFontW, DEFAULT_CHARSET,
+    DEFAULT_GUI_FONT, DeleteObject, FF_MODERN, FIXED_PITCH, FW_NORMAL, GetStockObject,
+    GetSysColorBrush, HFONT, HGDIOBJ, OUT_DEFAULT_PRECIS,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CB_ADDSTRING, CB_GETCURSEL, CB_SETCURSEL, CBN_SELCHANGE, CBS_DROPDOWNLIST, CW_USEDEFAULT,
+    CreateWindowExW, DefWindowProcW, DestroyWindow, ES_AUTOHSCROLL, ES_AUTOVSCROLL, ES_MULTILINE,
+    ES_READONLY, GetClientRect, IDC_ARROW, LoadCursorW, MoveWindow, RegisterClassW, SW_SHOW,
+    SendMessageW, SetForegroundWindow, SetWindowTextW, ShowWindow, WA_INACTIVE, WINDOW_EX_STYLE,
+    WINDOW_STYLE, WM_ACTIVATE, WM_COMMAND, WM_DESTROY, WM_SETFONT, WM_SIZE, WNDCLASSW, WS_CHILD,
+    WS_EX_CLIENTEDGE, WS_HSCROLL, WS_OVERLAPPEDWINDOW, WS_TABSTOP, WS_VISIBLE, WS_VSCROLL,
+};
+use windows::core::{HSTRING, PCWSTR, w};
+
+const CLASS_NAME: PCWSTR = w!("VolumeLockerTextViewer");
+const MARGIN: i32 = 8;
+const FILTER_WIDTH: i32 = 240;
+/// Height of a closed drop-down list, which is where the text starts.
+const FILTER_HEIGHT: i32 = 24;
+/// Height of an open drop-down list.
+const FILTER_LIST_HEIGHT: i32 = 320;
+
+/// Returns the text for the selected index of each filter.
+type Render = Box<dyn Fn(&[usize]) -> String>;
+
+struct Viewer {
+    window: HWND,
+    filters: Vec<HWND>,
+    text: HWND,
+    font: HFONT,
+    render: Render,
+}
+
+thread_local! {
+    /// The open viewer, if any. It lives on the event loop thread, which also
+    /// dispatches its messages.
+    static VIEWER: RefCell<Option<Viewer>> = const { RefCell::new(None) };
+}
+
+/// Opens a window titled `title` with a drop-down list per filter above the
+/// text `render` returns for the selected filter indices. The text is rendered
+/// again whenever a filter changes or the window is activated. Replaces a
+/// viewer that is already open.
+pub fn show_text_viewer(
+    title: &str,
+    filters: Vec<ViewerFilter>,
+    render: impl Fn(&[usize]) -> String + 'static,
+) -> anyhow::Result<()> {
+    if let Some(window) = VIEWER.with_borrow(|viewer| viewer.as_ref().map(|v| v.window)) {
+        // SAFETY: window belongs to this thread; WM_DESTROY drops the viewer.
+        unsafe {
+            let _ = DestroyWindow(window);
+        }
+    }
+
+    // SAFETY: A null module name returns the handle of this executable.
+    let instance: HINSTANCE = unsafe { GetModuleHandleW(PCWSTR::null())? }.into();
+    register_class(instance)?;
+    // SAFETY: The class was registered above and the title outlives the call.
+    let window = unsafe {
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            CLASS_NAME,
+            &HSTRING::from(title),
+            WS_OVERLAPPEDWINDOW,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            960,
+            540,
+            None,
+            None,
+            Some(instance),
+            None,
+        )?
+    };
+    // SAFETY: Plain font creation; a failure returns a null font, which makes
+    // the control fall back to the system font.
+    let font = unsafe {
+        CreateFontW(
+            -15,
+            0,
+            0,
+            0,
+            FW_NORMAL.0.cast_signed(),
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET,
+            OUT_DEFAULT_PRECIS,
+            CLIP_DEFAULT_PRECIS,
+            CLEARTYPE_QUALITY,
+            u32::from(FIXED_PITCH.0 | FF_MODERN.0),
+            w!("Consolas"),
+        )
+    };
+
+    let controls = create_filters(window, instance, &filters)
+        .and_then(|combos| Ok((combos, create_text(window, instance, font)?)));
+    let (combos, text) = match controls {
+        Ok(controls) => controls,
+        Err(e) => {
+            // SAFETY: Both were created above and are not stored anywhere else.
+            unsafe {
+                let _ = DestroyWindow(window);
+                let _ = DeleteObject(HGDIOBJ::from(font));
+            }
+            return Err(e);
+        }
+    };
+
+    VIEWER.with_borrow_mut(|viewer| {
+        *viewer = Some(Viewer {
+            window,
+            filters: combos,
+            text,
+            font,
+            render: Box::new(render),
+        });
+    });
+    with_viewer(window, layout);
+    with_viewer(window, refresh);
+    // SAFETY: window is a valid top-level window of this thread.
+    unsafe {
+        let _ = ShowWindow(window, SW_SHOW);
+        let _ = SetForegroundWindow(window);
+    }
+    Ok(())
+}
+
+fn register_class(instance: HINSTANCE) -> anyhow::Result<()> {
+    let class = WNDCLASSW {
+        lpfnWndProc: Some(window_proc),
+        hInstance: instance,
+        // SAFETY: IDC_ARROW is a predefined system cursor.
+        hCursor: unsafe { LoadCursorW(None, IDC_ARROW)? },
+        // SAFETY: COLOR_BTNFACE is a valid system color index.
+        hbrBackground: unsafe { GetSysColorBrush(COLOR_BTNFACE) },
+        lpszClassName: CLASS_NAME,
+        ..Default::default()
+    };
+    // SAFETY: class is fully initialized and its strings are static.
+    if unsafe { RegisterClassW(&raw const class) } == 0 {
+        let error = windows::core::Error::from_thread();
+        if error.code() != ERROR_CLASS_ALREADY_EXISTS.to_hresult() {
+            return Err(error.into());
+        }
+    }
+    Ok(())
+}
+
+fn create_filters(
+    window: HWND,
+    instance: HINSTANCE,
+    filters: &[ViewerFilter],
+) -> anyhow::Result<Vec<HWND>> {
+    let style = WS_CHILD
+        | WS_VISIBLE
+        | WS_VSCROLL
+        | WS_TABSTOP
+        | WINDOW_STYLE(CBS_DROPDOWNLIST.cast_unsigned());
+    filters
+        .iter()
+        .map(|filter| {
+            // SAFETY: window is a valid parent window of this thread.
+            let combo = unsafe {
+                CreateWindowExW(
+                    WINDOW_EX_STYLE::default(),
+                    w!("COMBOBOX"),
+                    None,
+                    style,
+                    0,
+                    0,
+                    FILTER_WIDTH,
+                    FILTER_LIST_HEIGHT,
+                    Some(window),
+                    None,
+                    Some(instance),
+                    None,
+                )?
+            };
+            // SAFETY: combo is the combo box created above, and every label
+            // outlives the message that copies it.
+            unsafe {
+                let font = GetStockObject(DEFAULT_GUI_FONT);
+                SendMessageW(combo, WM_SETFONT, Some(WPARAM(font.0 as usize)), None);
+                for choice in &filter.choices {
+                    let label = HSTRING::from(choice.as_str());
+                    SendMessageW(
+                        combo,
+                        CB_ADDSTRING,
+                        None,
+                        Some(LPARAM(label.as_ptr() as isize)),
+                    );
+                }
+                SendMessageW(combo, CB_SETCURSEL, Some(WPARAM(filter.selected)), None);
+            }
+            Ok(combo)
+        })
+        .collect()
+}
+
+fn create_text(window: HWND, instance: HINSTANCE, font: HFONT) -> anyhow::Result<HWND> {
+    let style = WS_CHILD
+        | WS_VISIBLE
+        | WS_VSCROLL
+        | WS_HSCROLL
+        | WS_TABSTOP
+        | WINDOW_STYLE(
+            (ES_MULTILINE | ES_READONLY | ES_AUTOVSCROLL | ES_AUTOHSCROLL).cast_unsigned(),
+        );
+    // SAFETY: window is a valid parent window of this thread.
+    let text = unsafe {
+        CreateWindowExW(
+            WS_EX_CLIENTEDGE,
+            w!("EDIT"),
+            None,
+            style,
+            0,
+            0,
+            0,
+            0,
+            Some(window),
+            None,
+            Some(instance),
+            None,
+        )?
+    };
+    // SAFETY: text is the edit control created above; the font outlives it.
+    unsafe {
+        SendMessageW(text, WM_SETFONT, Some(WPARAM(font.0 as usize)), None);
+    }
+    Ok(text)
+}
+
+/// Runs `f` on the open viewer if `window` is its window.
+fn with_viewer(window: HWND, f: fn(&Viewer)) {
+    VIEWER.with_borrow(|viewer| {
+        if let Some(viewer) = viewer
+            && viewer.window == window
+        {
+            f(viewer);
+        }
+    });
+}
+
+/// Puts the filters in a row at the top and gives the rest to the text.
+fn layout(viewer: &Viewer) {
+    let mut client = RECT::default();
+    // SAFETY: viewer.window is valid and client is a writable RECT.
+    if unsafe { GetClientRect(viewer.window, &raw mut client) }.is_err() {
+        return;
+    }
+    let mut x = MARGIN;
+    for &combo in &viewer.filters {
+        // SAFETY: combo is a child control of viewer.window.
+        unsafe {
+            let _ = MoveWindow(combo, x, MARGIN, FILTER_WIDTH, FILTER_LIST_HEIGHT, true);
+        }
+        x += FILTER_WIDTH + MARGIN;
+    }
+    let top = if viewer.filters.is_empty() {
+        MARGIN
+    } else {
+        2 * MARGIN + FILTER_HEIGHT
+    };
+    // SAFETY: viewer.text is a child control of viewer.window.
+    unsafe {
+        let _ = MoveWindow(
+            viewer.text,
+            MARGIN,
+            top,
+            (client.right - 2 * MARGIN).max(0),
+            (client.bottom - top - MARGIN).max(0),
+            true,
+        );
+    }
+}
+
+/// Renders the text again for the current filter selection.
+fn refresh(viewer: &Viewer) {
+    let selected: Vec<usize> = viewer
+        .filters
+        .iter()
+        .map(|&combo| {
+            // SAFETY: combo is a combo box owned by the viewer.
+            let index = unsafe { SendMessageW(combo, CB_GETCURSEL, None, None) };
+            usize::try_from(index.0).unwrap_or(0)
+        })
+        .collect();
+    // Edit controls only break lines at CRLF
+    let text = (viewer.render)(&selected).replace('\n', "\r\n");
+    // SAFETY: viewer.text is an edit control owned by the viewer.
+    if let Err(e) = unsafe { SetWindowTextW(viewer.text, &HSTRING::from(text)) } {
+        log::warn!("Failed to show text in the viewer: {e:#}");
+    }
+}
+
+extern "system" fn window_proc(
+    window: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    let notification = (wparam.0 >> 16) & 0xFFFF;
+    match message {
+        WM_SIZE => with_viewer(window, layout),
+        WM_ACTIVATE if wparam.0 & 0xFFFF != WA_INACTIVE as usize => {
+            with_viewer(window, refresh);
+        }
+        WM_COMMAND if notification == CBN_SELCHANGE as usize => with_viewer(window, refresh),
+        WM_DESTROY => {
+            let closed = VIEWER.with(|viewer| {
+                viewer
+                    .try_borrow_mut()
+                    .ok()
+                    .and_then(|mut viewer| viewer.take_if(|v| v.window == window))
+            });
+            if let Some(viewer) = closed {
+                // SAFETY: The font was only used by the viewer's controls, which are gone.
+                unsafe {
+                    let _ = DeleteObject(HGDIOBJ::from(viewer.font));
+                }
+            }
+        }
+        _ => {}
+    }
+    // SAFETY: Forwards the message unchanged to the default window procedure.
+    unsafe { DefWindowProcW(window, message, wparam, lparam) }
+}
@@ -1,45 +1,93 @@
 use crate::consts::{APP_AUMID, APP_NAME, PNG_ICON_BYTES, PNG_ICON_FILE_NAME};
 use crate::types::{DeviceId, DeviceType};
-use std::fs;
-use std::path::Path;
+use anyhow::Context;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::windows::io::{AsRawHandle, FromRawHandle};
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use windows::Win32::Foundation::ERROR_ALREADY_EXISTS;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{
+    CloseHandle, ERROR_ALREADY_EXISTS, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED, HANDLE,
+};
+use windows::Win32::Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX};
 use windows::Win32::System::Com::{COINIT_MULTITHREADED, CoInitializeEx};
-use windows::Win32::System::Threading::CreateMutexW;
-use windows::Win32::UI::Shell::SetCurrentProcessExplicitAppUserModelID;
+use windows::Win32::System::Console::{ATTACH_PARENT_PROCESS, AttachConsole};
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW, TH32CS_SNAPPROCESS,
+};
+use windows::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_READMODE_BYTE,
+    PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_WAIT,
+};
+use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+use windows::Win32::System::Threading::{CreateMutexW, GetCurrentProcessId};
+use windows::Win32::UI::Shell::{
+    QUNS_BUSY, QUNS_PRESENTATION_MODE, QUNS_QUIET_TIME, QUNS_RUNNING_D3D_FULL_SCREEN,
+    SHQueryUserNotificationState, SetCurrentProcessExplicitAppUserModelID,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    IDYES, MB_ICONQUESTION, MB_SETFOREGROUND, MB_YESNO, MessageBoxW,
+};
 use windows::core::{HSTRING, Result};
-use windows_registry::CURRENT_USER;
+use windows_registry::{CURRENT_USER, LOCAL_MACHINE};
 
 /// Witness type proving COM has been initialized on this thread.
 /// Only constructible via [`init_platform`], which calls `CoInitializeEx`.
 pub struct ComToken(());
 
-pub fn init_platform(executable_directory: &Path) -> anyhow::Result<ComToken> {
+/// Whether the app's AUMID is registered, so notifications can be branded with it.
+static AUMID_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_aumid_registered() -> bool {
+    AUMID_REGISTERED.load(Ordering::Relaxed)
+}
+
+pub fn init_platform(
+    executable_directory: &Path,
+    register_aumid: bool,
+) -> anyhow::Result<ComToken> {
     // Initialize COM for the process. Must be called before any COM usage,
     // including WindowsAudioBackend::new().
     // SAFETY: CoInitializeEx is safe to call; first call on this thread.
     unsafe { CoInitializeEx(None, COINIT_MULTITHREADED).ok()? };
-    if let Err(e) = setup_app_aumid(executable_directory) {
+    if !register_aumid {
+        log::info!("Skipping app AUMID registration, notifications will use default branding");
+    } else if let Err(e) = setup_app_aumid(executable_directory) {
         log::warn!("Failed to set up app AUMID: {e:#}");
+    } else {
+        AUMID_REGISTERED.store(true, Ordering::Relaxed);
     }
     Ok(ComToken(()))
 }
 
+/// Registers the app's AUMID so notifications show its name and icon. Only
+/// writes values that are missing or outdated, so regular launches don't touch
+/// the registry or the icon file.
 fn setup_app_aumid(executable_directory: &Path) -> Result<()> {
     let registry_path = format!(r"SOFTWARE\Classes\AppUserModelId\{APP_AUMID}");
-    let _ = CURRENT_USER.remove_tree(registry_path.clone());
     let key = CURRENT_USER.create(&registry_path)?;
-    if let Err(e) = key.set_string("DisplayName", APP_NAME) {
+    if key.get_string("DisplayName").ok().as_deref() != Some(APP_NAME)
+        && let Err(e) = key.set_string("DisplayName", APP_NAME)
+    {
         log::warn!("Failed to set AUMID DisplayName: {e:#}");
     }
 
     // We need an icon file for the AUMID to work properly
     let png_path = executable_directory.join(PNG_ICON_FILE_NAME);
-    if let Err(e) = fs::write(&png_path, PNG_ICON_BYTES) {
+    let icon_is_current = fs::read(&png_path).is_ok_and(|bytes| bytes == PNG_ICON_BYTES);
+    if !icon_is_current && let Err(e) = fs::write(&png_path, PNG_ICON_BYTES) {
         log::warn!("Failed to write {PNG_ICON_FILE_NAME} icon: {e:#}");
         let _ = key.remove_value("IconUri");
-    } else if let Err(e) = key.set_hstring("IconUri", &png_path.as_path().into()) {
-        log::warn!("Failed to set AUMID IconUri: {e:#}");
+    } else {
+        let icon_uri = HSTRING::from(png_path.as_path());
+        if key.get_hstring("IconUri").ok().as_ref() != Some(&icon_uri)
+            && let Err(e) = key.set_hstring("IconUri", &icon_uri)
+        {
+            log::warn!("Failed to set AUMID IconUri: {e:#}");
+        }
     }
 
     // SAFETY: APP_AUMID is a valid static string; setting the AUMID is a standard shell API call.
@@ -75,6 +123,131 @@ impl SingleInstanceGuard {
     }
 }
 
+/// Longest request accepted on a named pipe, in bytes.
+const MAX_PIPE_REQUEST_LEN: u64 = 4096;
+/// How long a client keeps retrying while the server is busy with another one.
+const PIPE_BUSY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Path of the named pipe called `name` in the current Windows session. Pipes
+/// are machine-wide, unlike the single instance mutex, so the session ID keeps
+/// users signed in side by side apart.
+fn session_pipe_path(name: &str) -> anyhow::Result<String> {
+    let mut session_id = 0;
+    // SAFETY: session_id is a valid, writable u32 for the duration of the call.
+    unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &raw mut session_id)? };
+    Ok(format!(r"\\.\pipe\{name}-{session_id}"))
+}
+
+/// Answers one-line requests on the named pipe called `name` in a background
+/// thread, one client at a time, with the line `handle_request` returns.
+/// Fails if the pipe already exists.
+pub fn serve_named_pipe(
+    name: &str,
+    handle_request: impl Fn(&str) -> String + Send + 'static,
+) -> anyhow::Result<()> {
+    let path = HSTRING::from(session_pipe_path(name)?);
+    // SAFETY: path outlives the call. Without security attributes the pipe
+    // gets the default DACL, which only lets the current user, administrators
+    // and the system write to it.
+    let handle = unsafe {
+        CreateNamedPipeW(
+            &path,
+            PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            1,
+            0,
+            0,
+            0,
+            None,
+        )
+    };
+    if handle.is_invalid() {
+        return Err(windows::core::Error::from_thread()).context("failed to create named pipe");
+    }
+    // SAFETY: handle is a valid pipe handle that nothing else owns.
+    let pipe = unsafe { File::from_raw_handle(handle.0) };
+
+    std::thread::spawn(move || {
+        let handle = HANDLE(pipe.as_raw_handle());
+        loop {
+            // SAFETY: handle stays valid as long as pipe, which this thread owns.
+            if let Err(e) = unsafe { ConnectNamedPipe(handle, None) }
+                && e.code() != ERROR_PIPE_CONNECTED.to_hresult()
+            {
+                log::warn!("Stopped listening on named pipe: {e:#}");
+                return;
+            }
+
+            let mut request = String::new();
+            match BufReader::new((&pipe).take(MAX_PIPE_REQUEST_LEN)).read_line(&mut request) {
+                Ok(_) => {
+                    let reply = handle_request(&request);
+                    let mut writer = &pipe;
+                    // Flushing waits until the client has read the reply
+                    if let Err(e) = writeln!(writer, "{reply}").and_then(|()| pipe.sync_all()) {
+                        log::warn!("Failed to reply on named pipe: {e:#}");
+                    }
+                }
+                Err(e) => log::warn!("Failed to read from named pipe: {e:#}"),
+            }
+
+            // SAFETY: Same as ConnectNamedPipe above.
+            if let Err(e) = unsafe { DisconnectNamedPipe(handle) } {
+                log::warn!("Stopped listening on named pipe: {e:#}");
+                return;
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Sends a one-line request to the named pipe called `name` and returns the
+/// reply, or `None` if nothing is listening on it.
+pub fn send_to_named_pipe(name: &str, request: &str) -> anyhow::Result<Option<String>> {
+    let path = session_pipe_path(name)?;
+    let deadline = Instant::now() + PIPE_BUSY_TIMEOUT;
+    let mut pipe = loop {
+        match OpenOptions::new().read(true).write(true).open(&path) {
+            Ok(pipe) => break pipe,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e)
+                if e.raw_os_error().and_then(|code| u32::try_from(code).ok())
+                    == Some(ERROR_PIPE_BUSY.0)
+                    && Instant::now() < deadline =>
+            {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => return Err(e).context("failed to connect to named pipe"),
+        }
+    };
+    writeln!(pipe, "{request}").context("failed to write to named pipe")?;
+    let mut reply = String::new();
+    BufReader::new(pipe)
+        .read_line(&mut reply)
+        .context("failed to read from named pipe")?;
+    Ok(Some(reply))
+}
+
+/// Registers `scheme:` links to open `executable` with the link as its only
+/// argument. Only writes values that are missing or outdated.
+pub fn register_uri_scheme(scheme: &str, executable: &Path) -> anyhow::Result<()> {
+    let key = CURRENT_USER.create(format!(r"SOFTWARE\Classes\{scheme}"))?;
+    let description = format!("URL:{APP_NAME}");
+    if key.get_string("").ok().as_deref() != Some(description.as_str()) {
+        key.set_string("", &description)?;
+    }
+    if key.get_string("URL Protocol").is_err() {
+        key.set_string("URL Protocol", "")?;
+    }
+
+    let command_key = key.create(r"shell\open\command")?;
+    let command = format!("\"{}\" \"%1\"", executable.display());
+    if command_key.get_string("").ok().as_deref() != Some(command.as_str()) {
+        command_key.set_string("", &command)?;
+    }
+    Ok(())
+}
+
 /// Checks if a directory is writable by attempting to create and delete a temp file.
 pub fn is_directory_writable(dir: &Path) -> bool {
     let test_path = dir.join(".volume_locker_write_test");
@@ -87,6 +260,123 @@ pub fn is_directory_writable(dir: &Path) -> bool {
     }
 }
 
+/// Returns true while Windows wants notifications held back: quiet hours or
+/// Focus, presentation mode, or a full-screen app or game.
+pub fn is_quiet_time() -> bool {
+    // SAFETY: SHQueryUserNotificationState has no preconditions and only returns a value.
+    match unsafe { SHQueryUserNotificationState() } {
+        Ok(state) => matches!(
+            state,
+            QUNS_QUIET_TIME | QUNS_BUSY | QUNS_PRESENTATION_MODE | QUNS_RUNNING_D3D_FULL_SCREEN
+        ),
+        Err(e) => {
+            log::warn!("Failed to query user notification state: {e:#}");
+            false
+        }
+    }
+}
+
+/// Registry key holding the breadcrumb to the directory the app last ran from.
+fn breadcrumb_key() -> String {
+    format!(r"SOFTWARE\{APP_NAME}")
+}
+
+/// Returns the data directory recorded by the last run, if any.
+pub fn read_state_breadcrumb() -> Option<PathBuf> {
+    CURRENT_USER
+        .open(breadcrumb_key())
+        .and_then(|key| key.get_string("StateDirectory"))
+        .ok()
+        .map(PathBuf::from)
+}
+
+/// Records `directory` as the one the app last ran from, unless it already is.
+pub fn write_state_breadcrumb(directory: &Path) -> anyhow::Result<()> {
+    if read_state_breadcrumb().as_deref() == Some(directory) {
+        return Ok(());
+    }
+    CURRENT_USER
+        .create(breadcrumb_key())?
+        .set_string("StateDirectory", directory.to_string_lossy())?;
+    Ok(())
+}
+
+/// Returns the command line of the auto-launch entry named `app_name`, for
+/// the current user or, failing that, for all users.
+pub fn registered_auto_launch_command(app_name: &str) -> Option<String> {
+    const RUN_KEY: &str = r"SOFTWARE\Microsoft\Windows\CurrentVersion\Run";
+    [CURRENT_USER, LOCAL_MACHINE].into_iter().find_map(|root| {
+        root.open(RUN_KEY)
+            .and_then(|key| key.get_string(app_name))
+            .ok()
+    })
+}
+
+/// Returns true while Windows battery saver is on.
+pub fn is_battery_saver_on() -> bool {
+    let mut status = SYSTEM_POWER_STATUS::default();
+    // SAFETY: status is a valid, writable SYSTEM_POWER_STATUS for the duration of the call.
+    match unsafe { GetSystemPowerStatus(&raw mut status) } {
+        Ok(()) => status.SystemStatusFlag == 1,
+        Err(e) => {
+            log::warn!("Failed to query system power status: {e:#}");
+            false
+        }
+    }
+}
+
+/// Attaches to the console of the process that started this one, if any, so
+/// command-line output reaches it despite `windows_subsystem = "windows"`.
+/// Only for commands that exit soon: closing that console would end the process.
+pub fn attach_parent_console() {
+    // SAFETY: No arguments besides a constant; failure only means there is no console.
+    if unsafe { AttachConsole(ATTACH_PARENT_PROCESS) }.is_err() {
+        log::debug!("No parent console to attach to");
+    }
+}
+
+/// Shows a modal Yes/No dialog and returns whether the user chose Yes.
+pub fn confirm(title: &str, message: &str) -> bool {
+    // SAFETY: Both strings outlive the call and no owner window is passed.
+    let result = unsafe {
+        MessageBoxW(
+            None,
+            &HSTRING::from(message),
+            &HSTRING::from(title),
+            MB_YESNO | MB_ICONQUESTION | MB_SETFOREGROUND,
+        )
+    };
+    result == IDYES
+}
+
+/// Returns the executable names of all running processes.
+pub fn running_process_names() -> anyhow::Result<Vec<String>> {
+    // SAFETY: Taking a process snapshot has no preconditions; the handle is closed below.
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)? };
+    let mut entry = PROCESSENTRY32W {
+        dwSize: u32::try_from(size_of::<PROCESSENTRY32W>())?,
+        ..Default::default()
+    };
+    let mut names = Vec::new();
+    // SAFETY: snapshot is a valid process snapshot and entry has dwSize set as required.
+    let mut found = unsafe { Process32FirstW(snapshot, &raw mut entry) }.is_ok();
+    while found {
+        let len = entry
+            .szExeFile
+            .iter()
+            .position(|&c| c == 0)
+            .unwrap_or(entry.szExeFile.len());
+        names.push(String::from_utf16_lossy(&entry.szExeFile[..len]));
+        // SAFETY: Same as Process32FirstW above.
+        found = unsafe { Process32NextW(snapshot, &raw mut entry) }.is_ok();
+    }
+    // SAFETY: snapshot is a valid handle owned by this function.
+    unsafe {
+        let _ = CloseHandle(snapshot);
+    }
+    Ok(names)
+}
+
 fn spawn_rundll32(dll: &str, function: &str, arg: &str, context: &str) -> anyhow::Result<()> {
     Command::new("rundll32.exe")
         .arg(format!("{dll},{function}"))
@@ -0,0 +1,98 @@
+use crate::consts::APP_UID;
+use crate::utils::get_executable_directory;
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Directory given with `--config-dir`, if any. Set once at startup.
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Selects the directory that holds the state, log and history files for this
+/// process. Without one, they are kept next to the executable.
+pub fn init(config_dir: Option<&Path>) -> anyhow::Result<()> {
+    let Some(config_dir) = config_dir else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(config_dir).with_context(|| {
+        format!(
+            "failed to create config directory '{}'",
+            config_dir.display()
+        )
+    })?;
+    let config_dir = dunce::canonicalize(config_dir).with_context(|| {
+        format!(
+            "failed to resolve config directory '{}'",
+            config_dir.display()
+        )
+    })?;
+    CONFIG_DIR
+        .set(config_dir)
+        .map_err(|_| anyhow::anyhow!("config directory is already set"))
+}
+
+/// Returns the directory given with `--config-dir`, if any.
+pub fn config_dir() -> Option<&'static Path> {
+    CONFIG_DIR.get().map(PathBuf::as_path)
+}
+
+/// Returns the directory that holds the state, log and history files.
+pub fn data_directory() -> anyhow::Result<PathBuf> {
+    match config_dir() {
+        Some(dir) => Ok(dir.to_path_buf()),
+        None => get_executable_directory(),
+    }
+}
+
+/// Returns the ID used for the single-instance lock and the tray icon, so
+/// that each config directory runs as its own instance.
+pub fn instance_id() -> String {
+    instance_id_for(config_dir())
+}
+
+fn instance_id_for(config_dir: Option<&Path>) -> String {
+    match config_dir {
+        None => APP_UID.to_string(),
+        Some(dir) => {
+            // Windows paths are case-insensitive, so C:\Foo and c:\foo are the same profile
+            let normalized = dir.to_string_lossy().to_lowercase();
+            format!("{APP_UID}-{:016x}", fnv1a(normalized.as_bytes()))
+        }
+    }
+}
+
+/// 64-bit FNV-1a, used instead of `DefaultHasher` because instance IDs must be
+/// stable across builds.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_profile_uses_app_uid() {
+        assert_eq!(instance_id_for(None), APP_UID);
+    }
+
+    #[test]
+    fn config_dirs_get_distinct_stable_ids() {
+        let console = instance_id_for(Some(Path::new(r"C:\Profiles\Console")));
+        let rdp = instance_id_for(Some(Path::new(r"C:\Profiles\RDP")));
+        assert_ne!(console, rdp);
+        assert_ne!(console, APP_UID);
+        assert!(console.starts_with(APP_UID));
+        assert_eq!(
+            console,
+            instance_id_for(Some(Path::new(r"c:\profiles\console")))
+        );
+    }
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
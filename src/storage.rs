@@ -0,0 +1,169 @@
+use anyhow::Context;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Append-only JSON Lines file with size-bounded retention. Once the file
+/// grows past `max_bytes`, it is compacted down to the newest records that fit
+/// in half of that, so compaction doesn't run again on the next append.
+pub struct JsonlStore {
+    path: PathBuf,
+    max_bytes: u64,
+    /// Serializes appends and compactions.
+    lock: Mutex<()>,
+}
+
+impl JsonlStore {
+    pub fn new(path: PathBuf, max_bytes: u64) -> Self {
+        Self {
+            path,
+            max_bytes,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn append<T: Serialize>(&self, record: &T) -> anyhow::Result<()> {
+        let mut line = serde_json::to_string(record).context("failed to serialize record")?;
+        line.push('\n');
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("failed to open '{}'", self.path.display()))?;
+        file.write_all(line.as_bytes())
+            .with_context(|| format!("failed to append to '{}'", self.path.display()))?;
+        if file.metadata().map(|m| m.len()).unwrap_or(0) > self.max_bytes {
+            drop(file);
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Reads every record, oldest first. Lines that can't be parsed, such as
+    /// one cut short by a crash, are skipped.
+    pub fn load<T: DeserializeOwned>(&self) -> anyhow::Result<Vec<T>> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let data = match fs::read_to_string(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to read '{}'", self.path.display()));
+            }
+        };
+        let mut skipped = 0;
+        let records = data
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| {
+                serde_json::from_str(line)
+                    .inspect_err(|_| skipped += 1)
+                    .ok()
+            })
+            .collect();
+        if skipped > 0 {
+            log::warn!(
+                "Skipped {skipped} unreadable records in '{}'",
+                self.path.display()
+            );
+        }
+        Ok(records)
+    }
+
+    /// Rewrites the file with the newest lines that fit in half of `max_bytes`,
+    /// via a temp file + rename so a crash never leaves it half-written.
+    fn compact(&self) -> anyhow::Result<()> {
+        let data = fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read '{}'", self.path.display()))?;
+        let kept = newest_lines_within(&data, self.max_bytes / 2);
+        write_atomically(&self.path, kept)
+    }
+}
+
+/// Returns the tail of `data` made of whole lines and at most `budget` bytes long.
+fn newest_lines_within(data: &str, budget: u64) -> &str {
+    let budget = usize::try_from(budget).unwrap_or(usize::MAX);
+    if data.len() <= budget {
+        return data;
+    }
+    let cut = data.len() - budget;
+    // Start at the first line that begins at or after the cut. Searching bytes
+    // keeps the cut off multi-byte characters, and a newline is always a boundary.
+    match data.as_bytes()[cut - 1..].iter().position(|&b| b == b'\n') {
+        Some(offset) => &data[cut + offset..],
+        None => "",
+    }
+}
+
+fn write_atomically(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)
+        .with_context(|| format!("failed to write '{}'", tmp_path.display()))?;
+    fs::rename(&tmp_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp_path);
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// The store's file lives in the returned directory, which is deleted on drop.
+    fn temp_store(max_bytes: u64) -> (TempDir, JsonlStore) {
+        let dir = TempDir::new().unwrap();
+        let store = JsonlStore::new(dir.path().join("store.jsonl"), max_bytes);
+        (dir, store)
+    }
+
+    #[test]
+    fn appended_records_load_in_order() {
+        let (_dir, store) = temp_store(1024);
+        for i in 0..3 {
+            store.append(&i).unwrap();
+        }
+        assert_eq!(store.load::<u32>().unwrap(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn missing_file_loads_empty() {
+        let (_dir, store) = temp_store(1024);
+        assert!(store.load::<u32>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unreadable_lines_are_skipped() {
+        let (_dir, store) = temp_store(1024);
+        fs::write(&store.path, "1\n{\"trunc\n2\n").unwrap();
+        assert_eq!(store.load::<u32>().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn growing_past_limit_keeps_newest_records() {
+        let (_dir, store) = temp_store(100);
+        for i in 0..100_u32 {
+            store.append(&format!("record {i:03}")).unwrap();
+        }
+        let size = fs::metadata(&store.path).unwrap().len();
+        assert!(size <= 100, "store grew to {size} bytes");
+        let records = store.load::<String>().unwrap();
+        assert_eq!(records.last().map(String::as_str), Some("record 099"));
+        // Kept records are a contiguous, ordered tail
+        let first: u32 = records[0]["record ".len()..].parse().unwrap();
+        let expected: Vec<_> = (first..100).map(|i| format!("record {i:03}")).collect();
+        assert_eq!(records, expected);
+    }
+
+    #[test]
+    fn newest_lines_only_cuts_at_line_boundaries() {
+        assert_eq!(newest_lines_within("aa\nbb\ncc\n", 100), "aa\nbb\ncc\n");
+        assert_eq!(newest_lines_within("aa\nbb\ncc\n", 6), "bb\ncc\n");
+        assert_eq!(newest_lines_within("aa\nbb\ncc\n", 5), "cc\n");
+        assert_eq!(newest_lines_within("aaaaaa\n", 3), "");
+        assert_eq!(newest_lines_within("ééé\nb\n", 3), "b\n");
+    }
+}
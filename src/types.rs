@@ -1,3 +1,4 @@
+use crate::config::{ConfigDelta, PersistentState};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::fmt;
@@ -7,6 +8,9 @@ use std::fmt;
 pub struct VolumeScalar(f32);
 
 impl VolumeScalar {
+    pub const MIN: Self = Self(0.0);
+    pub const MAX: Self = Self(1.0);
+
     pub fn as_f32(self) -> f32 {
         self.0
     }
@@ -70,7 +74,7 @@ impl PartialEq<f32> for VolumePercent {
 
 /// A strongly-typed wrapper around a device identifier string.
 /// Prevents accidental confusion between device IDs and device names.
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[serde(transparent)]
 pub struct DeviceId(String);
 
@@ -138,7 +142,7 @@ impl fmt::Display for DeviceType {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DeviceRole {
     Console,
     Multimedia,
@@ -155,7 +159,7 @@ impl fmt::Display for DeviceRole {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
 pub struct VolumeLockPolicy {
     #[serde(default, rename = "is_volume_locked")]
     pub is_locked: bool,
@@ -165,7 +169,7 @@ pub struct VolumeLockPolicy {
     pub notify: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
 pub struct UnmuteLockPolicy {
     #[serde(default, rename = "is_unmute_locked")]
     pub is_locked: bool,
@@ -173,7 +177,7 @@ pub struct UnmuteLockPolicy {
     pub notify: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct DeviceSettings {
     #[serde(flatten)]
     pub volume_lock: VolumeLockPolicy,
@@ -181,6 +185,9 @@ pub struct DeviceSettings {
     pub unmute_lock: UnmuteLockPolicy,
     pub device_type: DeviceType,
     pub name: String,
+    /// Short user-chosen tag, like an emoji, shown before the name for quick identification.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
 }
 
 impl DeviceSettings {
@@ -190,17 +197,31 @@ impl DeviceSettings {
             unmute_lock: UnmuteLockPolicy::default(),
             device_type,
             name,
+            tag: None,
         }
     }
 
-    /// Returns true if the device has any active volume/unmute lock or notification setting.
-    /// Used to decide whether a `DeviceSettings` entry can be pruned when no longer referenced
-    /// by a priority list.
-    pub fn has_active_locks_or_notifications(&self) -> bool {
+    /// Returns true if the device has any active volume/unmute lock, notification setting
+    /// or tag. Used to decide whether a `DeviceSettings` entry can be pruned when no longer
+    /// referenced by a priority list.
+    pub fn has_user_settings(&self) -> bool {
         self.volume_lock.is_locked
             || self.unmute_lock.is_locked
             || self.volume_lock.notify
             || self.unmute_lock.notify
+            || self.tag.is_some()
+    }
+
+    /// The name to show in labels and notifications, prefixed with the tag if set.
+    pub fn display_name(&self) -> String {
+        tagged_name(self.tag.as_deref(), &self.name)
+    }
+}
+
+pub fn tagged_name(tag: Option<&str>, name: &str) -> String {
+    match tag {
+        Some(tag) => format!("{tag} {name}"),
+        None => name.to_string(),
     }
 }
 
@@ -237,14 +258,27 @@ pub enum UserEvent {
     TrayIcon(tray_icon::TrayIconEvent),
     Menu(tray_icon::menu::MenuEvent),
     VolumeChanged(VolumeChangedEvent),
+    /// The volume or mute state of the default output device changed.
+    DefaultOutputVolumeChanged(Option<VolumeScalar>),
     DevicesChanged,
-    ConfigurationChanged,
+    ConfigurationChanged(ConfigDelta),
+    /// A later launch handed over an activation route.
+    Activate(crate::activation::Route),
+    /// A later launch handed over a validated configuration to install.
+    ApplyConfiguration(Box<PersistentState>),
 }
 
 #[cfg(test)]
 #[allow(clippy::float_cmp)]
 mod tests {
-    use super::{DeviceSettings, DeviceType, VolumePercent, VolumeScalar};
+    use super::{DeviceSettings, DeviceType, UserEvent, VolumePercent, VolumeScalar};
+
+    /// Workers hand changes to the event loop as events, so they must be sendable.
+    #[test]
+    fn user_event_is_send() {
+        fn assert_send<T: Send + 'static>() {}
+        assert_send::<UserEvent>();
+    }
 
     #[test]
     fn device_type_serialization_roundtrip() {
@@ -271,6 +305,8 @@ mod tests {
         assert!(!settings.unmute_lock.notify);
         assert_eq!(settings.device_type, DeviceType::Output);
         assert_eq!(settings.name, "Test");
+        assert!(settings.tag.is_none());
+        assert_eq!(settings.display_name(), "Test");
     }
 
     #[test]
@@ -288,6 +324,7 @@ mod tests {
             },
             device_type: DeviceType::Input,
             name: "Microphone".into(),
+            tag: Some("🎤".into()),
         };
         let json = serde_json::to_string(&settings).unwrap();
         let loaded: DeviceSettings = serde_json::from_str(&json).unwrap();
@@ -298,6 +335,8 @@ mod tests {
         assert!(!loaded.unmute_lock.notify);
         assert_eq!(loaded.device_type, DeviceType::Input);
         assert_eq!(loaded.name, "Microphone");
+        assert_eq!(loaded.tag.as_deref(), Some("🎤"));
+        assert_eq!(loaded.display_name(), "🎤 Microphone");
     }
 
     #[test]
@@ -1,15 +1,20 @@
-use super::{AppAction, DeviceAction, MenuAction, MenuItemInfo, PreferenceAction};
-use crate::audio::AudioBackend;
-use crate::config::PersistentState;
+use super::{AppAction, BulkSetting, DeviceAction, MenuAction, MenuItemInfo, PreferenceAction};
+use crate::audio::{AudioBackend, DeviceCapabilities};
+use crate::audit::{
+    self, AuditEvent, AuditRecord, AuditTrigger, HistoryChoices, HistoryFilter, render_timeline,
+};
+use crate::config::{PersistentState, parse_soundswitch_config, soundswitch_config_path};
 use crate::consts::GITHUB_REPO_URL;
-use crate::notification::log_and_notify_error;
+use crate::notification::Notifier;
 use crate::platform::{
-    open_device_settings, open_devices_list, open_sound_control_panel, open_sound_settings,
-    open_volume_mixer,
+    ViewerFilter, confirm, open_device_settings, open_devices_list, open_sound_control_panel,
+    open_sound_settings, open_volume_mixer, show_text_viewer,
 };
-use crate::types::{DeviceId, DeviceType, TemporaryPriorities};
+use crate::profile::data_directory;
+use crate::types::{DeviceId, DeviceRole, DeviceType, TemporaryPriorities};
 use crate::update::UpdateInfo;
-use crate::utils::{get_executable_directory, open_path, open_url};
+use crate::utils::{open_path, open_url};
+use anyhow::Context;
 use tray_icon::menu::Menu;
 
 use super::find_menu_item;
@@ -44,16 +49,21 @@ pub enum MenuEventResult {
     UpdateCheck,
     UpdatePerform(UpdateInfo),
     ToggleAutoLaunch(bool),
+    RestoreAll,
+    /// The device was forgotten; the record goes into the event history once
+    /// the change is saved.
+    DeviceForgotten(AuditRecord),
 }
 
 /// Returns `true` if the device has no active locks or notifications,
 /// meaning its settings entry can be removed when not in a priority list.
 #[cfg(test)]
 fn device_settings_are_empty(settings: &crate::types::DeviceSettings) -> bool {
-    !settings.has_active_locks_or_notifications()
+    !settings.has_user_settings()
 }
 
-/// Applies a device lock/notify toggle to the device's settings entry.
+/// Applies a device lock/notify toggle to the device's settings entry. Fails if
+/// a volume lock can't read the current volume, leaving the lock off.
 fn apply_device_lock_toggle(
     action: &DeviceAction,
     is_checked: bool,
@@ -62,7 +72,7 @@ fn apply_device_lock_toggle(
     device_type: DeviceType,
     persistent_state: &mut PersistentState,
     backend: &impl AudioBackend,
-) {
+) -> anyhow::Result<()> {
     let device_settings = persistent_state.ensure_device_settings(
         device_id.clone(),
         device_name.to_string(),
@@ -78,11 +88,8 @@ fn apply_device_lock_toggle(
                     device_settings.volume_lock.target_percent = vol.to_percent();
                     device_settings.volume_lock.is_locked = true;
                 } else {
-                    log_and_notify_error(
-                        "Failed to Lock Volume",
-                        &format!("Failed to get volume for device {device_name}, cannot lock."),
-                    );
                     device_settings.volume_lock.is_locked = false;
+                    anyhow::bail!("Failed to get volume for device {device_name}, cannot lock.");
                 }
             } else {
                 device_settings.volume_lock.is_locked = false;
@@ -99,6 +106,189 @@ fn apply_device_lock_toggle(
         }
         _ => {}
     }
+    Ok(())
+}
+
+/// Makes the device the default for the roles implied by `action` as a one-off
+/// switch, without touching the priority lists.
+fn set_default_device_now(
+    action: &DeviceAction,
+    device_id: &DeviceId,
+    device_name: &str,
+    device_type: DeviceType,
+    backend: &impl AudioBackend,
+    notifier: &dyn Notifier,
+) -> bool {
+    let roles: &[DeviceRole] = match action {
+        DeviceAction::SetAsDefault => &[DeviceRole::Console, DeviceRole::Multimedia],
+        DeviceAction::SetAsDefaultCommunication => &[DeviceRole::Communications],
+        _ => &[],
+    };
+    for &role in roles {
+        let previous_id = backend
+            .default_device(device_type, role)
+            .map_or_else(|_| "none".to_string(), |d| d.id().to_string());
+        if let Err(e) = backend.set_default_device(device_id, role) {
+            notifier.log_and_notify_error(
+                "Failed to Set Default Device",
+                &format!("Failed to set {device_name} as default {role} device: {e:#}"),
+            );
+            return false;
+        }
+        log::info!("Set {device_name} as default {role} device");
+        audit::record(&AuditRecord {
+            event: AuditEvent::DefaultDeviceSwitch,
+            trigger: AuditTrigger::User,
+            device_id: device_id.clone(),
+            device_name: device_name.to_string(),
+            role: Some(role),
+            before: previous_id,
+            after: device_id.to_string(),
+        });
+    }
+    true
+}
+
+/// Drops everything remembered about the device. Returns a record of what was
+/// dropped for the event history, or `None` if nothing was remembered.
+fn forget_device(
+    device_id: &DeviceId,
+    device_name: &str,
+    persistent_state: &mut PersistentState,
+) -> Option<AuditRecord> {
+    let before = describe_remembered_device(device_id, persistent_state);
+    if !persistent_state.forget_device(device_id) {
+        return None;
+    }
+    log::info!("Forgot device {device_name} ({device_id})");
+    Some(AuditRecord {
+        event: AuditEvent::Forget,
+        trigger: AuditTrigger::User,
+        device_id: device_id.clone(),
+        device_name: device_name.to_string(),
+        role: None,
+        before,
+        after: "forgotten".to_string(),
+    })
+}
+
+/// Summarizes the device's saved settings and its priority list positions.
+fn describe_remembered_device(device_id: &DeviceId, persistent_state: &PersistentState) -> String {
+    let mut parts = Vec::new();
+    if let Some(settings) = persistent_state.device_settings(device_id) {
+        match serde_json::to_string(settings) {
+            Ok(json) => parts.push(format!("settings={json}")),
+            Err(e) => log::warn!("Failed to serialize settings of {device_id}: {e:#}"),
+        }
+    }
+    for device_type in [DeviceType::Output, DeviceType::Input] {
+        if let Some(position) = persistent_state
+            .priority_list(device_type)
+            .iter()
+            .position(|id| id == device_id)
+        {
+            parts.push(format!("{device_type:?} priority #{}", position + 1));
+        }
+    }
+    if parts.is_empty() {
+        "nothing".to_string()
+    } else {
+        parts.join("; ")
+    }
+}
+
+/// Sets the device as default, locks its volume at the current level and
+/// enables the unmute lock in a single action. Locks the device can't support
+/// are left off and reported.
+fn apply_switch_and_lock(
+    device_id: &DeviceId,
+    device_name: &str,
+    device_type: DeviceType,
+    persistent_state: &mut PersistentState,
+    backend: &impl AudioBackend,
+    notifier: &dyn Notifier,
+) {
+    set_default_device_now(
+        &DeviceAction::SetAsDefault,
+        device_id,
+        device_name,
+        device_type,
+        backend,
+        notifier,
+    );
+    // A missing device is reported by the volume lock itself
+    let capabilities = backend
+        .device_by_id(device_id)
+        .map_or(DeviceCapabilities::ALL, |device| device.capabilities());
+    let mut skipped = Vec::new();
+    for (lock_action, supported, label) in [
+        (DeviceAction::VolumeLock, capabilities.volume, "volume lock"),
+        (DeviceAction::UnmuteLock, capabilities.mute, "unmute lock"),
+    ] {
+        if !supported {
+            skipped.push(label);
+            continue;
+        }
+        if let Err(e) = apply_device_lock_toggle(
+            &lock_action,
+            true,
+            device_id,
+            device_name,
+            device_type,
+            persistent_state,
+            backend,
+        ) {
+            notifier.log_and_notify_error("Failed to Lock Volume", &format!("{e:#}"));
+        }
+    }
+    if !skipped.is_empty() {
+        let message = format!(
+            "{device_name} does not support the {}, so it was left off.",
+            skipped.join(" or the ")
+        );
+        log::info!("{message}");
+        notifier.respond("Lock Not Supported", &message);
+    }
+}
+
+/// Returns whether a device offered in the "Add device" submenu can still be
+/// added: it is either connected or one the app already remembers.
+fn can_add_to_priority(
+    device_id: &DeviceId,
+    device_type: DeviceType,
+    persistent_state: &PersistentState,
+    backend: &impl AudioBackend,
+) -> bool {
+    persistent_state.device_settings(device_id).is_some()
+        || backend
+            .devices(device_type)
+            .is_ok_and(|devices| devices.iter().any(|device| device.id() == device_id))
+}
+
+/// Mutes every active input device. Returns how many were muted.
+fn mute_all_inputs(backend: &impl AudioBackend, notifier: &dyn Notifier) -> usize {
+    let devices = match backend.devices(DeviceType::Input) {
+        Ok(devices) => devices,
+        Err(e) => {
+            notifier.log_and_notify_error(
+                "Failed to Mute Inputs",
+                &format!("Failed to list input devices: {e:#}"),
+            );
+            return 0;
+        }
+    };
+    let mut muted = 0;
+    for device in devices {
+        match device.set_mute(true) {
+            Ok(()) => muted += 1,
+            Err(e) => notifier.log_and_notify_error(
+                "Failed to Mute Input",
+                &format!("Failed to mute {}: {e:#}", device.name()),
+            ),
+        }
+    }
+    log::info!("Muted {muted} input devices on request");
+    muted
 }
 
 fn handle_priority_event(
@@ -136,7 +326,8 @@ fn handle_priority_event(
         DeviceAction::MovePriorityUp
         | DeviceAction::MovePriorityDown
         | DeviceAction::MovePriorityToTop
-        | DeviceAction::MovePriorityToBottom => {
+        | DeviceAction::MovePriorityToBottom
+        | DeviceAction::MovePriorityToPosition(_) => {
             move_priority_item(action, device_id, device_type, persistent_state)
         }
         _ => false,
@@ -173,6 +364,11 @@ fn move_priority_item(
             list.push(id);
             true
         }
+        DeviceAction::MovePriorityToPosition(target) if *target != pos && *target < list.len() => {
+            let id = list.remove(pos);
+            list.insert(*target, id);
+            true
+        }
         _ => false,
     }
 }
@@ -181,6 +377,7 @@ pub struct MenuEventContext<'a, B: AudioBackend> {
     pub tray_menu: &'a Menu,
     pub persistent_state: &'a mut PersistentState,
     pub backend: &'a B,
+    pub notifier: &'a dyn Notifier,
     pub temporary_priorities: &'a mut TemporaryPriorities,
     pub update_info: &'a Option<UpdateInfo>,
 }
@@ -218,7 +415,7 @@ fn handle_device_event(
         | DeviceAction::UnmuteLock
         | DeviceAction::UnmuteLockNotify => {
             if let Some(is_checked) = get_check_item_state(ctx.tray_menu, &event.id) {
-                apply_device_lock_toggle(
+                if let Err(e) = apply_device_lock_toggle(
                     action,
                     is_checked,
                     device_id,
@@ -226,19 +423,35 @@ fn handle_device_event(
                     device_type,
                     ctx.persistent_state,
                     ctx.backend,
-                );
+                ) {
+                    ctx.notifier
+                        .log_and_notify_error("Failed to Lock Volume", &format!("{e:#}"));
+                }
                 ctx.persistent_state.remove_device_if_unused(device_id);
                 MenuEventResult::SaveConfig
             } else {
                 MenuEventResult::NoChange
             }
         }
+        DeviceAction::AddToPriority
+            if !can_add_to_priority(device_id, device_type, ctx.persistent_state, ctx.backend) =>
+        {
+            log::warn!("Not adding {device_name} ({device_id}) to priority: device is gone");
+            ctx.notifier.respond(
+                "Device Not Found",
+                &format!(
+                    "{device_name} was disconnected before it could be added to the priority list."
+                ),
+            );
+            MenuEventResult::DevicesChanged
+        }
         DeviceAction::AddToPriority
         | DeviceAction::RemoveFromPriority
         | DeviceAction::MovePriorityUp
         | DeviceAction::MovePriorityDown
         | DeviceAction::MovePriorityToTop
-        | DeviceAction::MovePriorityToBottom => {
+        | DeviceAction::MovePriorityToBottom
+        | DeviceAction::MovePriorityToPosition(_) => {
             if handle_priority_event(
                 action,
                 device_id,
@@ -263,6 +476,45 @@ fn handle_device_event(
             );
             MenuEventResult::DevicesChanged
         }
+        DeviceAction::ForgetDevice => {
+            if ctx.temporary_priorities.get(device_type) == Some(device_id) {
+                ctx.temporary_priorities.set(device_type, None);
+            }
+            forget_device(device_id, device_name, ctx.persistent_state)
+                .map_or(MenuEventResult::NoChange, MenuEventResult::DeviceForgotten)
+        }
+        DeviceAction::SetTag(tag) => {
+            ctx.persistent_state
+                .ensure_device_settings(device_id.clone(), device_name.to_string(), device_type)
+                .tag
+                .clone_from(tag);
+            if tag.is_none() {
+                ctx.persistent_state.remove_device_if_unused(device_id);
+            }
+            MenuEventResult::SaveConfig
+        }
+        DeviceAction::SetAsDefault | DeviceAction::SetAsDefaultCommunication => {
+            set_default_device_now(
+                action,
+                device_id,
+                device_name,
+                device_type,
+                ctx.backend,
+                ctx.notifier,
+            );
+            MenuEventResult::NoChange
+        }
+        DeviceAction::SwitchAndLock => {
+            apply_switch_and_lock(
+                device_id,
+                device_name,
+                device_type,
+                ctx.persistent_state,
+                ctx.backend,
+                ctx.notifier,
+            );
+            MenuEventResult::SaveConfig
+        }
         DeviceAction::OpenProperties => {
             let tab = match device_type {
                 DeviceType::Output => "0",
@@ -279,9 +531,94 @@ fn handle_device_event(
             }
             MenuEventResult::NoChange
         }
+        DeviceAction::OpenEventHistory => {
+            open_event_history(
+                &HistoryFilter {
+                    event: None,
+                    device: Some((device_id.clone(), device_name.to_string())),
+                },
+                ctx.notifier,
+            );
+            MenuEventResult::NoChange
+        }
     }
 }
 
+/// Opens the event history in a window that narrows it down by event type and
+/// device, starting with `filter`. Nothing is written to disk.
+fn open_event_history(filter: &HistoryFilter, notifier: &dyn Notifier) {
+    let choices = HistoryChoices::new(&audit::history(), filter);
+    let [event, device] = choices.position_of(filter);
+    let filters = vec![
+        ViewerFilter {
+            choices: choices.event_labels(),
+            selected: event,
+        },
+        ViewerFilter {
+            choices: choices.device_labels(),
+            selected: device,
+        },
+    ];
+    let result = show_text_viewer("Volume Locker Event History", filters, move |selected| {
+        render_timeline(&audit::history(), &choices.filter_at(selected))
+    });
+    if let Err(e) = result {
+        notifier.log_and_notify_error(
+            "Failed to Open Event History",
+            &format!("Failed to open event history: {e:#}"),
+        );
+    }
+}
+
+/// Reads SoundSwitch's settings, previews them and, once confirmed, merges
+/// them into the priority lists.
+fn import_from_soundswitch(
+    persistent_state: &mut PersistentState,
+    notifier: &dyn Notifier,
+) -> MenuEventResult {
+    let import = soundswitch_config_path()
+        .context("could not locate the AppData folder")
+        .and_then(|path| {
+            let json = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read '{}'", path.display()))?;
+            parse_soundswitch_config(&json)
+        });
+    let import = match import {
+        Ok(import) => import,
+        Err(e) => {
+            notifier.log_and_notify_error(
+                "SoundSwitch Import Failed",
+                &format!("Could not read the SoundSwitch settings: {e:#}"),
+            );
+            return MenuEventResult::NoChange;
+        }
+    };
+    if import.is_empty() {
+        log::info!("Nothing to import from SoundSwitch");
+        notifier.respond(
+            "Nothing to Import",
+            "SoundSwitch has no playback or recording devices selected.",
+        );
+        return MenuEventResult::NoChange;
+    }
+
+    let message = format!(
+        "The following settings will be imported from SoundSwitch:\n\n{}\n\nImport them?",
+        import.preview()
+    );
+    if !confirm("Import from SoundSwitch", &message) {
+        log::info!("SoundSwitch import cancelled");
+        return MenuEventResult::NoChange;
+    }
+    import.apply_to(persistent_state);
+    log::info!(
+        "Imported {} output and {} input devices from SoundSwitch",
+        import.output.len(),
+        import.input.len()
+    );
+    MenuEventResult::SaveConfig
+}
+
 fn handle_preference_event(
     event: &tray_icon::menu::MenuEvent,
     action: &PreferenceAction,
@@ -307,7 +644,105 @@ fn handle_preference_event(
             }
             MenuEventResult::NoChange
         }
+        PreferenceAction::ApplyToAllDevices(setting) => {
+            let (updated, _) = apply_to_all_devices(
+                *setting,
+                device_type,
+                ctx.persistent_state,
+                ctx.backend,
+                ctx.notifier,
+            );
+            if updated > 0 {
+                MenuEventResult::SaveConfig
+            } else {
+                MenuEventResult::NoChange
+            }
+        }
+    }
+}
+
+/// Whether a device with `capabilities` can honor `setting`.
+fn bulk_setting_is_supported(setting: BulkSetting, capabilities: DeviceCapabilities) -> bool {
+    match setting {
+        BulkSetting::LockVolume(_) => capabilities.volume,
+        BulkSetting::KeepUnmuted => capabilities.mute,
+        BulkSetting::EnableNotifications => true,
+    }
+}
+
+/// Applies `setting` to every active device of `device_type`. All changes are
+/// made in memory, so the caller saves them once. Devices that can't support
+/// the setting are skipped and reported. Returns how many devices were updated
+/// and how many were skipped.
+fn apply_to_all_devices(
+    setting: BulkSetting,
+    device_type: DeviceType,
+    persistent_state: &mut PersistentState,
+    backend: &impl AudioBackend,
+    notifier: &dyn Notifier,
+) -> (usize, usize) {
+    let devices = match backend.devices(device_type) {
+        Ok(devices) => devices,
+        Err(e) => {
+            notifier.log_and_notify_error(
+                "Failed to Apply Setting",
+                &format!("Failed to get {device_type} devices: {e:#}"),
+            );
+            return (0, 0);
+        }
+    };
+    let mut updated = 0;
+    let mut skipped = 0;
+    for device in devices {
+        if !bulk_setting_is_supported(setting, device.capabilities()) {
+            log::info!(
+                "Not applying {setting:?} to {}: not supported",
+                device.name()
+            );
+            skipped += 1;
+            continue;
+        }
+        let target_percent = match setting {
+            BulkSetting::LockVolume(None) => match device.volume() {
+                Ok(volume) => Some(volume.to_percent()),
+                Err(e) => {
+                    log::warn!("Not locking {}: failed to get volume: {e:#}", device.name());
+                    continue;
+                }
+            },
+            BulkSetting::LockVolume(percent) => percent,
+            BulkSetting::KeepUnmuted | BulkSetting::EnableNotifications => None,
+        };
+        let settings = persistent_state.ensure_device_settings(
+            device.id().clone(),
+            device.name(),
+            device_type,
+        );
+        match setting {
+            BulkSetting::LockVolume(_) => {
+                settings.volume_lock.is_locked = true;
+                if let Some(percent) = target_percent {
+                    settings.volume_lock.target_percent = percent;
+                }
+            }
+            BulkSetting::KeepUnmuted => settings.unmute_lock.is_locked = true,
+            BulkSetting::EnableNotifications => {
+                settings.volume_lock.notify = true;
+                settings.unmute_lock.notify = true;
+            }
+        }
+        updated += 1;
     }
+    log::info!("Applied {setting:?} to {updated} {device_type} devices, skipped {skipped}");
+    if skipped > 0 {
+        notifier.respond(
+            "Some Devices Skipped",
+            &format!(
+                "{skipped} {device_type} device(s) don't support this setting and were left unchanged."
+            ),
+        );
+    }
+    (updated, skipped)
 }
 
 fn handle_app_event(
@@ -329,8 +764,16 @@ fn handle_app_event(
             MenuEventResult::NoChange
         }
         AppAction::CheckForUpdates => MenuEventResult::UpdateCheck,
+        AppAction::RestoreAllLocks => MenuEventResult::RestoreAll,
         AppAction::PerformUpdate => {
-            if let Some(info) = ctx.update_info {
+            if let Some(info) = ctx.update_info
+                && ctx.persistent_state.minimal_footprint
+            {
+                if let Err(e) = open_url(&info.release_url) {
+                    log::error!("Failed to open release page: {e:#}");
+                }
+                MenuEventResult::NoChange
+            } else if let Some(info) = ctx.update_info {
                 MenuEventResult::UpdatePerform(info.clone())
             } else {
                 MenuEventResult::NoChange
@@ -344,6 +787,10 @@ fn handle_app_event(
         }
         AppAction::ToggleAutoLaunch => {
             if let Some(checked) = get_check_item_state(ctx.tray_menu, &event.id) {
+                if checked && ctx.persistent_state.minimal_footprint {
+                    // Only turning an existing entry off is allowed in minimal footprint mode
+                    return MenuEventResult::DevicesChanged;
+                }
                 MenuEventResult::ToggleAutoLaunch(checked)
             } else {
                 MenuEventResult::NoChange
@@ -354,14 +801,38 @@ fn handle_app_event(
                 ctx.persistent_state.check_updates_on_launch = checked;
             })
         }
+        AppAction::ToggleYieldToConflictingApps => {
+            with_check_state(ctx.tray_menu, &event.id, |checked| {
+                ctx.persistent_state.yield_to_conflicting_apps = checked;
+            })
+        }
+        AppAction::ToggleEcoMode => with_check_state(ctx.tray_menu, &event.id, |checked| {
+            ctx.persistent_state.eco_mode = checked;
+        }),
+        AppAction::ToggleMuteInputs => with_check_state(ctx.tray_menu, &event.id, |checked| {
+            ctx.persistent_state.mute_inputs = checked;
+            if checked {
+                mute_all_inputs(ctx.backend, ctx.notifier);
+            }
+        }),
+        AppAction::ToggleAlertLiveMics => with_check_state(ctx.tray_menu, &event.id, |checked| {
+            ctx.persistent_state.alert_live_mics = checked;
+        }),
+        AppAction::ImportFromSoundSwitch => {
+            import_from_soundswitch(ctx.persistent_state, ctx.notifier)
+        }
+        AppAction::OpenEventHistory => {
+            open_event_history(&HistoryFilter::default(), ctx.notifier);
+            MenuEventResult::NoChange
+        }
         AppAction::OpenAppDirectory => {
-            match get_executable_directory() {
+            match data_directory() {
                 Ok(dir) => {
                     if let Err(e) = open_path(&dir) {
                         log::error!("Failed to open app directory: {e:#}");
                     }
                 }
-                Err(e) => log::error!("Failed to get executable directory: {e:#}"),
+                Err(e) => log::error!("Failed to get app directory: {e:#}"),
             }
             MenuEventResult::NoChange
         }
@@ -1,10 +1,10 @@
 #![allow(clippy::expect_used)]
 
 use super::{
-    DeviceAction, DeviceId, DeviceType, PersistentState, device_settings_are_empty,
+    DeviceAction, DeviceId, DeviceType, PersistentState, device_settings_are_empty, forget_device,
     handle_priority_event,
 };
-use crate::types::DeviceSettings;
+use crate::types::{DeviceRole, DeviceSettings};
 
 #[test]
 fn device_settings_empty_when_all_false() {
@@ -151,6 +151,42 @@ fn priority_move_to_bottom() {
     assert_eq!(state.priority_list(DeviceType::Output), &["b", "c", "a"]);
 }
 
+#[test]
+fn priority_move_to_position() {
+    let mut state = PersistentState::default();
+    *state.priority_list_mut(DeviceType::Output) =
+        vec!["a".into(), "b".into(), "c".into(), "d".into()];
+    let changed = handle_priority_event(
+        &DeviceAction::MovePriorityToPosition(1),
+        &DeviceId::from("d"),
+        DeviceType::Output,
+        "D",
+        &mut state,
+    );
+    assert!(changed);
+    assert_eq!(
+        state.priority_list(DeviceType::Output),
+        &["a", "d", "b", "c"]
+    );
+}
+
+#[test]
+fn priority_move_to_same_or_invalid_position_no_op() {
+    let mut state = PersistentState::default();
+    *state.priority_list_mut(DeviceType::Output) = vec!["a".into(), "b".into()];
+    for position in [1, 2] {
+        let changed = handle_priority_event(
+            &DeviceAction::MovePriorityToPosition(position),
+            &DeviceId::from("b"),
+            DeviceType::Output,
+            "B",
+            &mut state,
+        );
+        assert!(!changed);
+    }
+    assert_eq!(state.priority_list(DeviceType::Output), &["a", "b"]);
+}
+
 #[test]
 fn priority_input_type_uses_input_list() {
     let mut state = PersistentState::default();
@@ -165,9 +201,66 @@ fn priority_input_type_uses_input_list() {
     assert_eq!(state.priority_list(DeviceType::Input), &["mic1"]);
 }
 
+// --- can_add_to_priority tests ---
+
+use super::can_add_to_priority;
+
+#[test]
+fn can_add_connected_device() {
+    let backend = make_backend_with_device("dev1", "Speaker");
+    let state = PersistentState::default();
+    assert!(can_add_to_priority(
+        &DeviceId::from("dev1"),
+        DeviceType::Output,
+        &state,
+        &backend
+    ));
+}
+
+#[test]
+fn can_add_remembered_disconnected_device() {
+    let backend = MockAudioBackend::new(Vec::new());
+    let state = make_state_with_device("dev1", DeviceType::Output);
+    assert!(can_add_to_priority(
+        &DeviceId::from("dev1"),
+        DeviceType::Output,
+        &state,
+        &backend
+    ));
+}
+
+#[test]
+fn cannot_add_device_that_disappeared() {
+    let backend = make_backend_with_device("other", "Headphones");
+    let state = PersistentState::default();
+    assert!(!can_add_to_priority(
+        &DeviceId::from("dev1"),
+        DeviceType::Output,
+        &state,
+        &backend
+    ));
+}
+
+// --- mute_all_inputs tests ---
+
+use super::mute_all_inputs;
+
+#[test]
+fn mute_all_inputs_mutes_only_inputs() {
+    let mut mic = MockDevice::new("mic1", "Microphone", true);
+    mic.device_type = DeviceType::Input;
+    let mut headset = MockDevice::new("mic2", "Headset", true);
+    headset.device_type = DeviceType::Input;
+    let speaker = MockDevice::new("spk", "Speaker", true);
+    let backend = MockAudioBackend::new(vec![mic, headset, speaker]);
+
+    assert_eq!(mute_all_inputs(&backend, &RecordingNotifier::default()), 2);
+}
+
 // --- apply_device_lock_toggle tests ---
 
 use super::apply_device_lock_toggle;
+use crate::audio::DeviceCapabilities;
 use crate::audio::tests::MockAudioBackend;
 use crate::audio::tests::MockDevice;
 
@@ -190,7 +283,8 @@ fn volume_lock_enable_captures_current_volume() {
         DeviceType::Output,
         &mut state,
         &backend,
-    );
+    )
+    .expect("lock toggle should succeed");
 
     let settings = state
         .devices
@@ -215,7 +309,8 @@ fn volume_lock_disable_clears_locked_state() {
         DeviceType::Output,
         &mut state,
         &backend,
-    );
+    )
+    .expect("lock toggle should succeed");
     assert!(
         state
             .devices
@@ -234,7 +329,8 @@ fn volume_lock_disable_clears_locked_state() {
         DeviceType::Output,
         &mut state,
         &backend,
-    );
+    )
+    .expect("lock toggle should succeed");
     assert!(
         !state
             .devices
@@ -251,14 +347,17 @@ fn volume_lock_fails_when_device_not_found() {
     let backend = MockAudioBackend::new(vec![]);
     let mut state = PersistentState::default();
 
-    apply_device_lock_toggle(
-        &DeviceAction::VolumeLock,
-        true,
-        &DeviceId::from("missing"),
-        "Ghost",
-        DeviceType::Output,
-        &mut state,
-        &backend,
+    assert!(
+        apply_device_lock_toggle(
+            &DeviceAction::VolumeLock,
+            true,
+            &DeviceId::from("missing"),
+            "Ghost",
+            DeviceType::Output,
+            &mut state,
+            &backend,
+        )
+        .is_err()
     );
 
     let settings = state
@@ -281,7 +380,8 @@ fn unmute_lock_toggle() {
         DeviceType::Output,
         &mut state,
         &backend,
-    );
+    )
+    .expect("lock toggle should succeed");
     assert!(
         state
             .devices
@@ -299,7 +399,8 @@ fn unmute_lock_toggle() {
         DeviceType::Output,
         &mut state,
         &backend,
-    );
+    )
+    .expect("lock toggle should succeed");
     assert!(
         !state
             .devices
@@ -323,7 +424,8 @@ fn notify_toggles_independent_of_lock() {
         DeviceType::Output,
         &mut state,
         &backend,
-    );
+    )
+    .expect("lock toggle should succeed");
     let settings = state
         .devices
         .get("dev1")
@@ -346,7 +448,8 @@ fn empty_settings_detected_after_all_unlocked() {
         DeviceType::Output,
         &mut state,
         &backend,
-    );
+    )
+    .expect("lock toggle should succeed");
     apply_device_lock_toggle(
         &DeviceAction::VolumeLock,
         false,
@@ -355,7 +458,8 @@ fn empty_settings_detected_after_all_unlocked() {
         DeviceType::Output,
         &mut state,
         &backend,
-    );
+    )
+    .expect("lock toggle should succeed");
 
     let settings = state
         .devices
@@ -363,3 +467,324 @@ fn empty_settings_detected_after_all_unlocked() {
         .expect("device should exist after lock cycle");
     assert!(device_settings_are_empty(settings));
 }
+
+// --- set_default_device_now tests ---
+
+use super::set_default_device_now;
+use crate::audio::AudioBackend;
+
+#[test]
+fn set_as_default_switches_console_and_multimedia() {
+    let backend = make_backend_with_device("dev1", "Speaker");
+    let state = PersistentState::default();
+
+    assert!(set_default_device_now(
+        &DeviceAction::SetAsDefault,
+        &DeviceId::from("dev1"),
+        "Speaker",
+        DeviceType::Output,
+        &backend,
+        &RecordingNotifier::default(),
+    ));
+
+    for role in [DeviceRole::Console, DeviceRole::Multimedia] {
+        let default = backend
+            .default_device(DeviceType::Output, role)
+            .expect("default device should be set");
+        assert_eq!(default.id(), "dev1");
+    }
+    assert!(
+        backend
+            .default_device(DeviceType::Output, DeviceRole::Communications)
+            .is_err()
+    );
+    // One-off switch must not touch the priority list
+    assert!(state.priority_list(DeviceType::Output).is_empty());
+}
+
+#[test]
+fn set_as_default_communication_only_switches_communications() {
+    let backend = make_backend_with_device("dev1", "Speaker");
+
+    assert!(set_default_device_now(
+        &DeviceAction::SetAsDefaultCommunication,
+        &DeviceId::from("dev1"),
+        "Speaker",
+        DeviceType::Output,
+        &backend,
+        &RecordingNotifier::default(),
+    ));
+
+    let default = backend
+        .default_device(DeviceType::Output, DeviceRole::Communications)
+        .expect("default communications device should be set");
+    assert_eq!(default.id(), "dev1");
+    assert!(
+        backend
+            .default_device(DeviceType::Output, DeviceRole::Console)
+            .is_err()
+    );
+}
+
+#[test]
+fn set_as_default_reports_backend_failure() {
+    let backend = make_backend_with_device("dev1", "Speaker");
+    *backend.set_default_fails.borrow_mut() = true;
+    let notifier = RecordingNotifier::default();
+
+    assert!(!set_default_device_now(
+        &DeviceAction::SetAsDefault,
+        &DeviceId::from("dev1"),
+        "Speaker",
+        DeviceType::Output,
+        &backend,
+        &notifier,
+    ));
+    assert_eq!(notifier.titles(), ["Failed to Set Default Device"]);
+}
+
+// --- apply_switch_and_lock tests ---
+
+use super::apply_switch_and_lock;
+use crate::notification::tests::RecordingNotifier;
+
+#[test]
+fn switch_and_lock_sets_default_and_enables_locks() {
+    let backend = make_backend_with_device("dev1", "Speaker");
+    let mut state = PersistentState::default();
+
+    apply_switch_and_lock(
+        &DeviceId::from("dev1"),
+        "Speaker",
+        DeviceType::Output,
+        &mut state,
+        &backend,
+        &RecordingNotifier::default(),
+    );
+
+    let default = backend
+        .default_device(DeviceType::Output, DeviceRole::Console)
+        .expect("default device should be set");
+    assert_eq!(default.id(), "dev1");
+
+    let settings = state
+        .devices
+        .get("dev1")
+        .expect("device settings should exist after switch and lock");
+    assert!(settings.volume_lock.is_locked);
+    assert_eq!(settings.volume_lock.target_percent, 100.0);
+    assert!(settings.unmute_lock.is_locked);
+}
+
+#[test]
+fn switch_and_lock_still_locks_when_switch_fails() {
+    let backend = make_backend_with_device("dev1", "Speaker");
+    *backend.set_default_fails.borrow_mut() = true;
+    let mut state = PersistentState::default();
+
+    apply_switch_and_lock(
+        &DeviceId::from("dev1"),
+        "Speaker",
+        DeviceType::Output,
+        &mut state,
+        &backend,
+        &RecordingNotifier::default(),
+    );
+
+    let settings = state
+        .devices
+        .get("dev1")
+        .expect("device settings should exist after switch and lock");
+    assert!(settings.volume_lock.is_locked);
+    assert!(settings.unmute_lock.is_locked);
+}
+
+#[test]
+fn switch_and_lock_skips_unsupported_locks() {
+    let mut device = MockDevice::new("dev1", "Speaker", true);
+    device.capabilities = DeviceCapabilities {
+        volume: false,
+        mute: true,
+    };
+    let backend = MockAudioBackend::new(vec![device]);
+    let mut state = PersistentState::default();
+    let notifier = RecordingNotifier::default();
+
+    apply_switch_and_lock(
+        &DeviceId::from("dev1"),
+        "Speaker",
+        DeviceType::Output,
+        &mut state,
+        &backend,
+        &notifier,
+    );
+
+    let settings = state
+        .devices
+        .get("dev1")
+        .expect("device settings should exist after switch and lock");
+    assert!(!settings.volume_lock.is_locked);
+    assert!(settings.unmute_lock.is_locked);
+    assert_eq!(notifier.titles(), ["Lock Not Supported"]);
+}
+
+// --- apply_to_all_devices tests ---
+
+use super::apply_to_all_devices;
+use crate::types::VolumePercent;
+use crate::ui::BulkSetting;
+
+fn make_backend_with_mixed_devices() -> MockAudioBackend {
+    let mut mic = MockDevice::new("mic1", "Mic", true);
+    mic.device_type = DeviceType::Input;
+    MockAudioBackend::new(vec![
+        MockDevice::new("spk1", "Speakers", true),
+        MockDevice::new("spk2", "Headphones", true),
+        mic,
+    ])
+}
+
+#[test]
+fn bulk_lock_at_level_applies_to_every_device_of_type() {
+    let backend = make_backend_with_mixed_devices();
+    let mut state = PersistentState::default();
+
+    let (updated, _) = apply_to_all_devices(
+        BulkSetting::LockVolume(Some(VolumePercent::from(50.0))),
+        DeviceType::Output,
+        &mut state,
+        &backend,
+        &RecordingNotifier::default(),
+    );
+
+    assert_eq!(updated, 2);
+    for id in ["spk1", "spk2"] {
+        let settings = state.device_settings(&DeviceId::from(id)).expect("tracked");
+        assert!(settings.volume_lock.is_locked);
+        assert_eq!(settings.volume_lock.target_percent, 50.0);
+    }
+    assert!(state.device_settings(&DeviceId::from("mic1")).is_none());
+}
+
+#[test]
+fn bulk_lock_at_current_level_uses_each_device_volume() {
+    let backend = make_backend_with_mixed_devices();
+    let mut state = PersistentState::default();
+
+    apply_to_all_devices(
+        BulkSetting::LockVolume(None),
+        DeviceType::Input,
+        &mut state,
+        &backend,
+        &RecordingNotifier::default(),
+    );
+
+    let settings = state
+        .device_settings(&DeviceId::from("mic1"))
+        .expect("tracked");
+    assert!(settings.volume_lock.is_locked);
+    assert_eq!(settings.volume_lock.target_percent, 100.0);
+}
+
+#[test]
+fn bulk_keep_unmuted_and_notifications_preserve_other_settings() {
+    let backend = make_backend_with_mixed_devices();
+    let mut state = PersistentState::default();
+    state
+        .ensure_device_settings("spk1".into(), "Speakers".into(), DeviceType::Output)
+        .volume_lock
+        .is_locked = true;
+
+    apply_to_all_devices(
+        BulkSetting::KeepUnmuted,
+        DeviceType::Output,
+        &mut state,
+        &backend,
+        &RecordingNotifier::default(),
+    );
+    apply_to_all_devices(
+        BulkSetting::EnableNotifications,
+        DeviceType::Output,
+        &mut state,
+        &backend,
+        &RecordingNotifier::default(),
+    );
+
+    let settings = state
+        .device_settings(&DeviceId::from("spk1"))
+        .expect("tracked");
+    assert!(settings.volume_lock.is_locked);
+    assert!(settings.unmute_lock.is_locked);
+    assert!(settings.volume_lock.notify);
+    assert!(settings.unmute_lock.notify);
+}
+
+#[test]
+fn bulk_apply_skips_devices_without_the_control() {
+    let mut fixed = MockDevice::new("spk2", "HDMI", true);
+    fixed.capabilities = DeviceCapabilities {
+        volume: false,
+        mute: true,
+    };
+    let backend = MockAudioBackend::new(vec![MockDevice::new("spk1", "Speakers", true), fixed]);
+    let mut state = PersistentState::default();
+    let notifier = RecordingNotifier::default();
+
+    let (updated, skipped) = apply_to_all_devices(
+        BulkSetting::LockVolume(Some(VolumePercent::from(50.0))),
+        DeviceType::Output,
+        &mut state,
+        &backend,
+        &notifier,
+    );
+
+    assert_eq!((updated, skipped), (1, 1));
+    assert_eq!(notifier.titles(), ["Some Devices Skipped"]);
+    assert!(state.device_settings(&DeviceId::from("spk2")).is_none());
+
+    let (updated, skipped) = apply_to_all_devices(
+        BulkSetting::KeepUnmuted,
+        DeviceType::Output,
+        &mut state,
+        &backend,
+        &RecordingNotifier::default(),
+    );
+    assert_eq!((updated, skipped), (2, 0));
+}
+
+#[test]
+fn bulk_apply_reports_nothing_when_enumeration_fails() {
+    let backend = make_backend_with_mixed_devices();
+    *backend.service_down.borrow_mut() = true;
+    let mut state = PersistentState::default();
+
+    let (updated, _) = apply_to_all_devices(
+        BulkSetting::KeepUnmuted,
+        DeviceType::Output,
+        &mut state,
+        &backend,
+        &RecordingNotifier::default(),
+    );
+
+    assert_eq!(updated, 0);
+    assert_eq!(state.device_count(), 0);
+}
+
+#[test]
+fn forget_device_describes_what_was_dropped() {
+    let mut state = make_state_with_device("forget-dev", DeviceType::Output);
+    state
+        .priority_list_mut(DeviceType::Output)
+        .extend(["dev0".into(), "forget-dev".into()]);
+
+    let record = forget_device(&DeviceId::from("forget-dev"), "Test Device", &mut state)
+        .expect("a remembered device is forgotten");
+    assert!(forget_device(&DeviceId::from("forget-dev"), "Test Device", &mut state).is_none());
+
+    assert_eq!(record.event, crate::audit::AuditEvent::Forget);
+    assert!(record.before.contains(r#""name":"Test Device""#));
+    assert!(record.before.ends_with("Output priority #2"));
+    assert_eq!(record.after, "forgotten");
+    // Recording waits until the change is saved
+    assert!(crate::audit::tests::recorded().is_empty());
+}
@@ -1,10 +1,27 @@
-use super::{DeviceDisplayInfo, append_action_item, format_device_menu_label, register_menu_item};
+use super::{
+    DISCONNECTED_SUFFIX, DeviceDisplayInfo, append_action_item, format_device_menu_label,
+    register_menu_item,
+};
 use crate::audio::{AudioBackend, AudioDevice};
 use crate::config::PersistentState;
-use crate::types::{DeviceId, DeviceRole, DeviceType};
-use crate::ui::{DeviceAction, MenuAction, MenuIdMap, PreferenceAction};
+use crate::types::{DeviceId, DeviceRole, DeviceType, tagged_name};
+use crate::ui::{
+    AppAction, BulkSetting, DeviceAction, MenuAction, MenuIdMap, MenuItemInfo, PreferenceAction,
+};
 use tray_icon::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
 
+/// Tags offered in the menu. Any other short text can be set in the state file.
+const TAG_PRESETS: [&str; 8] = ["🎧", "🔊", "🎤", "🎙️", "🖥️", "📺", "🎮", "💻"];
+
+/// Marks a lock option the device can't honor.
+fn lock_option_label(label: &str, supported: bool) -> String {
+    if supported {
+        label.to_string()
+    } else {
+        format!("{label} (not supported by this device)")
+    }
+}
+
 pub fn build_device_submenu(
     device: &dyn AudioDevice,
     device_type: DeviceType,
@@ -36,10 +53,14 @@ pub fn build_device_submenu(
         } else {
             (false, false, false, false)
         };
+    let tag = persistent_state
+        .device_settings(device_id)
+        .and_then(|settings| settings.tag.as_deref());
 
     let is_locked = is_volume_locked || is_unmute_locked;
     let label = format_device_menu_label(&DeviceDisplayInfo {
         name: &name,
+        tag,
         volume_percent,
         is_default,
         is_locked,
@@ -48,14 +69,26 @@ pub fn build_device_submenu(
 
     let submenu = Submenu::new(&label, true);
 
-    let volume_lock_item = CheckMenuItem::new("Keep volume locked", true, is_volume_locked, None);
+    // An unsupported lock that is already on stays enabled so it can be turned off
+    let capabilities = device.capabilities();
+    let volume_lock_item = CheckMenuItem::new(
+        lock_option_label("Keep volume locked", capabilities.volume),
+        capabilities.volume || is_volume_locked,
+        is_volume_locked,
+        None,
+    );
     let volume_notify_item = CheckMenuItem::new(
         "Notify on volume restore",
         is_volume_locked,
         notify_on_volume_lock,
         None,
     );
-    let unmute_lock_item = CheckMenuItem::new("Keep unmuted", true, is_unmute_locked, None);
+    let unmute_lock_item = CheckMenuItem::new(
+        lock_option_label("Keep unmuted", capabilities.mute),
+        capabilities.mute || is_unmute_locked,
+        is_unmute_locked,
+        None,
+    );
     let unmute_notify_item = CheckMenuItem::new(
         "Notify on unmute",
         is_unmute_locked,
@@ -84,6 +117,29 @@ pub fn build_device_submenu(
     submenu.append(&unmute_notify_item)?;
     submenu.append(&PredefinedMenuItem::separator())?;
 
+    let set_default_label = match device_type {
+        DeviceType::Output => "Set as default output device",
+        DeviceType::Input => "Set as default input device",
+    };
+    let set_default_item = MenuItem::new(set_default_label, !is_default, None);
+    register(set_default_item.id().clone(), DeviceAction::SetAsDefault);
+    submenu.append(&set_default_item)?;
+
+    let set_default_comm_item = MenuItem::new("Set as default communication device", true, None);
+    register(
+        set_default_comm_item.id().clone(),
+        DeviceAction::SetAsDefaultCommunication,
+    );
+    submenu.append(&set_default_comm_item)?;
+
+    let switch_and_lock_item = MenuItem::new("Set as default and lock", true, None);
+    register(
+        switch_and_lock_item.id().clone(),
+        DeviceAction::SwitchAndLock,
+    );
+    submenu.append(&switch_and_lock_item)?;
+    submenu.append(&PredefinedMenuItem::separator())?;
+
     let properties_item = MenuItem::new("Properties...", true, None);
     register(properties_item.id().clone(), DeviceAction::OpenProperties);
     submenu.append(&properties_item)?;
@@ -92,6 +148,84 @@ pub fn build_device_submenu(
     register(settings_item.id().clone(), DeviceAction::OpenSettings);
     submenu.append(&settings_item)?;
 
+    let tag_submenu = Submenu::new("Tag", true);
+    let no_tag_item = CheckMenuItem::new("No tag", true, tag.is_none(), None);
+    register(no_tag_item.id().clone(), DeviceAction::SetTag(None));
+    tag_submenu.append(&no_tag_item)?;
+    for preset in TAG_PRESETS {
+        let item = CheckMenuItem::new(preset, true, tag == Some(preset), None);
+        register(
+            item.id().clone(),
+            DeviceAction::SetTag(Some(preset.to_string())),
+        );
+        tag_submenu.append(&item)?;
+    }
+    submenu.append(&tag_submenu)?;
+
+    let history_item = MenuItem::new("Event history...", true, None);
+    register(history_item.id().clone(), DeviceAction::OpenEventHistory);
+    submenu.append(&history_item)?;
+
+    Ok(submenu)
+}
+
+fn build_bulk_apply_submenu(
+    device_type: DeviceType,
+    map: &mut MenuIdMap,
+) -> anyhow::Result<Submenu> {
+    let label = match device_type {
+        DeviceType::Output => "Apply to all output devices",
+        DeviceType::Input => "Apply to all input devices",
+    };
+    let submenu = Submenu::new(label, true);
+    let volume_locks = [
+        (
+            "Lock volume at current level",
+            BulkSetting::LockVolume(None),
+        ),
+        (
+            "Lock volume at 25%",
+            BulkSetting::LockVolume(Some(25.0.into())),
+        ),
+        (
+            "Lock volume at 50%",
+            BulkSetting::LockVolume(Some(50.0.into())),
+        ),
+        (
+            "Lock volume at 75%",
+            BulkSetting::LockVolume(Some(75.0.into())),
+        ),
+        (
+            "Lock volume at 100%",
+            BulkSetting::LockVolume(Some(100.0.into())),
+        ),
+    ];
+    let other_settings = [
+        ("Keep unmuted", BulkSetting::KeepUnmuted),
+        ("Enable notifications", BulkSetting::EnableNotifications),
+    ];
+    for (group_index, group) in [&volume_locks[..], &other_settings[..]]
+        .into_iter()
+        .enumerate()
+    {
+        if group_index > 0 {
+            submenu.append(&PredefinedMenuItem::separator())?;
+        }
+        for &(item_label, setting) in group {
+            let item = MenuItem::new(item_label, true, None);
+            map.insert(
+                item.id().clone(),
+                MenuItemInfo {
+                    name: item_label.to_string(),
+                    action: MenuAction::Preference {
+                        device_type,
+                        action: PreferenceAction::ApplyToAllDevices(setting),
+                    },
+                },
+            );
+            submenu.append(&item)?;
+        }
+    }
     Ok(submenu)
 }
 
@@ -115,7 +249,7 @@ pub fn append_device_list_to_menu(
         .map(|d| d.id().clone())
         .ok();
 
-    for device in devices {
+    for device in &devices {
         let submenu = build_device_submenu(
             device.as_ref(),
             device_type,
@@ -126,6 +260,24 @@ pub fn append_device_list_to_menu(
         tray_menu.append(&submenu)?;
     }
 
+    // Known devices that are not connected are listed greyed out, so their
+    // settings are not mistaken for lost while they are unplugged.
+    let mut disconnected: Vec<_> = persistent_state
+        .devices_iter()
+        .filter(|(id, settings)| {
+            settings.device_type == device_type && !devices.iter().any(|d| d.id() == *id)
+        })
+        .map(|(_, settings)| tagged_name(settings.tag.as_deref(), &settings.name))
+        .collect();
+    disconnected.sort();
+    for label in disconnected {
+        tray_menu.append(&MenuItem::new(
+            format!("{label}{DISCONNECTED_SUFFIX}"),
+            false,
+            None,
+        ))?;
+    }
+
     let properties_label = match device_type {
         DeviceType::Output => "Playback devices...",
         DeviceType::Input => "Recording devices...",
@@ -139,6 +291,31 @@ pub fn append_device_list_to_menu(
             action: PreferenceAction::OpenDevicesList,
         },
     )?;
+    tray_menu.append(&build_bulk_apply_submenu(device_type, map)?)?;
+
+    if device_type == DeviceType::Input {
+        let label = "Mute all inputs";
+        let mute_item = CheckMenuItem::new(label, true, persistent_state.mute_inputs, None);
+        map.insert(
+            mute_item.id().clone(),
+            MenuItemInfo {
+                name: label.to_string(),
+                action: MenuAction::App(AppAction::ToggleMuteInputs),
+            },
+        );
+        tray_menu.append(&mute_item)?;
+
+        let label = "Alert when a lock unmutes a mic";
+        let alert_item = CheckMenuItem::new(label, true, persistent_state.alert_live_mics, None);
+        map.insert(
+            alert_item.id().clone(),
+            MenuItemInfo {
+                name: label.to_string(),
+                action: MenuAction::App(AppAction::ToggleAlertLiveMics),
+            },
+        );
+        tray_menu.append(&alert_item)?;
+    }
 
     tray_menu.append(&PredefinedMenuItem::separator())?;
 
@@ -151,7 +328,7 @@ mod tests {
 
     use super::*;
     use crate::audio::AudioDevice;
-    use crate::audio::tests::MockDevice;
+    use crate::audio::tests::{MockAudioBackend, MockDevice};
 
     #[test]
     fn submenu_registers_all_actions() {
@@ -168,12 +345,56 @@ mod tests {
         )
         .expect("build_device_submenu should succeed");
 
-        // Should register 6 actions: VolumeLock, VolumeLockNotify, UnmuteLock,
-        // UnmuteLockNotify, OpenProperties, OpenSettings
-        assert_eq!(map.len(), 6);
+        // Should register 10 actions: VolumeLock, VolumeLockNotify, UnmuteLock,
+        // UnmuteLockNotify, SetAsDefault, SetAsDefaultCommunication,
+        // SwitchAndLock, OpenProperties, OpenSettings, OpenEventHistory,
+        // plus SetTag for "No tag" and each preset
+        assert_eq!(map.len(), 10 + 1 + TAG_PRESETS.len());
         assert!(submenu.text().contains("Speakers"));
     }
 
+    #[test]
+    fn submenu_disables_unsupported_locks() {
+        let mut device = MockDevice::new("dev1", "HDMI Output", true);
+        device.capabilities.mute = false;
+        let state = PersistentState::default();
+        let mut map = MenuIdMap::new();
+
+        let submenu = build_device_submenu(&device, DeviceType::Output, None, &state, &mut map)
+            .expect("should succeed");
+
+        let items = submenu.items();
+        let volume_lock = items[0].as_check_menuitem().expect("volume lock item");
+        let unmute_lock = items[1].as_check_menuitem().expect("unmute lock item");
+        assert!(volume_lock.is_enabled());
+        assert_eq!(volume_lock.text(), "Keep volume locked");
+        assert!(!unmute_lock.is_enabled());
+        assert_eq!(
+            unmute_lock.text(),
+            "Keep unmuted (not supported by this device)"
+        );
+    }
+
+    #[test]
+    fn submenu_keeps_unsupported_lock_that_is_on_enabled() {
+        let mut device = MockDevice::new("dev1", "HDMI Output", true);
+        device.capabilities.volume = false;
+        let mut state = PersistentState::default();
+        let mut settings =
+            crate::types::DeviceSettings::new("HDMI Output".into(), DeviceType::Output);
+        settings.volume_lock.is_locked = true;
+        state.devices.insert("dev1".into(), settings);
+        let mut map = MenuIdMap::new();
+
+        let submenu = build_device_submenu(&device, DeviceType::Output, None, &state, &mut map)
+            .expect("should succeed");
+
+        let items = submenu.items();
+        let volume_lock = items[0].as_check_menuitem().expect("volume lock item");
+        assert!(volume_lock.is_enabled());
+        assert!(volume_lock.is_checked());
+    }
+
     #[test]
     fn submenu_shows_default_indicator() {
         let device = MockDevice::new("dev1", "Speakers", true);
@@ -203,4 +424,54 @@ mod tests {
 
         assert!(!submenu.text().contains("☆"));
     }
+
+    #[test]
+    fn submenu_label_shows_tag() {
+        let device = MockDevice::new("dev1", "Headset", true);
+        let mut state = PersistentState::default();
+        state
+            .ensure_device_settings("dev1".into(), "Headset".into(), DeviceType::Output)
+            .tag = Some("🎧".into());
+        let mut map = MenuIdMap::new();
+
+        let submenu = build_device_submenu(&device, DeviceType::Output, None, &state, &mut map)
+            .expect("should succeed");
+
+        assert!(submenu.text().starts_with("🎧 Headset"));
+    }
+
+    #[test]
+    fn disconnected_known_devices_are_listed_greyed_out() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("dev1", "Speakers", true)]);
+        let mut state = PersistentState::default();
+        state
+            .ensure_device_settings("dock".into(), "Office Dock".into(), DeviceType::Output)
+            .tag = Some("🖥️".into());
+        state.ensure_device_settings("mic".into(), "Microphone".into(), DeviceType::Input);
+        let heading = MenuItem::new("Output devices", false, None);
+        let tray_menu = Menu::new();
+        let mut map = MenuIdMap::new();
+
+        append_device_list_to_menu(
+            &tray_menu,
+            &heading,
+            DeviceType::Output,
+            &backend,
+            &state,
+            &mut map,
+        )
+        .expect("should succeed");
+
+        let items = tray_menu.items();
+        assert!(items[1].as_submenu().is_some());
+        let dock = items[2].as_menuitem().expect("disconnected device item");
+        assert_eq!(dock.text(), "🖥️ Office Dock (disconnected)");
+        assert!(!dock.is_enabled());
+        assert!(
+            items
+                .iter()
+                .filter_map(|item| item.as_menuitem())
+                .all(|item| !item.text().contains("Microphone"))
+        );
+    }
 }
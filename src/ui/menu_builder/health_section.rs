@@ -0,0 +1,120 @@
+use crate::health::{HealthSnapshot, format_elapsed};
+use std::time::Instant;
+use tray_icon::menu::{Menu, MenuItem, Submenu};
+
+/// Keeps the last error line from stretching the menu too wide.
+const MAX_ERROR_LABEL_CHARS: usize = 60;
+
+pub(super) fn append_health_submenu(
+    tray_menu: &Menu,
+    health: &HealthSnapshot,
+) -> anyhow::Result<()> {
+    let now = Instant::now();
+    let title = if health.is_healthy(now) {
+        "Status: OK"
+    } else {
+        "Status: Needs attention"
+    };
+    let submenu = Submenu::new(title, true);
+    for line in health_lines(health, now) {
+        submenu.append(&MenuItem::new(line, false, None))?;
+    }
+    tray_menu.append(&submenu)?;
+    Ok(())
+}
+
+fn health_lines(health: &HealthSnapshot, now: Instant) -> Vec<String> {
+    let last_enforcement = match health.last_enforcement {
+        Some(at) => format_elapsed(now.saturating_duration_since(at)),
+        None => "never".to_string(),
+    };
+    let last_error = match &health.last_error {
+        Some((at, message)) => {
+            let mut label: String = message.chars().take(MAX_ERROR_LABEL_CHARS).collect();
+            if label.len() < message.len() {
+                label.push('…');
+            }
+            format!(
+                "Last error ({}): {label}",
+                format_elapsed(now.saturating_duration_since(*at))
+            )
+        }
+        None => "Last error: none".to_string(),
+    };
+    let mut lines = vec![
+        format!(
+            "Watching {} of {} locked devices",
+            health.watched_devices, health.locked_devices
+        ),
+        format!("Last enforcement: {last_enforcement}"),
+        last_error,
+    ];
+    if health.volume_callbacks_stalled(now) {
+        lines.push("Volume change notifications stopped arriving".to_string());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn health_lines_without_errors() {
+        let now = Instant::now();
+        let health = HealthSnapshot {
+            watched_devices: 2,
+            locked_devices: 3,
+            last_enforcement: now.checked_sub(Duration::from_secs(5)),
+            last_error: None,
+            awaiting_volume_callback: None,
+        };
+        assert_eq!(
+            health_lines(&health, now),
+            [
+                "Watching 2 of 3 locked devices",
+                "Last enforcement: 5s ago",
+                "Last error: none",
+            ]
+        );
+    }
+
+    #[test]
+    fn health_lines_truncate_long_errors() {
+        let now = Instant::now();
+        let health = HealthSnapshot {
+            watched_devices: 0,
+            locked_devices: 0,
+            last_enforcement: None,
+            last_error: Some((now, "x".repeat(100))),
+            awaiting_volume_callback: None,
+        };
+        let lines = health_lines(&health, now);
+        assert_eq!(lines[1], "Last enforcement: never");
+        assert_eq!(
+            lines[2],
+            format!(
+                "Last error (just now): {}…",
+                "x".repeat(MAX_ERROR_LABEL_CHARS)
+            )
+        );
+    }
+
+    #[test]
+    fn health_lines_report_stalled_volume_callbacks() {
+        let now = Instant::now();
+        let health = HealthSnapshot {
+            watched_devices: 1,
+            locked_devices: 1,
+            last_enforcement: None,
+            last_error: None,
+            awaiting_volume_callback: now.checked_sub(Duration::from_secs(120)),
+        };
+        let lines = health_lines(&health, now);
+        assert_eq!(
+            lines.last().map(String::as_str),
+            Some("Volume change notifications stopped arriving")
+        );
+    }
+}
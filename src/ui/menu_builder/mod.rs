@@ -1,10 +1,12 @@
 mod device_section;
+mod health_section;
 mod priority_section;
 
 use super::{AppAction, DeviceAction, MenuAction, MenuItemInfo};
 use crate::audio::AudioBackend;
 use crate::config::PersistentState;
-use crate::types::{DeviceId, DeviceType, TemporaryPriorities, VolumePercent};
+use crate::health::HealthSnapshot;
+use crate::types::{DeviceId, DeviceType, TemporaryPriorities, VolumePercent, tagged_name};
 use crate::update::UpdateInfo;
 use std::collections::HashMap;
 use tray_icon::menu::{CheckMenuItem, Menu, MenuId, MenuItem, PredefinedMenuItem};
@@ -12,10 +14,15 @@ use tray_icon::menu::{CheckMenuItem, Menu, MenuId, MenuItem, PredefinedMenuItem}
 use super::MenuIdMap;
 
 use device_section::append_device_list_to_menu;
+use health_section::append_health_submenu;
 use priority_section::{append_priority_list_to_menu, append_temporary_priority_section};
 
+/// Suffix appended to devices that are known but not currently connected.
+const DISCONNECTED_SUFFIX: &str = " (disconnected)";
+
 pub struct DeviceDisplayInfo<'a> {
     pub name: &'a str,
+    pub tag: Option<&'a str>,
     pub volume_percent: VolumePercent,
     pub is_default: bool,
     pub is_locked: bool,
@@ -28,7 +35,8 @@ pub fn format_device_menu_label(info: &DeviceDisplayInfo) -> String {
     let muted_indicator = if info.is_muted { " 🚫" } else { "" };
     format!(
         "{}{default_indicator} · {}%{muted_indicator}{locked_indicator}",
-        info.name, info.volume_percent
+        tagged_name(info.tag, info.name),
+        info.volume_percent
     )
 }
 
@@ -105,6 +113,7 @@ pub struct MenuContext<'a, B: AudioBackend> {
     pub temporary_priorities: &'a TemporaryPriorities,
     pub auto_launch_enabled: bool,
     pub update_info: &'a Option<UpdateInfo>,
+    pub health: &'a HealthSnapshot,
 }
 
 pub fn rebuild_tray_menu(
@@ -173,7 +182,14 @@ pub fn rebuild_tray_menu(
         &mut map,
     )?;
 
-    append_footer_section(tray_menu, &mut map, ctx.update_info.as_ref(), items)?;
+    append_footer_section(
+        tray_menu,
+        &mut map,
+        ctx.update_info.as_ref(),
+        ctx.persistent_state.minimal_footprint,
+        ctx.health,
+        items,
+    )?;
 
     Ok(map)
 }
@@ -188,6 +204,17 @@ fn append_preferences_section(
     tray_menu.append(&MenuItem::new("Preferences", false, None))?;
 
     items.auto_launch_check.set_checked(auto_launch_enabled);
+    // An entry that already exists can still be turned off in minimal footprint mode
+    items
+        .auto_launch_check
+        .set_enabled(!persistent_state.minimal_footprint || auto_launch_enabled);
+    items
+        .auto_launch_check
+        .set_text(if persistent_state.minimal_footprint {
+            "Auto-launch on startup (off in minimal footprint mode)"
+        } else {
+            "Auto-launch on startup"
+        });
     map.insert(
         items.auto_launch_check.id().clone(),
         MenuItemInfo {
@@ -208,6 +235,43 @@ fn append_preferences_section(
         },
     );
     tray_menu.append(items.check_updates_on_launch)?;
+
+    let yield_item = CheckMenuItem::new(
+        "Pause priorities while other switchers run",
+        true,
+        persistent_state.yield_to_conflicting_apps,
+        None,
+    );
+    map.insert(
+        yield_item.id().clone(),
+        MenuItemInfo {
+            name: "Pause priorities while other switchers run".to_string(),
+            action: MenuAction::App(AppAction::ToggleYieldToConflictingApps),
+        },
+    );
+    tray_menu.append(&yield_item)?;
+
+    let eco_item = CheckMenuItem::new(
+        "Save power in battery saver",
+        true,
+        persistent_state.eco_mode,
+        None,
+    );
+    map.insert(
+        eco_item.id().clone(),
+        MenuItemInfo {
+            name: "Save power in battery saver".to_string(),
+            action: MenuAction::App(AppAction::ToggleEcoMode),
+        },
+    );
+    tray_menu.append(&eco_item)?;
+
+    append_action_item(
+        tray_menu,
+        map,
+        "Import from SoundSwitch...",
+        MenuAction::App(AppAction::ImportFromSoundSwitch),
+    )?;
     tray_menu.append(&PredefinedMenuItem::separator())?;
 
     Ok(())
@@ -217,10 +281,28 @@ fn append_footer_section(
     tray_menu: &Menu,
     map: &mut MenuIdMap,
     update_info: Option<&UpdateInfo>,
+    minimal_footprint: bool,
+    health: &HealthSnapshot,
     items: &TrayMenuItems,
 ) -> anyhow::Result<()> {
     tray_menu.append(&MenuItem::new("Troubleshooting", false, None))?;
 
+    append_health_submenu(tray_menu, health)?;
+
+    append_action_item(
+        tray_menu,
+        map,
+        "Restore all locks now",
+        MenuAction::App(AppAction::RestoreAllLocks),
+    )?;
+
+    append_action_item(
+        tray_menu,
+        map,
+        "Event history...",
+        MenuAction::App(AppAction::OpenEventHistory),
+    )?;
+
     append_action_item(
         tray_menu,
         map,
@@ -238,6 +320,11 @@ fn append_footer_section(
     )?;
 
     let (label, action) = match update_info {
+        // Updating downloads next to the executable, so only the release page is offered
+        Some(info) if minimal_footprint => (
+            format!("Get {} from the release page...", info.latest_version),
+            AppAction::PerformUpdate,
+        ),
         Some(info) => (
             format!("Update to {}...", info.latest_version),
             AppAction::PerformUpdate,
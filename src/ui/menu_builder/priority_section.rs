@@ -1,19 +1,36 @@
-use super::{lookup_device_name, register_menu_item};
+use super::{DISCONNECTED_SUFFIX, lookup_device_name, register_menu_item};
 use crate::audio::AudioBackend;
 use crate::config::PersistentState;
-use crate::types::{DeviceId, DeviceType, TemporaryPriorities};
+use crate::types::{DeviceId, DeviceType, TemporaryPriorities, tagged_name};
 use crate::ui::{DeviceAction, MenuAction, MenuIdMap, MenuItemInfo, PreferenceAction};
 use tray_icon::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
 
+struct PriorityItem<'a> {
+    device_id: &'a DeviceId,
+    device_name: &'a str,
+    tag: Option<&'a str>,
+    is_connected: bool,
+}
+
 fn build_priority_item_submenu(
     index: usize,
     list_len: usize,
-    device_id: &DeviceId,
-    device_name: &str,
+    item: &PriorityItem,
     device_type: DeviceType,
     map: &mut MenuIdMap,
 ) -> anyhow::Result<Submenu> {
-    let label = format!("{}. {}", index + 1, device_name);
+    let PriorityItem {
+        device_id,
+        device_name,
+        tag,
+        is_connected,
+    } = *item;
+    let suffix = if is_connected {
+        ""
+    } else {
+        DISCONNECTED_SUFFIX
+    };
+    let label = format!("{}. {}{suffix}", index + 1, tagged_name(tag, device_name));
     let submenu = Submenu::new(&label, true);
 
     let move_items: [(&str, bool, DeviceAction); 4] = [
@@ -45,6 +62,23 @@ fn build_priority_item_submenu(
         );
         submenu.append(&item)?;
     }
+
+    if list_len > 1 {
+        let position_submenu = Submenu::new("Move to position", true);
+        for position in 0..list_len {
+            let item = MenuItem::new((position + 1).to_string(), position != index, None);
+            register_menu_item(
+                map,
+                item.id().clone(),
+                DeviceAction::MovePriorityToPosition(position),
+                device_id,
+                device_name,
+                device_type,
+            );
+            position_submenu.append(&item)?;
+        }
+        submenu.append(&position_submenu)?;
+    }
     submenu.append(&PredefinedMenuItem::separator())?;
 
     let remove_item = MenuItem::new("Remove device", true, None);
@@ -58,6 +92,19 @@ fn build_priority_item_submenu(
     );
     submenu.append(&remove_item)?;
 
+    if !is_connected {
+        let forget_item = MenuItem::new("Forget device", true, None);
+        register_menu_item(
+            map,
+            forget_item.id().clone(),
+            DeviceAction::ForgetDevice,
+            device_id,
+            device_name,
+            device_type,
+        );
+        submenu.append(&forget_item)?;
+    }
+
     Ok(submenu)
 }
 
@@ -86,11 +133,19 @@ pub fn append_priority_list_to_menu(
 
     for (index, device_id) in priority_list.iter().enumerate() {
         let device_name = lookup_device_name(device_id, persistent_state, backend);
+        let is_connected = available_devices.iter().any(|(id, _)| id == device_id);
+        let tag = persistent_state
+            .device_settings(device_id)
+            .and_then(|settings| settings.tag.as_deref());
         let submenu = build_priority_item_submenu(
             index,
             priority_list.len(),
-            device_id,
-            &device_name,
+            &PriorityItem {
+                device_id,
+                device_name: &device_name,
+                tag,
+                is_connected,
+            },
             device_type,
             map,
         )?;
@@ -102,7 +157,25 @@ pub fn append_priority_list_to_menu(
         .filter(|(id, _)| !priority_list.contains(id))
         .collect();
 
-    let add_device_submenu = Submenu::new("Add device", !devices_to_add.is_empty());
+    // Devices remembered from earlier sessions can be added ahead of time, so
+    // they take effect as soon as they are connected.
+    let mut known_devices_to_add: Vec<_> = persistent_state
+        .devices_iter()
+        .filter(|(id, settings)| {
+            settings.device_type == device_type
+                && !priority_list.contains(*id)
+                && !available_devices
+                    .iter()
+                    .any(|(available_id, _)| available_id == *id)
+        })
+        .map(|(id, settings)| (id, settings.name.as_str()))
+        .collect();
+    known_devices_to_add.sort_by_key(|(_, name)| *name);
+
+    let add_device_submenu = Submenu::new(
+        "Add device",
+        !devices_to_add.is_empty() || !known_devices_to_add.is_empty(),
+    );
     for (id, name) in devices_to_add {
         let item = MenuItem::new(name, true, None);
         register_menu_item(
@@ -115,6 +188,22 @@ pub fn append_priority_list_to_menu(
         );
         add_device_submenu.append(&item)?;
     }
+    if !known_devices_to_add.is_empty() {
+        add_device_submenu.append(&PredefinedMenuItem::separator())?;
+        add_device_submenu.append(&MenuItem::new("Disconnected", false, None))?;
+    }
+    for (id, name) in known_devices_to_add {
+        let item = MenuItem::new(format!("{name}{DISCONNECTED_SUFFIX}"), true, None);
+        register_menu_item(
+            map,
+            item.id().clone(),
+            DeviceAction::AddToPriority,
+            id,
+            name,
+            device_type,
+        );
+        add_device_submenu.append(&item)?;
+    }
     tray_menu.append(&add_device_submenu)?;
 
     let notify_on_restore = persistent_state.notify_on_priority_restore(device_type);
@@ -226,6 +315,7 @@ mod tests {
 
     use super::*;
     use crate::audio::tests::{MockAudioBackend, MockDevice};
+    use crate::types::DeviceSettings;
 
     #[test]
     fn registers_add_device_actions() {
@@ -287,4 +377,63 @@ mod tests {
         // With empty priority list: AddToPriority for dev1 (1) + notify (1) + switch comm (1) = 3
         assert_eq!(map.len(), 3);
     }
+
+    #[test]
+    fn known_disconnected_devices_can_be_added() {
+        let backend = MockAudioBackend::new(vec![MockDevice::new("dev1", "Speakers", true)]);
+        let mut state = PersistentState::default();
+        state.devices.insert(
+            "dock".into(),
+            DeviceSettings::new("Office Dock".to_string(), DeviceType::Output),
+        );
+        state.devices.insert(
+            "mic".into(),
+            DeviceSettings::new("Microphone".to_string(), DeviceType::Input),
+        );
+
+        let tray_menu = Menu::new();
+        let mut map = MenuIdMap::new();
+
+        append_priority_list_to_menu(
+            &tray_menu,
+            DeviceType::Output,
+            &backend,
+            &state,
+            None,
+            &mut map,
+        )
+        .expect("should succeed");
+
+        let added_ids: Vec<_> = map
+            .values()
+            .filter_map(|info| match &info.action {
+                MenuAction::Device {
+                    device_id,
+                    action: DeviceAction::AddToPriority,
+                    ..
+                } => Some(device_id.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(added_ids.len(), 2);
+        assert!(added_ids.contains(&"dev1".to_string()));
+        assert!(added_ids.contains(&"dock".to_string()));
+
+        let add_device = tray_menu
+            .items()
+            .into_iter()
+            .find_map(|item| {
+                item.as_submenu()
+                    .filter(|s| s.text() == "Add device")
+                    .cloned()
+            })
+            .expect("add device submenu");
+        let items = add_device.items();
+        let heading = items[2].as_menuitem().expect("disconnected heading");
+        assert_eq!(heading.text(), "Disconnected");
+        assert!(!heading.is_enabled());
+        let dock = items[3].as_menuitem().expect("disconnected device");
+        assert_eq!(dock.text(), "Office Dock (disconnected)");
+        assert!(dock.is_enabled());
+    }
 }
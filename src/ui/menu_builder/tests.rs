@@ -6,6 +6,7 @@ use super::{DeviceDisplayInfo, VolumePercent, format_device_menu_label};
 fn to_label_basic() {
     let label = format_device_menu_label(&DeviceDisplayInfo {
         name: "Speakers",
+        tag: None,
         volume_percent: VolumePercent::from(50.0),
         is_default: false,
         is_locked: false,
@@ -18,6 +19,7 @@ fn to_label_basic() {
 fn to_label_default_device() {
     let label = format_device_menu_label(&DeviceDisplayInfo {
         name: "Speakers",
+        tag: None,
         volume_percent: VolumePercent::from(75.0),
         is_default: true,
         is_locked: false,
@@ -30,6 +32,7 @@ fn to_label_default_device() {
 fn to_label_locked() {
     let label = format_device_menu_label(&DeviceDisplayInfo {
         name: "Speakers",
+        tag: None,
         volume_percent: VolumePercent::from(100.0),
         is_default: false,
         is_locked: true,
@@ -42,6 +45,7 @@ fn to_label_locked() {
 fn to_label_muted() {
     let label = format_device_menu_label(&DeviceDisplayInfo {
         name: "Mic",
+        tag: None,
         volume_percent: VolumePercent::from(0.0),
         is_default: false,
         is_locked: false,
@@ -54,6 +58,7 @@ fn to_label_muted() {
 fn to_label_all_indicators() {
     let label = format_device_menu_label(&DeviceDisplayInfo {
         name: "Headset",
+        tag: None,
         volume_percent: VolumePercent::from(42.0),
         is_default: true,
         is_locked: true,
@@ -61,3 +66,16 @@ fn to_label_all_indicators() {
     });
     assert_eq!(label, "Headset · ☆ · 42% 🚫 · 🔒");
 }
+
+#[test]
+fn to_label_tagged() {
+    let label = format_device_menu_label(&DeviceDisplayInfo {
+        name: "Headset",
+        tag: Some("🎧"),
+        volume_percent: VolumePercent::from(80.0),
+        is_default: false,
+        is_locked: false,
+        is_muted: false,
+    });
+    assert_eq!(label, "🎧 Headset · 80%");
+}
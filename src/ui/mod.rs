@@ -1,10 +1,12 @@
 mod event_handler;
 mod menu_builder;
+mod tooltip;
 
 pub use event_handler::{MenuEventContext, MenuEventResult, handle_menu_event};
 pub use menu_builder::{MenuContext, TrayMenuItems, rebuild_tray_menu};
+pub use tooltip::{tray_tooltip, waiting_tooltip};
 
-use crate::types::{DeviceId, DeviceType};
+use crate::types::{DeviceId, DeviceType, VolumePercent};
 use std::collections::HashMap;
 use tray_icon::menu::{Menu, MenuId, MenuItemKind};
 
@@ -20,9 +22,18 @@ pub enum DeviceAction {
     MovePriorityDown,
     MovePriorityToTop,
     MovePriorityToBottom,
+    /// Moves the entry to the given zero-based position in the priority list.
+    MovePriorityToPosition(usize),
     SetTemporaryPriority,
+    ForgetDevice,
+    SetAsDefault,
+    SetAsDefaultCommunication,
+    SwitchAndLock,
     OpenProperties,
     OpenSettings,
+    OpenEventHistory,
+    /// Sets the device's tag, or clears it with `None`.
+    SetTag(Option<String>),
 }
 
 #[derive(Debug)]
@@ -30,6 +41,16 @@ pub enum PreferenceAction {
     PriorityRestoreNotify,
     SwitchCommunicationDevice,
     OpenDevicesList,
+    ApplyToAllDevices(BulkSetting),
+}
+
+/// A setting that can be applied to every active device of a type at once.
+#[derive(Debug, Clone, Copy)]
+pub enum BulkSetting {
+    /// Locks the volume at the given level, or at each device's current level if `None`.
+    LockVolume(Option<VolumePercent>),
+    KeepUnmuted,
+    EnableNotifications,
 }
 
 #[derive(Debug)]
@@ -42,6 +63,18 @@ pub enum AppAction {
     OpenAppDirectory,
     ToggleAutoLaunch,
     ToggleCheckUpdatesOnLaunch,
+    /// Pauses priority enforcement while a known conflicting device switcher runs.
+    ToggleYieldToConflictingApps,
+    ToggleEcoMode,
+    /// Mutes every input device and keeps unmute locks from unmuting them.
+    ToggleMuteInputs,
+    /// Notifies the first time an unmute lock unmutes each input device.
+    ToggleAlertLiveMics,
+    ImportFromSoundSwitch,
+    /// Opens the event history window.
+    OpenEventHistory,
+    /// Re-applies every volume lock, unmute lock and priority list right away.
+    RestoreAllLocks,
 }
 
 #[derive(Debug)]
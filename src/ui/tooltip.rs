@@ -0,0 +1,106 @@
+use crate::consts::{APP_NAME, CURRENT_VERSION};
+use crate::types::VolumePercent;
+
+/// Windows cuts tray tooltips off at 127 characters.
+const MAX_TOOLTIP_CHARS: usize = 127;
+
+/// Room left for the line listing inputs kept unmuted, so the level still fits.
+const MAX_INPUTS_LINE_CHARS: usize = 48;
+
+/// Builds the tray tooltip, showing the default output's level when known and
+/// the inputs an unmute lock keeps live.
+pub fn tray_tooltip(
+    default_output: Option<(&str, VolumePercent, bool)>,
+    unmuted_inputs: &[&str],
+) -> String {
+    let header = format!("{APP_NAME} v{CURRENT_VERSION}");
+    let inputs_line = (!unmuted_inputs.is_empty()).then(|| {
+        shorten(
+            &format!("Mic kept unmuted: {}", unmuted_inputs.join(", ")),
+            MAX_INPUTS_LINE_CHARS,
+        )
+    });
+    let inputs_suffix = inputs_line
+        .map(|line| format!("\n{line}"))
+        .unwrap_or_default();
+    let Some((name, volume, muted)) = default_output else {
+        return format!("{header}{inputs_suffix}");
+    };
+    let level = if muted {
+        format!("muted ({volume}%)")
+    } else {
+        format!("{volume}%")
+    };
+    // Shorten the device name rather than the level, which is what the user hovers for
+    let budget = MAX_TOOLTIP_CHARS.saturating_sub(
+        header.chars().count() + level.chars().count() + inputs_suffix.chars().count() + 3,
+    );
+    let name = shorten(name, budget);
+    format!("{header}\n{name}: {level}{inputs_suffix}")
+}
+
+/// Builds the tray tooltip shown while the audio service is not up yet.
+pub fn waiting_tooltip() -> String {
+    format!("{APP_NAME} v{CURRENT_VERSION}\nWaiting for the audio service...")
+}
+
+/// Cuts `text` down to `max` characters, ending it with an ellipsis if it was cut.
+fn shorten(text: &str, max: usize) -> String {
+    if text.chars().count() > max {
+        let mut shortened: String = text.chars().take(max.saturating_sub(1)).collect();
+        shortened.push('…');
+        shortened
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tooltip_without_default_output_is_header_only() {
+        assert_eq!(
+            tray_tooltip(None, &[]),
+            format!("{APP_NAME} v{CURRENT_VERSION}")
+        );
+    }
+
+    #[test]
+    fn tooltip_shows_level_and_mute() {
+        let volume = VolumePercent::from(42.0);
+        assert!(tray_tooltip(Some(("Speakers", volume, false)), &[]).ends_with("\nSpeakers: 42%"));
+        assert!(
+            tray_tooltip(Some(("Speakers", volume, true)), &[])
+                .ends_with("\nSpeakers: muted (42%)")
+        );
+    }
+
+    #[test]
+    fn tooltip_shortens_long_device_names() {
+        let name = "Speakers ".repeat(30);
+        let tooltip = tray_tooltip(Some((&name, VolumePercent::from(100.0), true)), &[]);
+        assert!(tooltip.chars().count() <= MAX_TOOLTIP_CHARS);
+        assert!(tooltip.ends_with("…: muted (100%)"));
+    }
+
+    #[test]
+    fn tooltip_lists_inputs_kept_unmuted_last() {
+        let tooltip = tray_tooltip(
+            Some(("Speakers", VolumePercent::from(42.0), false)),
+            &["Microphone", "Headset"],
+        );
+        assert!(tooltip.ends_with("\nSpeakers: 42%\nMic kept unmuted: Microphone, Headset"));
+    }
+
+    #[test]
+    fn tooltip_keeps_level_with_many_inputs() {
+        let names = ["Microphone Array (Realtek High Definition Audio)"; 4];
+        let output = "Speakers ".repeat(30);
+        let tooltip = tray_tooltip(Some((&output, VolumePercent::from(100.0), false)), &names);
+        assert!(tooltip.chars().count() <= MAX_TOOLTIP_CHARS);
+        assert!(tooltip.contains("…: 100%\nMic kept unmuted: "));
+        assert!(tooltip.ends_with('…'));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::consts::{CURRENT_VERSION, GITHUB_RELEASE_ASSET, GITHUB_REPO_URL};
-use crate::notification::log_and_notify_error;
-use crate::platform::{NotificationDuration, send_notification};
+use crate::notification::Notifier;
+use crate::platform::NotificationDuration;
 use crate::utils::get_executable_path_str;
 use anyhow::Context;
 use semver::Version;
@@ -12,7 +12,7 @@ use ureq::config::Config;
 use ureq::tls::{RootCerts, TlsConfig, TlsProvider};
 use ureq::{Agent, ResponseExt};
 
-fn create_agent() -> Agent {
+pub(crate) fn create_agent() -> Agent {
     let config = Config::builder()
         .tls_config(
             TlsConfig::builder()
@@ -78,40 +78,38 @@ fn fetch_update_info() -> anyhow::Result<Option<UpdateInfo>> {
 /// If `manual_request` is false, only logs errors without notifying.
 /// Returns `Ok(Some(info))` when an update is available, `Ok(None)` when up to date,
 /// or `Err` when the check itself failed.
-pub fn check_for_update(manual_request: bool) -> anyhow::Result<Option<UpdateInfo>> {
+pub fn check_for_update(
+    manual_request: bool,
+    notifier: &dyn Notifier,
+) -> anyhow::Result<Option<UpdateInfo>> {
     match fetch_update_info() {
         Ok(Some(info)) => {
             log::info!("Update available: v{}", info.latest_version);
-            if manual_request
-                && let Err(e) = send_notification(
+            if manual_request {
+                notifier.notify_or_log(
                     "Update Available",
                     &format!(
                         "Version {} is available. Click 'Update' in the menu to install.",
                         info.latest_version
                     ),
                     NotificationDuration::Long,
-                )
-            {
-                log::error!("Failed to send update notification: {e:#}");
+                );
             }
             Ok(Some(info))
         }
         Ok(None) => {
             log::info!("No updates available");
-            if manual_request
-                && let Err(e) = send_notification(
+            if manual_request {
+                notifier.respond(
                     "No Updates Available",
                     "You are running the latest version of Volume Locker.",
-                    NotificationDuration::Short,
-                )
-            {
-                log::error!("Failed to send no-update notification: {e:#}");
+                );
             }
             Ok(None)
         }
         Err(e) => {
             if manual_request {
-                log_and_notify_error(
+                notifier.log_and_notify_error(
                     "Update Check Failed",
                     &format!("Failed to check for updates: {e:#}"),
                 );
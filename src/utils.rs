@@ -1,5 +1,6 @@
 use anyhow::Context;
 use std::path::PathBuf;
+use std::time::Duration;
 
 pub fn get_executable_path() -> anyhow::Result<PathBuf> {
     let exe_path =
@@ -29,3 +30,47 @@ pub fn open_path(path: &std::path::Path) -> anyhow::Result<()> {
 pub fn open_url(url: &str) -> anyhow::Result<()> {
     open::that_detached(url).context("failed to open URL")
 }
+
+/// Delays between retries of a failing operation, doubling after each failure
+/// up to `max_delay`.
+pub struct Backoff {
+    delay: Duration,
+    max_delay: Duration,
+    failures: u32,
+}
+
+impl Backoff {
+    pub fn new(initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            delay: initial_delay,
+            max_delay,
+            failures: 0,
+        }
+    }
+
+    /// Records a failed attempt and returns how long to wait before the next one.
+    pub fn failed(&mut self) -> Duration {
+        let delay = self.delay;
+        self.delay = (delay * 2).min(self.max_delay);
+        self.failures += 1;
+        delay
+    }
+
+    /// How many attempts failed so far.
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_max_delay() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(8));
+        let delays: Vec<u64> = (0..6).map(|_| backoff.failed().as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 8, 8]);
+        assert_eq!(backoff.failures(), 6);
+    }
+}